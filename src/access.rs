@@ -0,0 +1,153 @@
+use std::ffi::CString;
+use std::fs::{self, File, Permissions};
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::{fchown, lchown, PermissionsExt};
+use std::path::Path;
+
+use log::debug;
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::tool::ToolSpec;
+
+// Permissions and group owner of the files and directories which are created on tool installation. The process umask
+// is shared by all the tools, so the configured umask is applied by explicit chmod instead.
+#[derive(Clone, Copy, Default)]
+pub struct FileAccess {
+    umask: Option<u32>,
+    gid: Option<u32>,
+}
+
+impl FileAccess {
+    pub fn new(spec: &ToolSpec) -> GenericResult<FileAccess> {
+        Ok(FileAccess {
+            umask: spec.umask.as_deref().map(parse_umask).transpose()?,
+            gid: spec.group.as_deref().map(get_gid).transpose()?,
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.umask.is_none() && self.gid.is_none()
+    }
+
+    // Creates the directory with all its missing parents applying the permissions to the created ones
+    pub fn create_dir_all(&self, path: &Path) -> EmptyResult {
+        if self.is_default() {
+            return Ok(fs::create_dir_all(path).map_err(|e| format!("Unable to create {path:?}: {e}"))?);
+        }
+
+        let mut missing = Vec::new();
+
+        for ancestor in path.ancestors().filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+            match fs::symlink_metadata(ancestor) {
+                Ok(_) => break,
+                Err(err) if err.kind() == ErrorKind::NotFound => missing.push(ancestor),
+                Err(err) => return Err!("Unable to stat {ancestor:?}: {err}"),
+            }
+        }
+
+        for path in missing.into_iter().rev() {
+            match fs::create_dir(path) {
+                Ok(()) => {},
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err!("Unable to create {path:?}: {err}"),
+            }
+
+            debug!("Created {path:?}.");
+            self.apply(path, 0o777)?;
+        }
+
+        Ok(())
+    }
+
+    // Applies the permissions to the created file which has been opened with the specified mode
+    pub fn apply_to_file(&self, file: &File, path: &Path, mode: u32) -> EmptyResult {
+        if let Some(umask) = self.umask {
+            file.set_permissions(Permissions::from_mode(mode & 0o7777 & !umask)).map_err(|e| format!(
+                "Unable to change {path:?} permissions: {e}"))?;
+        }
+
+        if let Some(gid) = self.gid {
+            fchown(file, None, Some(gid)).map_err(|e| format!("Unable to change {path:?} group: {e}"))?;
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, path: &Path, mode: u32) -> EmptyResult {
+        // Change the group first, since chown may reset setgid bit
+        if let Some(gid) = self.gid {
+            lchown(path, None, Some(gid)).map_err(|e| format!("Unable to change {path:?} group: {e}"))?;
+        }
+
+        if let Some(umask) = self.umask {
+            fs::set_permissions(path, Permissions::from_mode(mode & !umask)).map_err(|e| format!(
+                "Unable to change {path:?} permissions: {e}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn parse_umask(umask: &str) -> GenericResult<u32> {
+    match u32::from_str_radix(umask, 8) {
+        Ok(value) if !umask.is_empty() && value <= 0o777 => Ok(value),
+        _ => Err!("Invalid umask: {umask:?}"),
+    }
+}
+
+// Resolves group name or numeric group ID
+fn get_gid(group: &str) -> GenericResult<u32> {
+    if let Ok(gid) = group.parse() {
+        return Ok(gid);
+    }
+
+    let name = CString::new(group).map_err(|_| format!("Invalid group name: {group:?}"))?;
+
+    let mut buf = vec![0; 4096];
+    let mut entry: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+
+    loop {
+        let code = unsafe {
+            libc::getgrnam_r(name.as_ptr(), &mut entry, buf.as_mut_ptr(), buf.len(), &mut result)
+        };
+
+        match code {
+            0 if result.is_null() => return Err!("Unknown group: {group:?}"),
+            0 => return Ok(entry.gr_gid),
+            libc::ERANGE if buf.len() < 1024 * 1024 => buf.resize(buf.len() * 2, 0),
+            code => return Err!("Unable to resolve {group:?} group: {}", io::Error::from_raw_os_error(code)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn umask() {
+        assert_eq!(parse_umask("022").unwrap(), 0o022);
+        assert_eq!(parse_umask("7").unwrap(), 0o007);
+
+        for umask in ["", "0888", "1000", "-1"] {
+            assert!(parse_umask(umask).is_err(), "{umask}");
+        }
+    }
+
+    #[test]
+    fn create_dir_all() {
+        let root = std::env::temp_dir().join(format!("binup-access-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let access = FileAccess {umask: Some(0o027), gid: None};
+        access.create_dir_all(&root.join("a/b")).unwrap();
+        access.create_dir_all(&root.join("a/b")).unwrap();
+
+        for path in [root.clone(), root.join("a"), root.join("a/b")] {
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o7777, 0o750, "{path:?}");
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
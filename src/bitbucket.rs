@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use http::header;
+use log::{debug, trace, warn};
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder};
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::core::GenericResult;
+use crate::network::NetworkConfig;
+use crate::project::Project;
+use crate::provider::{ReleaseProvider, Repository};
+use crate::release::{Asset, Release};
+use crate::tls::TlsConfig;
+use crate::tool::ReleaseStrategy;
+use crate::util;
+use crate::version::{self, ReleaseVersion};
+
+pub const HOST: &str = "bitbucket.org";
+const API_URL: &str = "https://api.bitbucket.org/2.0/repositories";
+
+#[derive(Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BitbucketConfig {
+    pub username: Option<String>,
+    // App password which is required for private repositories
+    app_password: Option<String>,
+}
+
+impl BitbucketConfig {
+    pub fn has_app_password(&self) -> bool {
+        self.app_password.is_some()
+    }
+}
+
+// Bitbucket has no releases, so repository downloads are grouped into releases by the versions in their file names
+pub struct Bitbucket {
+    client: Client,
+    credentials: Option<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct Page<T> {
+    values: Vec<T>,
+    next: Option<Url>,
+}
+
+#[derive(Deserialize)]
+struct DownloadModel {
+    name: String,
+    created_on: DateTime<Utc>,
+    links: LinksModel,
+}
+
+#[derive(Deserialize)]
+struct LinksModel {
+    #[serde(rename = "self")]
+    download: LinkModel,
+}
+
+#[derive(Deserialize)]
+struct LinkModel {
+    href: Url,
+}
+
+#[derive(Deserialize)]
+struct RepositoryModel {
+    #[serde(default)]
+    description: String,
+}
+
+impl Bitbucket {
+    pub fn new(config: &BitbucketConfig, tls: &TlsConfig, network: &NetworkConfig) -> GenericResult<Bitbucket> {
+        let credentials = match (config.username.as_ref(), config.app_password.as_ref()) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            (None, None) => None,
+            _ => return Err!("Both Bitbucket username and app password must be specified"),
+        };
+
+        let client = ClientBuilder::new().user_agent(util::USER_AGENT);
+        let client = network.configure_client(client);
+        let client = tls.configure_client(client)?.build()?;
+
+        Ok(Bitbucket {client, credentials})
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match self.credentials.as_ref() {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&self, url: Url) -> GenericResult<Option<T>> {
+        trace!("Sending GET {url}...");
+        let response = self.authorize(self.client.get(url)).send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(serde_json::from_slice(&response.bytes()?).map_err(|e| format!(
+                "Got an invalid response from Bitbucket: {e}"))?)),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN if self.credentials.is_none() => Err!(
+                "Bitbucket returned {}. Private repositories require username and app password", response.status()),
+            status => Err!("Bitbucket returned an error: {status}"),
+        }
+    }
+}
+
+impl ReleaseProvider for Bitbucket {
+    // Returns the downloads grouped into releases by version in order of preference. If tag is specified, returns only
+    // the release with this version. Release strategy is ignored: the highest version is always preferred.
+    fn get_releases(
+        &self, project: &Project, tag: Option<&str>, _strategy: ReleaseStrategy, max_pages: usize,
+    ) -> GenericResult<Vec<Release>> {
+        debug!("Getting {} downloads...", project.full_name());
+
+        let mut url = Url::parse(&format!("{API_URL}/{}/{}/downloads?pagelen=100", project.owner, project.name))?;
+        let mut releases: BTreeMap<Version, (String, Vec<Asset>)> = BTreeMap::new();
+
+        for page_number in 1.. {
+            let page: Page<DownloadModel> = self.get(url)?.ok_or("The project doesn't exist")?;
+            debug!("Got page #{page_number} of {} downloads ({} files).", project.full_name(), page.values.len());
+
+            for download in page.values {
+                let Some((version, tag)) = version::get_file_version(&download.name) else {
+                    trace!("Skipping {:?}: it has no version in its name.", download.name);
+                    continue;
+                };
+
+                releases.entry(version).or_insert_with(|| (tag, Vec::new())).1.push(Asset {
+                    name: download.name,
+                    time: download.created_on,
+                    url: download.links.download.href,
+                });
+            }
+
+            match page.next {
+                Some(next) if page_number < max_pages => url = next,
+                Some(_) => {
+                    warn!(concat!(
+                        "{} has more than {} pages of downloads. Only the first ones are checked ",
+                        "(see max_release_pages option)."
+                    ), project.full_name(), max_pages);
+                    break;
+                },
+                None => break,
+            }
+        }
+
+        let releases: Vec<Release> = releases.into_iter().rev()
+            .filter(|(version, (name, _))| tag.is_none_or(|tag| {
+                tag == name || ReleaseVersion::new(tag).to_string() == version.to_string()
+            }))
+            .map(|(_, (tag, assets))| Release::new(project.clone(), &tag, assets))
+            .collect();
+
+        if releases.is_empty() {
+            return match tag {
+                Some(tag) => Err!("The project has no downloads of {tag} version"),
+                None => Err!("The project has no versioned downloads"),
+            };
+        }
+
+        Ok(releases)
+    }
+
+    fn get_repository(&self, project: &Project) -> GenericResult<Repository> {
+        debug!("Getting {} repository info...", project.full_name());
+
+        let url = Url::parse(&format!("{API_URL}/{}/{}", project.owner, project.name))?;
+        let repository: RepositoryModel = self.get(url)?.ok_or("The project doesn't exist")?;
+
+        // Bitbucket has no repository archiving
+        Ok(Repository {
+            description: repository.description.trim().to_owned(),
+            archived: false,
+        })
+    }
+
+    // Bitbucket has no releases, so its repository downloads are used instead
+    fn get_changelog(&self, project: &Project) -> Option<Url> {
+        Url::parse(&format!("https://{HOST}/{}/{}/downloads/", project.owner, project.name)).ok()
+    }
+
+    // Download URLs redirect to a storage on another host, where the Authorization header is stripped by default
+    fn get_download_headers(&self, _project: &Project, _artifact: bool) -> BTreeMap<String, String> {
+        let authorization = self.authorize(self.client.get(API_URL)).build().ok().and_then(|request| {
+            let value = request.headers().get(header::AUTHORIZATION)?;
+            value.to_str().ok().map(ToOwned::to_owned)
+        });
+
+        authorization.into_iter().map(|value| (header::AUTHORIZATION.to_string(), value)).collect()
+    }
+}
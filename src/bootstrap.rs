@@ -0,0 +1,109 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use indoc::formatdoc;
+use log::{info, warn};
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::util;
+
+// Generates a shell script which provisions a new machine: installs the current binup version, writes the current
+// configuration file and installs all the tools from it.
+pub fn bootstrap(config: &Config, output: Option<&Path>) -> GenericResult<ExitCode> {
+    let Some(config_data) = config.get_source_data() else {
+        return Err!("The configuration file doesn't exist");
+    };
+
+    let config_data = String::from_utf8(config_data.to_vec()).map_err(|_| "The configuration file has an invalid encoding")?;
+    if config.github.has_token() || config.bitbucket.has_app_password() {
+        warn!("The configuration file contains access tokens, so keep the generated script private.");
+    }
+
+    let install_path = config.path.to_str()
+        .ok_or_else(|| format!("Invalid install path: {:?}", config.path))?
+        .replace("{name}", env!("CARGO_PKG_NAME"));
+
+    let script = generate_script(&install_path, &config_data);
+
+    match output {
+        Some(path) => {
+            // The script embeds the configuration file which may contain credentials, so it's created private
+            util::write_file_with_mode(path, script.as_bytes(), Some(0o700))?;
+            info!("Bootstrap script is written to {path:?}.");
+        },
+        None => {
+            io::stdout().write_all(script.as_bytes()).map_err(|e| format!(
+                "Failed to write the script: {e}"))?;
+        },
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn generate_script(install_path: &str, config_data: &str) -> String {
+    let name = env!("CARGO_PKG_NAME");
+    let version = env!("CARGO_PKG_VERSION");
+    let repository = env!("CARGO_PKG_REPOSITORY");
+
+    let home = shellexpand::tilde("~/").to_string();
+    let install_path = match install_path.strip_prefix(&home) {
+        Some(relative) => format!("\"$HOME\"/{}", util::shell_quote(relative)),
+        None => util::shell_quote(install_path),
+    };
+
+    let mut delimiter = "BINUP_CONFIG".to_owned();
+    while config_data.lines().any(|line| line == delimiter) {
+        delimiter.push('_');
+    }
+
+    let mut config_data = config_data.to_owned();
+    if !config_data.ends_with('\n') {
+        config_data.push('\n');
+    }
+
+    formatdoc!(r#"
+        #!/usr/bin/env bash
+        #
+        # Generated by {name} {version}: installs {name}, writes its configuration file and installs all the tools from it
+        #
+
+        set -euo pipefail
+
+        version={version}
+        install_path={install_path}
+        config_path="${{BINUP_CONFIG:-$HOME/.config/{name}/config.yaml}}"
+
+        case "$(uname -s)" in
+            Linux) os=linux;;
+            Darwin) os=macos;;
+            *) echo "Unsupported OS: $(uname -s)." >&2; exit 1;;
+        esac
+
+        case "$(uname -m)" in
+            x86_64|amd64) arch=x64;;
+            arm64|aarch64) arch=arm64;;
+            *) echo "Unsupported architecture: $(uname -m)." >&2; exit 1;;
+        esac
+
+        temp_dir="$(mktemp -d)"
+        trap 'rm -rf "$temp_dir"' EXIT
+
+        echo "Installing {name} $version to $install_path..."
+        curl -fsSL "{repository}/releases/download/v$version/{name}-$os-$arch-v$version.tar.bz2" | tar -xjf - -C "$temp_dir"
+        mkdir -p "$install_path"
+        install -m 755 "$temp_dir/{name}" "$install_path/{name}"
+
+        if [ -e "$config_path" ]; then
+            echo "$config_path already exists. Leaving it as is."
+        else
+            mkdir -p "$(dirname "$config_path")"
+            # The configuration file may contain access tokens, so it's created private
+            (umask 077 && cat > "$config_path") <<'{delimiter}'
+        {config_data}{delimiter}
+        fi
+
+        "$install_path/{name}" --config "$config_path" install
+    "#)
+}
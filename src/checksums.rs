@@ -0,0 +1,167 @@
+use log::{debug, warn};
+
+use crate::core::GenericResult;
+use crate::download::{self, DownloadOptions};
+use crate::release::{Asset, Release};
+use crate::tool::ChecksumPolicy;
+
+// Checksum files list all release assets, so they are small even for the releases with dozens of platforms
+const MAX_CHECKSUMS_SIZE: u64 = 1024 * 1024;
+
+const CHECKSUMS_EXTENSIONS: [&str; 3] = [".sha256", ".sha256sum", ".sha256.txt"];
+
+pub struct ExpectedChecksum {
+    pub sha256: String,
+    pub source: String,
+}
+
+// Looks for the asset checksum in the checksum files which are published beside it
+pub fn get_expected_checksum(
+    release: &Release, asset: &Asset, policy: ChecksumPolicy, options: &DownloadOptions,
+) -> GenericResult<Option<ExpectedChecksum>> {
+    if policy == ChecksumPolicy::Off {
+        return Ok(None);
+    }
+
+    let candidates = get_checksum_assets(release, asset);
+    if candidates.is_empty() {
+        if policy == ChecksumPolicy::Required {
+            return Err!("The release has no checksum file to verify {} with", asset.name);
+        }
+        debug!("The release has no checksum files.");
+        return Ok(None);
+    }
+
+    for (candidate, dedicated) in candidates {
+        debug!("Getting {} checksum from {}...", asset.name, candidate.name);
+
+        let contents = match download::fetch(&candidate.url, options, MAX_CHECKSUMS_SIZE) {
+            Ok(contents) => String::from_utf8_lossy(&contents).into_owned(),
+            Err(err) => {
+                let message = format!("Failed to download {}: {err}", candidate.url);
+                if policy == ChecksumPolicy::Required {
+                    return Err(message.into());
+                }
+                warn!("{message}.");
+                continue;
+            },
+        };
+
+        if let Some(sha256) = find_checksum(&contents, &asset.name, dedicated) {
+            return Ok(Some(ExpectedChecksum {sha256, source: candidate.name.clone()}));
+        }
+
+        debug!("{} doesn't contain {} SHA-256 checksum.", candidate.name, asset.name);
+    }
+
+    if policy == ChecksumPolicy::Required {
+        return Err!("{} isn't listed in the release checksum files", asset.name);
+    }
+
+    Ok(None)
+}
+
+// Returns the checksum files of the release: the ones dedicated to the asset first and then the common ones
+fn get_checksum_assets<'a>(release: &'a Release, asset: &Asset) -> Vec<(&'a Asset, bool)> {
+    let mut dedicated = Vec::new();
+    let mut common = Vec::new();
+
+    for candidate in &release.assets {
+        if let Some(extension) = candidate.name.strip_prefix(&asset.name) {
+            if CHECKSUMS_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+                dedicated.push((candidate, true));
+            }
+        } else if is_checksums_file(&candidate.name) {
+            common.push((candidate, false));
+        }
+    }
+
+    dedicated.extend(common);
+    dedicated
+}
+
+fn is_checksums_file(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let name = name.strip_suffix(".txt").unwrap_or(&name);
+
+    ["sha256sums", "sha256sum", "checksums", "checksum"].iter().any(|suffix| {
+        name.strip_suffix(suffix).is_some_and(|prefix| prefix.is_empty() || prefix.ends_with(['-', '_', '.']))
+    })
+}
+
+// Supports GNU (`<checksum>  <name>` or `<checksum> *<name>`) and BSD (`SHA256 (<name>) = <checksum>`) formats. A file
+// dedicated to the asset may contain only the checksum.
+fn find_checksum(contents: &str, asset_name: &str, dedicated: bool) -> Option<String> {
+    let is_sha256 = |value: &str| value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit());
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        let (checksum, name) = if let Some(entry) = line.strip_prefix("SHA256 (") {
+            let Some((name, checksum)) = entry.rsplit_once(") = ") else {
+                continue;
+            };
+            (checksum, Some(name))
+        } else {
+            let mut parts = line.split_whitespace();
+            let Some(checksum) = parts.next() else {
+                continue;
+            };
+            (checksum, parts.next().map(|name| name.trim_start_matches('*')))
+        };
+
+        let matches = match name {
+            Some(name) => name.rsplit('/').next() == Some(asset_name),
+            None => dedicated,
+        };
+
+        if matches && is_sha256(checksum) {
+            return Some(checksum.to_lowercase());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(name, expected,
+        case("checksums.txt", true),
+        case("sha256sums.txt", true),
+        case("SHA256SUMS", true),
+        case("tool_1.0.0_checksums.txt", true),
+        case("tool-1.0.0-SHA256SUMS", true),
+        case("tool-1.0.0-linux-amd64.tar.gz", false),
+        case("checksums.txt.sig", false),
+        case("nochecksums.txt", false),
+    )]
+    fn checksums_file(name: &str, expected: bool) {
+        assert_eq!(is_checksums_file(name), expected);
+    }
+
+    #[rstest(contents, dedicated, expected,
+        case(indoc!("
+            0000000000000000000000000000000000000000000000000000000000000000  tool-1.0.0-darwin-arm64.tar.gz
+            9A5E6B3F0A4AC3D8B4B5A42E6C3A8C0F3F1E6E2D8B7A7E5A41B2C1D0E9F8A7B6  tool-1.0.0-linux-amd64.tar.gz
+        "), false, Some("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6")),
+        case(
+            "9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6 *dist/tool-1.0.0-linux-amd64.tar.gz",
+            false, Some("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6")),
+        case(
+            "SHA256 (tool-1.0.0-linux-amd64.tar.gz) = 9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6",
+            false, Some("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6")),
+        case("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6\n",
+            true, Some("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6")),
+        case("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6\n", false, None),
+        case("9a5e6b3f  tool-1.0.0-linux-amd64.tar.gz", false, None),
+        case("9a5e6b3f0a4ac3d8b4b5a42e6c3a8c0f3f1e6e2d8b7a7e5a41b2c1d0e9f8a7b6  tool-1.0.0-linux-amd64.tar.gz.sbom",
+            false, None),
+    )]
+    fn checksum_lookup(contents: &str, dedicated: bool, expected: Option<&str>) {
+        assert_eq!(find_checksum(contents, "tool-1.0.0-linux-amd64.tar.gz", dedicated).as_deref(), expected);
+    }
+}
@@ -1,39 +1,108 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
 use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use clap_complete::engine::ArgValueCompleter;
 use const_format::formatcp;
 use log::Level;
 use url::Url;
+use validator::Validate;
 
+use crate::completion;
 use crate::core::GenericResult;
 use crate::matcher::Matcher;
 use crate::install::Mode;
-use crate::tool::ToolSpec;
+use crate::list::ListFormat;
+use crate::network::IpVersion;
+use crate::porcelain::Porcelain;
+use crate::report::ReportFormat;
+use crate::tool::{self, ArtifactSpec, ChangeKind, ChecksumPolicy, Layout, PostHook, ReleaseStrategy, ToolSpec};
+use crate::util::{self, PromptDefault};
 
 pub struct CliArgs {
     pub log_level: Level,
     pub config_path: PathBuf,
     pub custom_config: bool,
+    pub system: bool,
+    pub prompt_default: PromptDefault,
+    pub ip_version: Option<IpVersion>,
     pub action: Action,
 }
 
 #[allow(clippy::large_enum_variant)]
 pub enum Action {
     List {
+        format: ListFormat,
         full: bool,
+        probe: bool,
+        local: bool,
+        stale_after: Option<Duration>,
+        unused: Option<Duration>,
     },
     Install {
         mode: Mode,
         names: Vec<String>,
+        transactional: bool,
+        force_overwrite: bool,
+        timings: bool,
     },
     InstallFromSpec {
         name: Option<String>,
         spec: ToolSpec,
         force: bool,
+        force_overwrite: bool,
+        timings: bool,
+    },
+    InstallSelf {
+        force: bool,
+        force_overwrite: bool,
+        timings: bool,
+    },
+    Inspect {
+        name: String,
+        porcelain: Option<Porcelain>,
+    },
+    SuggestForks {
+        name: String,
+        inactive_for: Duration,
+        porcelain: Option<Porcelain>,
+    },
+    Notes {
+        name: String,
+        output: Option<PathBuf>,
     },
     Uninstall {
         names: Vec<String>,
-    }
+    },
+    CollectGarbage {
+        keep: usize,
+    },
+    Report {
+        format: ReportFormat,
+        probe: bool,
+        new_only: bool,
+    },
+    Verify {
+        names: Vec<String>,
+        paranoid: bool,
+    },
+    Bootstrap {
+        output: Option<PathBuf>,
+    },
+    ShowConfig {
+        effective: bool,
+    },
+    LintConfig {
+        fix: bool,
+    },
+    SignState,
+    InstallShims {
+        name: Option<String>,
+        version: Option<String>,
+    },
+    RestoreConfig,
 }
 
 macro_rules! long_about {
@@ -42,10 +111,14 @@ macro_rules! long_about {
     }}
 }
 
-pub fn parse_args() -> GenericResult<CliArgs> {
-    const DEFAULT_CONFIG_PATH: &str = formatcp!("~/.config/{}/config.yaml", env!("CARGO_PKG_NAME"));
+pub const DEFAULT_CONFIG_PATH: &str = formatcp!("~/.config/{}/config.yaml", env!("CARGO_PKG_NAME"));
+pub const SYSTEM_CONFIG_PATH: &str = formatcp!("/etc/{}/config.yaml", env!("CARGO_PKG_NAME"));
+
+// Commands which support --porcelain option
+const PORCELAIN_COMMANDS: [&str; 4] = ["list", "report", "inspect", "suggest-forks"];
 
-    let matches = Command::new(env!("CARGO_PKG_NAME"))
+pub fn build_cli() -> Command {
+    Command::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
 
@@ -59,17 +132,73 @@ pub fn parse_args() -> GenericResult<CliArgs> {
             .value_parser(value_parser!(PathBuf))
             .help(formatcp!("Configuration file path [default: {}]", DEFAULT_CONFIG_PATH)))
 
+        .arg(Arg::new("system").short('S').long("system")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("config")
+            .help(formatcp!("Manage system-wide tools from {}", SYSTEM_CONFIG_PATH)))
+
         .arg(Arg::new("verbose")
             .short('v').long("verbose")
             .action(ArgAction::Count)
             .help("Set verbosity level"))
 
+        .arg(Arg::new("quiet")
+            .short('q').long("quiet")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("verbose")
+            .help("Show only errors"))
+
+        .arg(Arg::new("log_level").long("log-level")
+            .value_name("LEVEL")
+            .value_parser(["error", "warn", "info", "debug", "trace"])
+            .conflicts_with_all(["verbose", "quiet"])
+            .help("Set log level explicitly"))
+
+        .arg(Arg::new("prompt_default").long("prompt-default")
+            .value_name("ANSWER")
+            .value_parser(PromptDefault::VALUES)
+            .help("Answer to confirmation prompts in non-interactive environment (CI or no TTY) [default: fail]"))
+
+        .arg(Arg::new("ipv4").short('4').long("ipv4")
+            .action(ArgAction::SetTrue)
+            .help("Connect to IPv4 addresses only"))
+
+        .arg(Arg::new("ipv6").short('6').long("ipv6")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("ipv4")
+            .help("Connect to IPv6 addresses only"))
+
+        .arg(Arg::new("porcelain").long("porcelain")
+            .value_name("VERSION")
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("v1")
+            .value_parser(Porcelain::VALUES)
+            .help("Produce stable machine-readable output: tab-separated records without tables and colors"))
+
         .subcommand(Command::new("list").visible_alias("l")
             .about("List all configured tools")
             .args([
                 Arg::new("full").short('f').long("full")
-                    .help("Show full information including changelog URL")
+                    .help("Show full information including project description, changelog URL, selected release asset and binary")
+                    .action(ArgAction::SetTrue),
+                Arg::new("format").long("format")
+                    .value_name("FORMAT")
+                    .value_parser(ListFormat::VALUES)
+                    .help("Output format (env and tsv formats are intended for scripts) [default: table]"),
+                Arg::new("probe").long("probe")
+                    .help("Determine installed versions by running the binaries instead of using the state file")
                     .action(ArgAction::SetTrue),
+                Arg::new("local").long("local")
+                    .help("Don't access GitHub and show the last known latest releases")
+                    .action(ArgAction::SetTrue),
+                Arg::new("stale_after").long("stale-after")
+                    .value_name("DURATION")
+                    .help("Mark tools installed from releases older than the specified duration (like 30d or 2w) as stale"),
+                Arg::new("unused").long("unused")
+                    .value_name("DURATION")
+                    .conflicts_with("probe")
+                    .help("Mark tools which haven't been run for the specified duration (like 90d) as unused"),
             ]))
 
         .subcommand(Command::new("install").visible_alias("i")
@@ -89,44 +218,228 @@ pub fn parse_args() -> GenericResult<CliArgs> {
                     .action(ArgAction::SetTrue)
                     .help("Force installation even if tool is already installed"),
 
+                Arg::new("reuse_cache").long("reuse-cache")
+                    .action(ArgAction::SetTrue)
+                    .requires("force")
+                    .conflicts_with("project")
+                    .help("Reinstall from the cached release archive if the latest release has the same one"),
+
+                Arg::new("force_overwrite").long("force-overwrite")
+                    .action(ArgAction::SetTrue)
+                    .help("Replace existing binary which hasn't been installed by binup"),
+
+                Arg::new("timings").long("timings")
+                    .action(ArgAction::SetTrue)
+                    .help("Print a breakdown of time spent in each phase of the run per tool"),
+
+                Arg::new("self").long("self")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["name", "project", "reuse_cache"])
+                    .help("Add binup itself to the configuration file, so it's upgraded along with the other tools"),
+
                 Arg::new("project").short('p').long("project")
                     .value_name("NAME")
-                    .help("GitHub project to get the release from"),
+                    .action(ArgAction::Append)
+                    .add(ArgValueCompleter::new(completion::complete_project))
+                    .help("GitHub (owner/name), Gitea/Forgejo or Bitbucket (host/owner/name) project or S3 bucket (s3://bucket/prefix) to get the release from (may be specified multiple times to try them in order)"),
 
                 Arg::new("changelog").short('c').long("changelog")
                     .value_name("URL")
                     .requires("project")
                     .help("Project changelog URL"),
 
+                Arg::new("tag").short('t').long("tag")
+                    .value_name("TAG")
+                    .requires("project")
+                    .help("Install release with the specified (possibly moving like nightly) tag instead of the latest release"),
+
+                Arg::new("strategy").long("strategy")
+                    .value_name("STRATEGY")
+                    .requires("project")
+                    .value_parser(ReleaseStrategy::VALUES)
+                    .help("Install the release marked as latest by GitHub or the release with the highest version"),
+
+                Arg::new("max_release_pages").long("max-release-pages")
+                    .value_name("COUNT")
+                    .requires("project")
+                    .value_parser(value_parser!(usize))
+                    .help(formatcp!(
+                        "Maximum number of release list pages to check for highest-version strategy [default: {}]",
+                        tool::DEFAULT_MAX_RELEASE_PAGES)),
+
+                Arg::new("artifact").long("artifact")
+                    .conflicts_with("tag")
+                    .value_name("WORKFLOW:BRANCH:NAME")
+                    .requires("project")
+                    .help("Install GitHub Actions workflow artifact from the latest successful workflow run instead of release"),
+
                 Arg::new("release_matcher").short('r').long("release-matcher")
                     .value_name("PATTERN")
                     .requires("project")
                     .help("Release archive pattern"),
 
+                Arg::new("variant").long("variant")
+                    .value_name("VARIANT")
+                    .requires("project")
+                    .conflicts_with("release_matcher")
+                    .help("Variant build to choose when release matcher isn't specified (like full, lite or gnu)"),
+
                 Arg::new("binary_matcher").short('b').long("binary-matcher")
                     .value_name("PATTERN")
                     .requires("project")
                     .help("Binary path to look for inside the release archive"),
 
+                Arg::new("binary_exclude").short('x').long("binary-exclude")
+                    .value_name("PATTERN")
+                    .action(ArgAction::Append)
+                    .requires("project")
+                    .help("Executables to ignore when looking for the binary (overrides the default rules)"),
+
+                Arg::new("subdir").long("subdir")
+                    .value_name("PATTERN")
+                    .requires("project")
+                    .help("Archive directory to consider as archive root (like 'tool-*' for versioned top-level directory)"),
+
+                Arg::new("layout").short('l').long("layout")
+                    .value_name("LAYOUT")
+                    .requires("project")
+                    .value_parser(Layout::VALUES)
+                    .help("Install only the binary or extract the whole archive and symlink the binary"),
+
+                Arg::new("rosetta_fallback").long("rosetta-fallback")
+                    .action(ArgAction::SetTrue)
+                    .requires("project")
+                    .help("Fall back to x86_64 release on Apple Silicon if there is no arm64 one"),
+
+                Arg::new("sha256").long("sha256")
+                    .value_name("CHECKSUM")
+                    .requires("project")
+                    .help("Expected SHA-256 checksum of the release archive"),
+
+                Arg::new("checksum").long("checksum")
+                    .value_name("POLICY")
+                    .requires("project")
+                    .value_parser(ChecksumPolicy::VALUES)
+                    .help("Verify the release archive against the checksum file published in the release [default: auto]"),
+
+                Arg::new("download_header").long("download-header")
+                    .value_name("NAME:VALUE")
+                    .action(ArgAction::Append)
+                    .requires("project")
+                    .help("Custom HTTP header to send when downloading the release archive"),
+
+                Arg::new("forward_auth_on_redirect").long("forward-auth-on-redirect")
+                    .action(ArgAction::SetTrue)
+                    .requires("project")
+                    .help("Forward Authorization and Cookie headers when download is redirected to another host"),
+
+                Arg::new("capabilities").long("capabilities")
+                    .value_name("CAPABILITIES")
+                    .requires("project")
+                    .help("Linux file capabilities to set on the installed binary (in setcap format)"),
+
+                Arg::new("preserve_xattrs").long("preserve-xattrs")
+                    .action(ArgAction::SetTrue)
+                    .requires("project")
+                    .help("Restore security extended attributes of the replaced binary on upgrade"),
+
+                Arg::new("hold").long("hold")
+                    .value_name("REASON")
+                    .requires("project")
+                    .help("Don't upgrade the tool for the specified reason"),
+
                 Arg::new("path").short('d').long("path")
                     .value_name("PATH")
                     .requires("project")
                     .value_parser(value_parser!(PathBuf))
                     .help("Path where to install this specific tool to"),
 
+                Arg::new("umask").long("umask")
+                    .value_name("UMASK")
+                    .requires("project")
+                    .help("Umask for the files and directories which are created on installation (like 002)"),
+
+                Arg::new("group").long("group")
+                    .value_name("GROUP")
+                    .requires("project")
+                    .help("Group owner of the files and directories which are created on installation"),
+
+                Arg::new("smoke_test").long("smoke-test")
+                    .value_name("COMMAND")
+                    .requires("project")
+                    .help("Command which checks the new binary (passed in $BINUP_PATH) before it replaces the installed one"),
+
                 Arg::new("post").short('s').long("post")
                     .value_name("COMMAND")
                     .requires("project")
                     .help("Post-install command"),
+
+                Arg::new("post_on").long("post-on")
+                    .value_name("CHANGE")
+                    .value_parser(ChangeKind::VALUES)
+                    .action(ArgAction::Append)
+                    .requires("post")
+                    .help("Run the post-install command only on the specified kind of change [default: all]"),
             ]))
 
         .subcommand(Command::new("upgrade").visible_alias("u")
             .about("Upgrade all or only specified tools")
+            .args([
+                Arg::new("name")
+                    .value_name("NAME")
+                    .action(ArgAction::Append)
+                    .help("Tool name"),
+
+                Arg::new("transactional").short('t').long("transactional")
+                    .action(ArgAction::SetTrue)
+                    .help("Roll back all upgraded tools if any of them fails to install or its post-install command fails"),
+
+                Arg::new("force_overwrite").long("force-overwrite")
+                    .action(ArgAction::SetTrue)
+                    .help("Replace existing binary which hasn't been installed by binup"),
+
+                Arg::new("timings").long("timings")
+                    .action(ArgAction::SetTrue)
+                    .help("Print a breakdown of time spent in each phase of the run per tool"),
+
+                Arg::new("min_check_interval").long("min-check-interval")
+                    .value_name("DURATION")
+                    .help("Skip the tools which have been successfully checked within the specified duration (like 1h or 30m)"),
+            ]))
+
+        .subcommand(Command::new("inspect")
+            .about("List files of the tool's release asset without installing it")
             .arg(Arg::new("name")
                 .value_name("NAME")
-                .action(ArgAction::Append)
+                .required(true)
                 .help("Tool name")))
 
+        .subcommand(Command::new("suggest-forks")
+            .about("Look for maintained forks of the tool's project which has stopped releasing")
+            .args([
+                Arg::new("name")
+                    .value_name("NAME")
+                    .required(true)
+                    .help("Tool name"),
+                Arg::new("inactive_for").long("inactive-for")
+                    .value_name("DURATION")
+                    .default_value("26w")
+                    .help("Consider the project inactive if it has no releases for the specified duration (like 180d or 26w)"),
+            ]))
+
+        .subcommand(Command::new("notes")
+            .about("Collect release notes of all releases between the installed and the latest versions of the tool")
+            .args([
+                Arg::new("name")
+                    .value_name("NAME")
+                    .required(true)
+                    .help("Tool name"),
+                Arg::new("output").short('o').long("output")
+                    .value_name("PATH")
+                    .value_parser(value_parser!(PathBuf))
+                    .help("Path to write the Markdown document to (stdout by default)"),
+            ]))
+
         .subcommand(Command::new("uninstall").visible_aliases(["remove", "r"])
             .about("Uninstall the specified tools")
             .arg(Arg::new("name")
@@ -135,25 +448,144 @@ pub fn parse_args() -> GenericResult<CliArgs> {
                 .required(true)
                 .help("Tool name")))
 
-        .get_matches();
+        .subcommand(Command::new("gc")
+            .about("Delete the tool versions which aren't used by shims anymore")
+            .arg(Arg::new("keep").long("keep")
+                .value_name("COUNT")
+                .value_parser(value_parser!(usize))
+                .default_value("0")
+                .help("Number of the most recent unused versions of each tool to keep")))
 
-    let log_level = match matches.get_count("verbose") {
-        0 => log::Level::Info,
-        1 => log::Level::Debug,
-        2 => log::Level::Trace,
-        _ => return Err!("Invalid verbosity level"),
+        .subcommand(Command::new("report")
+            .about("Generate a report about installed tools and pending upgrades")
+            .args([
+                Arg::new("format").long("format")
+                    .value_name("FORMAT")
+                    .value_parser(ReportFormat::VALUES)
+                    .help("Report format [default: markdown]"),
+                Arg::new("probe").long("probe")
+                    .help("Determine installed versions by running the binaries instead of using the state file")
+                    .action(ArgAction::SetTrue),
+                Arg::new("new_only").long("new-only")
+                    .help("Generate the report only if there are upgrades which haven't been reported yet")
+                    .action(ArgAction::SetTrue),
+            ]))
+
+        .subcommand(Command::new("verify")
+            .about("Verify installed binaries against their release assets to detect tampering")
+            .args([
+                Arg::new("name")
+                    .value_name("NAME")
+                    .action(ArgAction::Append)
+                    .help("Tool name"),
+                Arg::new("paranoid").long("paranoid")
+                    .help("Re-download the release assets from upstream instead of using the cached ones")
+                    .action(ArgAction::SetTrue),
+            ]))
+
+        .subcommand(Command::new("bootstrap")
+            .about("Generate a shell script which installs binup, its configuration file and all the tools on a new machine")
+            .arg(Arg::new("output").short('o').long("output")
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .help("Path to write the script to (stdout by default)")))
+
+        .subcommand(Command::new("config")
+            .about("Manage the configuration file")
+            .subcommand_required(true)
+            .subcommand(Command::new("show")
+                .about("Print the configuration file")
+                .arg(Arg::new("effective").long("effective")
+                    .action(ArgAction::SetTrue)
+                    .help("Print the effective configuration with defaults and overrides applied and the source of each option")))
+            .subcommand(Command::new("lint")
+                .about("Check the configuration file for common mistakes")
+                .arg(Arg::new("fix").long("fix")
+                    .action(ArgAction::SetTrue)
+                    .help("Apply the fixes which don't change the effective configuration")))
+            .subcommand(Command::new("restore")
+                .about("Restore the configuration file from the latest backup")))
+
+        .subcommand(Command::new("state")
+            .about("Manage the state file")
+            .subcommand_required(true)
+            .subcommand(Command::new("sign")
+                .about("Sign the state file with the configured state key, trusting its current contents")))
+
+        .subcommand(Command::new("shims")
+            .about("Manage the versions available to the shims")
+            .subcommand_required(true)
+            .subcommand(Command::new("install")
+                .about("Install the specified tool version or the versions pinned in .binup-version and binup.toml files of the current directory and its parents")
+                .args([
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .help("Tool name"),
+                    Arg::new("version")
+                        .value_name("VERSION")
+                        .requires("name")
+                        .help("Tool version"),
+                ])))
+}
+
+pub fn parse_args() -> GenericResult<CliArgs> {
+    let matches = build_cli().get_matches();
+
+    let log_level = if let Some(level) = matches.get_one::<String>("log_level") {
+        level.parse().map_err(|_| format!("Invalid log level: {level:?}"))?
+    } else if matches.get_flag("quiet") {
+        log::Level::Error
+    } else {
+        match matches.get_count("verbose") {
+            0 => log::Level::Info,
+            1 => log::Level::Debug,
+            2 => log::Level::Trace,
+            _ => return Err!("Invalid verbosity level"),
+        }
     };
 
+    let system = matches.get_flag("system");
     let (config_path, custom_config) = match matches.get_one("config").cloned() {
         Some(path) => (path, true),
+        None if system => (PathBuf::from(SYSTEM_CONFIG_PATH), false),
         None => (PathBuf::from(shellexpand::tilde(DEFAULT_CONFIG_PATH).to_string()), false),
     };
 
+    let prompt_default = matches.get_one("prompt_default").map(|answer: &String| answer.parse()).transpose()?.unwrap_or_default();
+
+    let ip_version = if matches.get_flag("ipv4") {
+        Some(IpVersion::Ipv4)
+    } else if matches.get_flag("ipv6") {
+        Some(IpVersion::Ipv6)
+    } else {
+        None
+    };
+
+    let porcelain: Option<Porcelain> = matches.get_one("porcelain").map(|version: &String| version.parse()).transpose()?;
+
     let (command, matches) = matches.subcommand().unwrap();
+    if porcelain.is_some() && !PORCELAIN_COMMANDS.contains(&command) {
+        return Err!("--porcelain is not supported by {command} command");
+    }
 
     let action = match command {
         "list" => Action::List {
+            format: match (porcelain, matches.get_one("format")) {
+                (Some(_), Some(_)) => return Err!("--format can't be used with --porcelain"),
+                (Some(version), None) => ListFormat::Porcelain(version),
+                (None, format) => format.map(|format: &String| format.parse()).transpose()?.unwrap_or_default(),
+            },
             full: matches.get_flag("full"),
+            probe: matches.get_flag("probe"),
+            local: matches.get_flag("local"),
+            stale_after: matches.get_one("stale_after").map(|duration: &String| util::parse_duration(duration)).transpose()?,
+            unused: matches.get_one("unused").map(|duration: &String| util::parse_duration(duration)).transpose()?,
+        },
+
+        "install" if matches.get_flag("self") => Action::InstallSelf {
+            force: matches.get_flag("force"),
+            force_overwrite: matches.get_flag("force_overwrite"),
+            timings: matches.get_flag("timings"),
         },
 
         "install" if matches.contains_id("project") => {
@@ -166,6 +598,8 @@ pub fn parse_args() -> GenericResult<CliArgs> {
                 name: names.into_iter().next(),
                 spec: get_tool_spec(matches)?,
                 force: matches.get_flag("force"),
+                force_overwrite: matches.get_flag("force_overwrite"),
+                timings: matches.get_flag("timings"),
             }
         },
 
@@ -174,20 +608,86 @@ pub fn parse_args() -> GenericResult<CliArgs> {
                 "install" => Mode::Install {
                     force: matches.get_flag("force"),
                     recheck_spec: false,
+                    reuse_cache: matches.get_flag("reuse_cache"),
+                },
+                "upgrade" => Mode::Upgrade {
+                    min_check_interval: matches.get_one("min_check_interval")
+                        .map(|duration: &String| util::parse_duration(duration)).transpose()?,
                 },
-                "upgrade" => Mode::Upgrade,
                 _ => unreachable!(),
             };
 
-            Action::Install {mode, names: get_names(matches)}
+            Action::Install {
+                mode,
+                names: get_names(matches),
+                transactional: command == "upgrade" && matches.get_flag("transactional"),
+                force_overwrite: matches.get_flag("force_overwrite"),
+                timings: matches.get_flag("timings"),
+            }
+        },
+
+        "inspect" => Action::Inspect {
+            name: matches.get_one("name").cloned().unwrap(),
+            porcelain,
+        },
+
+        "suggest-forks" => Action::SuggestForks {
+            name: matches.get_one("name").cloned().unwrap(),
+            inactive_for: util::parse_duration(matches.get_one::<String>("inactive_for").unwrap())?,
+            porcelain,
+        },
+
+        "notes" => Action::Notes {
+            name: matches.get_one("name").cloned().unwrap(),
+            output: matches.get_one("output").cloned(),
         },
 
         "uninstall" => Action::Uninstall {names: get_names(matches)},
+        "gc" => Action::CollectGarbage {keep: *matches.get_one("keep").unwrap()},
+
+        "report" => Action::Report {
+            format: match (porcelain, matches.get_one("format")) {
+                (Some(_), Some(_)) => return Err!("--format can't be used with --porcelain"),
+                (Some(version), None) => ReportFormat::Porcelain(version),
+                (None, format) => format.map(|format: &String| format.parse()).transpose()?.unwrap_or_default(),
+            },
+            probe: matches.get_flag("probe"),
+            new_only: matches.get_flag("new_only"),
+        },
+
+        "verify" => Action::Verify {
+            names: get_names(matches),
+            paranoid: matches.get_flag("paranoid"),
+        },
+
+        "bootstrap" => Action::Bootstrap {
+            output: matches.get_one("output").cloned(),
+        },
+
+        "config" => match matches.subcommand().unwrap() {
+            ("show", matches) => Action::ShowConfig {effective: matches.get_flag("effective")},
+            ("lint", matches) => Action::LintConfig {fix: matches.get_flag("fix")},
+            ("restore", _) => Action::RestoreConfig,
+            _ => unreachable!(),
+        },
+
+        "state" => match matches.subcommand().unwrap().0 {
+            "sign" => Action::SignState,
+            _ => unreachable!(),
+        },
+
+        "shims" => match matches.subcommand().unwrap() {
+            ("install", matches) => Action::InstallShims {
+                name: matches.get_one("name").cloned(),
+                version: matches.get_one("version").cloned(),
+            },
+            _ => unreachable!(),
+        },
 
         _ => unreachable!(),
     };
 
-    Ok(CliArgs {log_level, config_path, custom_config, action})
+    Ok(CliArgs {log_level, config_path, custom_config, system, prompt_default, ip_version, action})
 }
 
 fn get_names(matches: &ArgMatches) -> Vec<String> {
@@ -209,6 +709,10 @@ fn get_tool_spec(matches: &ArgMatches) -> GenericResult<ToolSpec> {
         Url::parse(url).map_err(|e| format!("Invalid changelog URL: {e}"))
     }).transpose()?;
 
+    let artifact = matches.get_one("artifact").map(|artifact: &String| {
+        ArtifactSpec::from_str(artifact)
+    }).transpose()?;
+
     let release_matcher = matches.get_one("release_matcher").map(|pattern: &String| {
         Matcher::new(pattern).map_err(|e| format!("Invalid release matcher: {e}"))
     }).transpose()?;
@@ -217,12 +721,66 @@ fn get_tool_spec(matches: &ArgMatches) -> GenericResult<ToolSpec> {
         Matcher::new(pattern).map_err(|e| format!("Invalid binary matcher: {e}"))
     }).transpose()?;
 
-    Ok(ToolSpec {
-        project: matches.get_one("project").cloned().unwrap(),
+    let binary_excludes = matches.get_many("binary_exclude").map(|patterns| {
+        patterns.map(|pattern: &String| {
+            Matcher::new(pattern).map_err(|e| format!("Invalid binary exclude pattern: {e}"))
+        }).collect::<Result<Vec<_>, _>>()
+    }).transpose()?;
+
+    let subdir = matches.get_one("subdir").map(|pattern: &String| {
+        Matcher::new(pattern).map_err(|e| format!("Invalid subdirectory pattern: {e}"))
+    }).transpose()?;
+
+    let mut download_headers = BTreeMap::new();
+
+    if let Some(headers) = matches.get_many::<String>("download_header") {
+        for header in headers {
+            let (name, value) = header.split_once(':').ok_or_else(|| format!(
+                "Invalid download header: {header:?}"))?;
+            download_headers.insert(name.trim().to_owned(), value.trim().to_owned());
+        }
+    }
+
+    let spec = ToolSpec {
+        project: matches.get_many("project").unwrap().cloned().collect(),
+        url: None,
+        extends: Vec::new(),
         changelog,
+        tag: matches.get_one("tag").cloned(),
+        strategy: matches.get_one("strategy").map(|strategy: &String| strategy.parse()).transpose()?.unwrap_or_default(),
+        max_release_pages: matches.get_one("max_release_pages").cloned().unwrap_or(tool::DEFAULT_MAX_RELEASE_PAGES),
+        artifact,
+        only_on: None,
         release_matcher,
+        variant: matches.get_one("variant").cloned(),
         binary_matcher,
+        binary_excludes,
+        subdir,
+        layout: matches.get_one("layout").map(|layout: &String| layout.parse()).transpose()?.unwrap_or_default(),
+        rosetta_fallback: matches.get_flag("rosetta_fallback"),
+        sha256: matches.get_one("sha256").cloned(),
+        checksum: matches.get_one("checksum").map(|policy: &String| policy.parse()).transpose()?.unwrap_or_default(),
+        download_headers,
+        forward_auth_on_redirect: matches.get_flag("forward_auth_on_redirect"),
+        capabilities: matches.get_one("capabilities").cloned(),
+        preserve_xattrs: matches.get_flag("preserve_xattrs"),
+        hold: matches.get_one("hold").cloned(),
         path: matches.get_one("path").cloned(),
-        post: matches.get_one("post").cloned(),
-    })
+        umask: matches.get_one("umask").cloned(),
+        group: matches.get_one("group").cloned(),
+        smoke_test: matches.get_one("smoke_test").cloned(),
+        post: match matches.get_one::<String>("post") {
+            Some(command) => Some(PostHook {
+                command: command.clone(),
+                on: match matches.get_many::<String>("post_on") {
+                    Some(changes) => changes.map(|change| change.parse()).collect::<GenericResult<_>>()?,
+                    None => ChangeKind::ALL.to_vec(),
+                },
+            }),
+            None => None,
+        },
+    };
+    spec.validate()?;
+
+    Ok(spec)
 }
\ No newline at end of file
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use clap_complete::engine::CompletionCandidate;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{DEFAULT_CONFIG_PATH, SYSTEM_CONFIG_PATH};
+use crate::config::{Config, Layer};
+use crate::core::{EmptyResult, GenericResult};
+use crate::github::Github;
+use crate::util;
+
+const MIN_QUERY_LENGTH: usize = 2;
+const MAX_RESULTS: u8 = 20;
+
+// Completes --project value using GitHub search API. The results are cached, because search API has very strict rate
+// limits (especially for anonymous requests) and completion is requested on each key press.
+pub fn complete_project(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(current) = current.to_str() else {
+        return Vec::new();
+    };
+
+    // Owner is completed first and then its repositories
+    let search = match current.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.contains('/') => Search::Projects(if name.is_empty() {
+            format!("user:{owner}")
+        } else {
+            format!("{name} user:{owner} in:name")
+        }),
+        None if current.len() >= MIN_QUERY_LENGTH => Search::Owners(format!("{current} in:login")),
+        _ => return Vec::new(),
+    };
+
+    // Completion must never fail loudly, so just return no candidates on any error
+    let projects = search_projects(search).unwrap_or_default();
+
+    projects.into_iter()
+        .filter(|project| project.name.to_lowercase().starts_with(&current.to_lowercase()))
+        .map(|project| {
+            let mut candidate = CompletionCandidate::new(project.name);
+            if !project.description.is_empty() {
+                candidate = candidate.help(Some(project.description.into()));
+            }
+            candidate
+        })
+        .collect()
+}
+
+enum Search {
+    Owners(String),
+    Projects(String),
+}
+
+fn search_projects(search: Search) -> GenericResult<Vec<ProjectInfo>> {
+    // The completion is run without command line arguments parsing, so we can use only the default configuration file
+    let config_path = PathBuf::from(shellexpand::tilde(DEFAULT_CONFIG_PATH).to_string());
+    let config = Config::load(&config_path, false, Layer::user(Path::new(SYSTEM_CONFIG_PATH))?)?;
+
+    let key = match search {
+        Search::Owners(ref query) => format!("users: {query}"),
+        Search::Projects(ref query) => format!("repositories: {query}"),
+    };
+
+    let cache_path = config.get_completion_cache_path();
+    let mut cache = CompletionCache::load(&cache_path).unwrap_or_default();
+    let now = Utc::now();
+
+    if let Some(search) = cache.searches.get(&key) {
+        if search.time + Duration::days(1) > now {
+            return Ok(search.projects.clone());
+        }
+    }
+
+    let github = Github::new(&config.github, &config.tls, &config.network)?;
+    let projects: Vec<ProjectInfo> = match search {
+        Search::Owners(query) => github.search_owners(&query, MAX_RESULTS)?.into_iter().map(|owner| {
+            ProjectInfo {name: format!("{owner}/"), description: String::new()}
+        }).collect(),
+        Search::Projects(query) => github.search_projects(&query, MAX_RESULTS)?.into_iter().map(|(name, description)| {
+            ProjectInfo {name, description}
+        }).collect(),
+    };
+
+    cache.searches.retain(|_, search| search.time + Duration::days(1) > now);
+    cache.searches.insert(key, CachedSearch {time: now, projects: projects.clone()});
+    let _ = cache.save(&cache_path);
+
+    Ok(projects)
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct CompletionCache {
+    #[serde(default)]
+    searches: BTreeMap<String, CachedSearch>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedSearch {
+    time: DateTime<Utc>,
+    projects: Vec<ProjectInfo>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct ProjectInfo {
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+}
+
+impl CompletionCache {
+    fn load(path: &Path) -> GenericResult<CompletionCache> {
+        Ok(serde_yaml::from_slice(&fs::read(path)?)?)
+    }
+
+    fn save(&self, path: &Path) -> EmptyResult {
+        if let Some(path) = path.parent() {
+            fs::create_dir_all(path)?;
+        }
+        util::write_file(path, serde_yaml::to_string(self)?.as_bytes())
+    }
+}
@@ -1,17 +1,28 @@
 use std::collections::BTreeMap;
-use std::fs::{self, File, OpenOptions};
+use std::fs::{self, File};
 use std::io::{self, ErrorKind, Read, Write};
-use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chrono::Local;
+use log::{debug, info, warn};
 
-use log::error;
 use nondestructive::yaml::{self, Document, Separator};
+use openssl::sha::Sha256;
 use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
 use validator::Validate;
 
 use crate::core::{EmptyResult, GenericResult};
+use crate::download::ExtractionLimits;
+use crate::bitbucket::BitbucketConfig;
+use crate::distro::StaticAssets;
 use crate::github::GithubConfig;
-use crate::tool::ToolSpec;
+use crate::i18n;
+use crate::network::NetworkConfig;
+use crate::s3::S3Config;
+use crate::tls::TlsConfig;
+use crate::tool::{Layout, OnlyOn, ToolSpec};
 use crate::util;
 
 #[derive(Clone, Deserialize, PartialEq, Validate)]
@@ -20,19 +31,128 @@ pub struct Config {
     #[serde(skip)]
     source: Option<ConfigSource>,
 
+    // Reusable tool options which are referenced by `extends` tool option
+    #[serde(skip)]
+    templates: Mapping,
+
     #[serde(rename = "path", default = "default_install_path", deserialize_with = "util::deserialize_path")]
     pub path: PathBuf,
 
+    #[serde(default = "default_tree_path", deserialize_with = "util::deserialize_path")]
+    pub tree_path: PathBuf,
+
+    #[serde(default = "default_cache_path", deserialize_with = "util::deserialize_path")]
+    pub cache_path: PathBuf,
+
+    // Install shims to the install path which dispatch to the versioned binaries under versions path
+    #[serde(default)]
+    pub shims: bool,
+
+    #[serde(default = "default_versions_path", deserialize_with = "util::deserialize_path")]
+    pub versions_path: PathBuf,
+
+    // Install the tools to symlink targets instead of refusing to replace the symlinks
+    #[serde(default)]
+    pub follow_symlinks: bool,
+
+    // Use credentials from ~/.netrc for downloads
+    #[serde(default)]
+    pub netrc: bool,
+
     #[serde(default)]
     #[validate(nested)]
     pub tools: BTreeMap<String, ToolSpec>,
 
+    #[serde(default)]
+    pub decompressors: BTreeMap<String, String>,
+
+    // Prefer statically linked assets when a release has several builds for the current platform
+    #[serde(default)]
+    pub static_assets: StaticAssets,
+
+    #[serde(default)]
+    pub extraction_limits: ExtractionLimits,
+
+    pub post_upgrade: Option<String>,
+
+    // Command which receives the plan of the run as JSON on stdin and may veto it by exiting with non-zero code
+    pub pre_run: Option<String>,
+
+    #[serde(default, deserialize_with = "util::deserialize_optional_path")]
+    state_path: Option<PathBuf>,
+
+    // Secret key to sign the state file with to protect it from tampering
+    #[serde(default, deserialize_with = "util::deserialize_optional_path")]
+    pub state_key: Option<PathBuf>,
+
+    #[serde(default = "default_config_backups")]
+    pub config_backups: usize,
+
     #[serde(default)]
     pub github: GithubConfig,
+
+    #[serde(default)]
+    pub bitbucket: BitbucketConfig,
+
+    #[serde(default)]
+    pub s3: S3Config,
+
+    #[serde(default)]
+    #[validate(nested)]
+    pub tls: TlsConfig,
+
+    #[serde(default)]
+    pub network: NetworkConfig,
+}
+
+// The options which users inherit from the system configuration file if they don't specify them. The other options
+// (paths, hooks, state and tools) belong to the specific configuration file.
+const SYSTEM_OPTIONS: [&str; 9] = [
+    "netrc", "decompressors", "static_assets", "extraction_limits", "github", "bitbucket", "s3", "tls", "network"];
+
+// The system configuration file manages the tools of all users, so its paths default to the system locations
+const SYSTEM_DEFAULTS: [(&str, &str); 5] = [
+    ("path", "/usr/local/bin"),
+    ("tree_path", "/opt/binup"),
+    ("cache_path", "/var/cache/binup"),
+    ("versions_path", "/var/lib/binup/versions"),
+    ("state_path", "/var/lib/binup/state.yaml"),
+];
+
+// Copies of the configuration file: backups made on editing and the files replaced by `config restore`
+const BACKUP_SUFFIX: &str = "bak";
+const REPLACED_SUFFIX: &str = "replaced";
+
+#[derive(Clone, PartialEq)]
+pub enum Layer {
+    // User configuration file with the contents of the system one, if it exists
+    User(Option<Value>),
+    System,
+}
+
+impl Layer {
+    // Returns the layer of the user configuration file which is merged with the specified system one
+    pub fn user(system_path: &Path) -> GenericResult<Layer> {
+        let data = match fs::read(system_path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Layer::User(None)),
+            Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+                warn!("Unable to read {system_path:?}: {err}. Ignoring the system configuration file.");
+                return Ok(Layer::User(None));
+            },
+            Err(err) => return Err!("Unable to read {system_path:?}: {err}"),
+        };
+
+        let mut system: Value = serde_yaml::from_slice(&data).map_err(|e| format!(
+            "Error while reading {system_path:?} configuration file: {e}"))?;
+        system.apply_merge()?;
+
+        Ok(Layer::User(Some(system)))
+    }
 }
 
 impl Config {
-    pub fn load(path: &Path, custom: bool) -> GenericResult<Config> {
+    pub fn load(path: &Path, custom: bool, layer: Layer) -> GenericResult<Config> {
         let (mut reader, exists) = match File::open(path) {
             Ok(file) => (ConfigReader::new(file), true),
             Err(err) => {
@@ -43,11 +163,12 @@ impl Config {
             },
         };
 
-        let mut config = Config::read(&mut reader)?;
+        let mut config = Config::read_layer(&mut reader, &layer)?;
         config.source.replace(ConfigSource {
             path: path.to_owned(),
             data: reader.consume(),
             exists,
+            layer,
         });
 
         Ok(config)
@@ -63,13 +184,14 @@ impl Config {
         let mut expected_config = self.clone();
         let mut source = expected_config.source.take().unwrap();
 
-        let mut raw = yaml::from_slice(&source.data).map_err(|e| format!(
+        let (masked, placeholders) = mask_anchors(&String::from_utf8_lossy(&source.data));
+        let mut raw = yaml::from_slice(&masked).map_err(|e| format!(
             "{error_prefix} Got an error: {e}"))?;
 
         edit(&mut expected_config, &mut raw)?;
 
-        let result = raw.to_string();
-        let mut config = Config::read(result.as_bytes()).map_err(|e| format!(
+        let result = unmask_anchors(raw.to_string(), &placeholders);
+        let mut config = Config::read_layer(result.as_bytes(), &source.layer).map_err(|e| format!(
             "{error_prefix} Got the following invalid config ({e}):\n{result}"))?;
 
         if config != expected_config {
@@ -82,7 +204,18 @@ impl Config {
 
         let source = config.source.as_mut().unwrap();
 
-        if !source.exists {
+        if source.exists {
+            let original = &self.source.as_ref().unwrap().data;
+
+            // Protect from overwriting the changes made by user or another binup instance since we've read the config
+            let current = fs::read(&source.path).map_err(|e| format!(
+                "Unable to read {:?}: {e}", source.path))?;
+            if sha256(&current) != sha256(original) {
+                return Err!("{:?} has been changed since it was read. Please try again", source.path);
+            }
+
+            backup(&source.path, original, config.config_backups)?;
+        } else {
             if let Some(path) = source.path.parent() {
                 fs::create_dir_all(path).map_err(|e| format!(
                     "Unable to create {path:?}: {e}"))?;
@@ -90,14 +223,75 @@ impl Config {
             source.exists = true;
         }
 
-        Config::write(&source.path, &source.data)?;
+        util::write_file(&source.path, &source.data)?;
         *self = config;
 
         Ok(result)
     }
 
+    pub fn get_source_data(&self) -> Option<&[u8]> {
+        self.source.as_ref().filter(|source| source.exists).map(|source| source.data.as_slice())
+    }
+
+    pub fn get_state_path(&self) -> PathBuf {
+        self.state_path.clone().unwrap_or_else(|| {
+            let source = self.source.as_ref().expect("Config source is not set");
+            source.path.with_file_name("state.yaml")
+        })
+    }
+
+    // Returns the tools which are supposed to be installed on the current machine
+    pub fn get_active_tools(&self) -> impl Iterator<Item = (&String, &ToolSpec)> {
+        self.tools.iter().filter(|(_, spec)| spec.only_on.as_ref().is_none_or(OnlyOn::matches))
+    }
+
+    // Returns the path of the installed binary. In shim mode it's managed under versions path, and the command path gets
+    // a shim instead.
     pub fn get_tool_path(&self, name: &str, spec: &ToolSpec) -> PathBuf {
-        spec.path.as_ref().unwrap_or(&self.path).join(name)
+        if self.uses_shim(spec) {
+            return self.get_tool_versions_path(name).join(name);
+        }
+        self.get_command_path(name, spec)
+    }
+
+    // Returns the path of the tool command which is supposed to be in $PATH
+    pub fn get_command_path(&self, name: &str, spec: &ToolSpec) -> PathBuf {
+        let path = spec.path.as_ref().unwrap_or(&self.path);
+
+        let path = match path.to_str() {
+            Some(template) if template.contains("{name}") => PathBuf::from(template.replace("{name}", name)),
+            _ => path.to_owned(),
+        };
+
+        path.join(name)
+    }
+
+    // Tools with tree layout are installed as is, since their trees can't be kept in several versions
+    pub fn uses_shim(&self, spec: &ToolSpec) -> bool {
+        self.shims && spec.layout == Layout::Binary
+    }
+
+    pub fn get_tool_versions_path(&self, name: &str) -> PathBuf {
+        self.versions_path.join(name)
+    }
+
+    pub fn get_tool_tree_path(&self, name: &str, spec: &ToolSpec) -> Option<PathBuf> {
+        match spec.layout {
+            Layout::Binary => None,
+            Layout::Tree => Some(self.tree_path.join(name)),
+        }
+    }
+
+    pub fn get_tool_cache_path(&self, name: &str, asset_name: &str) -> PathBuf {
+        self.cache_path.join(name).join(asset_name)
+    }
+
+    pub fn get_completion_cache_path(&self) -> PathBuf {
+        self.cache_path.join("completion.yaml")
+    }
+
+    pub fn get_metadata_cache_path(&self) -> PathBuf {
+        self.cache_path.join("metadata.yaml")
     }
 
     pub fn update_tool(&mut self, raw: &mut Document, name: &str, spec: &ToolSpec) -> EmptyResult {
@@ -114,6 +308,22 @@ impl Config {
         }.make_mapping();
 
         spec.serialize(&mut tool)?;
+
+        // Don't duplicate the options which are inherited from the templates
+        if !spec.extends.is_empty() {
+            let inherited = get_template_options(&self.templates, &spec.extends)?;
+
+            let mut own = yaml::from_slice("")?;
+            spec.serialize(&mut own.as_mut().make_mapping())?;
+            let own: Mapping = serde_yaml::from_str(&own.to_string())?;
+
+            for (key, value) in own {
+                if let Some(key) = key.as_str().filter(|key| *key != "extends" && inherited.get(key) == Some(&value)) {
+                    tool.remove(key);
+                }
+            }
+        }
+
         self.tools.insert(name.to_owned(), spec.clone());
 
         Ok(())
@@ -133,61 +343,543 @@ impl Config {
         Ok(())
     }
 
-    fn read<R: Read>(reader: R) -> GenericResult<Config> {
-        let config: Config = serde_yaml::from_reader(reader)?;
+    pub fn remove_template(&mut self, raw: &mut Document, name: &str) -> EmptyResult {
+        let removed = || -> Option<bool> {
+            Some(raw.as_mut().as_mapping_mut()?
+                .get_mut("templates")?.as_mapping_mut()?
+                .remove(name))
+        }().unwrap_or_default();
+
+        if !removed || self.templates.remove(name).is_none() {
+            return Err!("Unable to find the template in the configuration file")
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: R) -> GenericResult<Config> {
+        Config::read_layer(reader, &Layer::User(None))
+    }
+
+    fn read_layer<R: Read>(mut reader: R, layer: &Layer) -> GenericResult<Config> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let mut value: Value = serde_yaml::from_slice(&data)?;
+        let original = value.clone();
+
+        value.apply_merge()?;
+        apply_layer(&mut value, layer)?;
+
+        let mut templates = Mapping::new();
+        if let Value::Mapping(ref mut root) = value {
+            // Hidden keys which may be used to define anchors with shared tool options
+            root.retain(|key, _| !key.as_str().is_some_and(|key| key.starts_with("x-")));
+            templates = apply_templates(root)?;
+        }
+
+        // Deserialize from the original data when possible to get errors with line numbers
+        let mut config: Config = if value == original {
+            serde_yaml::from_slice(&data)?
+        } else {
+            serde_yaml::from_value(value)?
+        };
+        config.templates = templates;
+
         config.validate()?;
         Ok(config)
     }
+}
 
-    fn write(path: &Path, data: &[u8]) -> EmptyResult {
-        let temp_path = {
-            let mut path = path.as_os_str().to_owned();
-            path.push(".new");
-            PathBuf::from(path)
-        };
+// User configuration file takes precedence over the system one: the system options and templates are used only if the
+// user doesn't specify them, and the system tools aren't merged at all, since they are managed by `binup --system`.
+fn apply_layer(value: &mut Value, layer: &Layer) -> EmptyResult {
+    if let Layer::User(None) = layer {
+        return Ok(());
+    } else if let Value::Null = value {
+        *value = Value::Mapping(Mapping::new());
+    }
+    let Value::Mapping(root) = value else {
+        return Ok(());
+    };
 
-        if let Err(err) = fs::remove_file(&temp_path) {
-            if err.kind() != ErrorKind::NotFound {
-                return Err!("Unable to delete {temp_path:?}: {err}");
+    match layer {
+        Layer::User(None) => {},
+
+        Layer::User(Some(system)) => {
+            for key in SYSTEM_OPTIONS {
+                if let (false, Some(value)) = (root.contains_key(key), system.get(key)) {
+                    root.insert(key.into(), value.clone());
+                }
+            }
+
+            match (root.get_mut("templates"), system.get("templates")) {
+                (Some(Value::Mapping(templates)), Some(Value::Mapping(system))) => {
+                    for (name, template) in system {
+                        if !templates.contains_key(name) {
+                            templates.insert(name.clone(), template.clone());
+                        }
+                    }
+                },
+                (None, Some(templates)) => {
+                    root.insert("templates".into(), templates.clone());
+                },
+                _ => {},
             }
+        },
+
+        Layer::System => {
+            for (key, default) in SYSTEM_DEFAULTS {
+                if !root.contains_key(key) {
+                    root.insert(key.into(), default.into());
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+// Merges the templates into the tools which extend them and returns the templates. Templates are applied in the
+// specified order, so the later ones override the options of the earlier ones, and the tool's own options override
+// all of them.
+fn apply_templates(root: &mut Mapping) -> GenericResult<Mapping> {
+    let templates = match root.remove("templates") {
+        Some(Value::Mapping(templates)) => templates,
+        None | Some(Value::Null) => Mapping::new(),
+        Some(_) => return Err!("Invalid templates: a mapping is expected"),
+    };
+
+    for (name, template) in &templates {
+        match template {
+            Value::Mapping(template) if template.contains_key("extends") => return Err!(
+                "Invalid {} template: templates can't extend other templates", name.as_str().unwrap_or_default()),
+            Value::Mapping(_) => {},
+            _ => return Err!("Invalid {} template: a mapping is expected", name.as_str().unwrap_or_default()),
         }
+    }
 
-        let mut open_options = OpenOptions::new();
-        open_options.create_new(true).write(true);
+    let Some(Value::Mapping(tools)) = root.get_mut("tools") else {
+        return Ok(templates);
+    };
 
-        match fs::metadata(path) {
-            Ok(metadata) => {
-                open_options.mode(metadata.mode());
-            },
-            Err(err) => if err.kind() != ErrorKind::NotFound {
-                return Err!("Unable to stat() {path:?}: {err}");
+    for (name, spec) in tools.iter_mut() {
+        let Value::Mapping(spec) = spec else {
+            continue;
+        };
+
+        let extends: Vec<String> = match spec.get("extends") {
+            Some(extends) => serde_yaml::from_value::<String>(extends.clone()).map(|template| vec![template])
+                .or_else(|_| serde_yaml::from_value(extends.clone()))
+                .map_err(|_| format!("Invalid {} tool extends: a template name or a list of them is expected",
+                    name.as_str().unwrap_or_default()))?,
+            None => continue,
+        };
+
+        let mut merged = get_template_options(&templates, &extends).map_err(|e| format!(
+            "Invalid {} tool: {e}", name.as_str().unwrap_or_default()))?;
+
+        for (key, value) in spec.iter() {
+            merged.insert(key.clone(), value.clone());
+        }
+
+        *spec = merged;
+    }
+
+    Ok(templates)
+}
+
+fn get_template_options(templates: &Mapping, names: &[String]) -> GenericResult<Mapping> {
+    let mut options = Mapping::new();
+
+    for name in names {
+        let Some(Value::Mapping(template)) = templates.get(name) else {
+            return Err!("Unknown {name:?} template");
+        };
+
+        for (key, value) in template {
+            options.insert(key.clone(), value.clone());
+        }
+    }
+
+    Ok(options)
+}
+
+pub fn restore_backup(path: &Path) -> GenericResult<ExitCode> {
+    let Some(backup_path) = list_backups(path)?.pop() else {
+        return Err!("There are no backups of {path:?}");
+    };
+
+    let data = fs::read(&backup_path).map_err(|e| format!(
+        "Unable to read {backup_path:?}: {e}"))?;
+
+    Config::read(data.as_slice()).map_err(|e| format!(
+        "{backup_path:?} contains an invalid configuration: {e}"))?;
+
+    if !util::confirm(i18n::trf(
+        "Restore {} from {}?", &[&format_args!("{path:?}"), &format_args!("{backup_path:?}")]))? {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if let Some(replaced_path) = restore(path, &backup_path, &data)? {
+        info!("The replaced configuration file is saved to {replaced_path:?}.");
+    }
+
+    info!("{path:?} is restored from {backup_path:?}.");
+    Ok(ExitCode::SUCCESS)
+}
+
+// The replaced file isn't saved as a backup, so each restore steps further back through the backups instead of
+// switching between the last two versions
+fn restore(path: &Path, backup_path: &Path, data: &[u8]) -> GenericResult<Option<PathBuf>> {
+    let replaced_path = match fs::read(path) {
+        Ok(current) => Some(save_copy(path, REPLACED_SUFFIX, &current)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err!("Unable to read {path:?}: {err}"),
+    };
+
+    util::write_file(path, data)?;
+    fs::remove_file(backup_path).map_err(|e| format!(
+        "Unable to delete {backup_path:?}: {e}"))?;
+
+    Ok(replaced_path)
+}
+
+// Prints the configuration file. In effective mode prints the configuration as binup sees it: with YAML merge keys
+// resolved, defaults applied, paths expanded and command line overrides, annotating each option with its source.
+pub fn show(config: &Config, effective: bool, overrides: &[&str]) -> GenericResult<ExitCode> {
+    let data = config.get_source_data().unwrap_or_default();
+
+    let output = if effective {
+        format_effective(config, data, overrides)?
+    } else {
+        String::from_utf8_lossy(data).into_owned()
+    };
+
+    io::stdout().write_all(output.as_bytes()).map_err(|e| format!(
+        "Failed to write the configuration: {e}"))?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn format_effective(config: &Config, data: &[u8], overrides: &[&str]) -> GenericResult<String> {
+    let original: Value = serde_yaml::from_slice(data)?;
+    let mut merged = original.clone();
+    merged.apply_merge()?;
+
+    let layer = config.source.as_ref().map(|source| &source.layer);
+
+    let get_source = |path: &[&str]| -> &'static str {
+        let lookup = |value: &Value| path.iter().try_fold(value, |value, key| value.get(key)).is_some();
+
+        if overrides.contains(&path.join(".").as_str()) {
+            "command line"
+        } else if lookup(&original) {
+            "config file"
+        } else if lookup(&merged) {
+            "merge key"
+        } else if let Some(Layer::User(Some(system))) = layer.filter(|_| {
+            SYSTEM_OPTIONS.contains(&path[0]) || path[0] == "templates"
+        }) {
+            if lookup(system) { "system config" } else { "default" }
+        } else if matches!(layer, Some(Layer::System)) && SYSTEM_DEFAULTS.iter().any(|(key, _)| path == [*key]) {
+            "system default"
+        } else {
+            "default"
+        }
+    };
+
+    // Null value denotes a section header
+    let write = |output: &mut String, path: &[&str], value: Option<Value>| -> EmptyResult {
+        if let Some(value) = value {
+            write_option(output, (path.len() - 1) * 2, path.last().unwrap(), &value, get_source(path))?;
+        }
+        Ok(())
+    };
+
+    let path_value = |path: &Path| Value::from(path.to_string_lossy().into_owned());
+    let optional_path_value = |path: &Option<PathBuf>| path.as_deref().map(path_value);
+    let hidden = || Value::from("<hidden>");
+
+    let mut output = String::new();
+
+    write(&mut output, &["path"], Some(path_value(&config.path)))?;
+    write(&mut output, &["tree_path"], Some(path_value(&config.tree_path)))?;
+    write(&mut output, &["cache_path"], Some(path_value(&config.cache_path)))?;
+    write(&mut output, &["shims"], Some(config.shims.into()))?;
+    write(&mut output, &["versions_path"], Some(path_value(&config.versions_path)))?;
+    write(&mut output, &["state_path"], Some(path_value(&config.get_state_path())))?;
+    write(&mut output, &["state_key"], optional_path_value(&config.state_key))?;
+    write(&mut output, &["follow_symlinks"], Some(config.follow_symlinks.into()))?;
+    write(&mut output, &["netrc"], Some(config.netrc.into()))?;
+    write(&mut output, &["config_backups"], Some(config.config_backups.into()))?;
+    write(&mut output, &["pre_run"], config.pre_run.clone().map(Value::from))?;
+    write(&mut output, &["post_upgrade"], config.post_upgrade.clone().map(Value::from))?;
+    write(&mut output, &["static_assets"], Some(config.static_assets.to_string().into()))?;
+    write(&mut output, &["decompressors"], Some(Value::Mapping(config.decompressors.iter().map(|(format, command)| {
+        (Value::from(format.as_str()), Value::from(command.as_str()))
+    }).collect())))?;
+
+    let limits = &config.extraction_limits;
+    write(&mut output, &["extraction_limits"], Some(Value::Null))?;
+    write(&mut output, &["extraction_limits", "max_compression_ratio"], Some(limits.max_compression_ratio.into()))?;
+    write(&mut output, &["extraction_limits", "max_entry_size"], Some(limits.max_entry_size.into()))?;
+    write(&mut output, &["extraction_limits", "max_download_size"], Some(limits.max_download_size.into()))?;
+
+    write(&mut output, &["github"], Some(Value::Null))?;
+    write(&mut output, &["github", "token"], config.github.has_default_token().then(hidden))?;
+    write(&mut output, &["github", "base_url"], config.github.base_url.as_ref().map(|url| Value::from(url.as_str())))?;
+    write(&mut output, &["github", "tokens"], Some(Value::Mapping(config.github.tokens.keys().map(|host| {
+        (Value::from(host.as_str()), hidden())
+    }).collect())))?;
+    if let Some(app) = config.github.app.as_ref() {
+        write(&mut output, &["github", "app"], Some(Value::Null))?;
+        write(&mut output, &["github", "app", "id"], Some(app.id.into()))?;
+        write(&mut output, &["github", "app", "installation_id"], Some(app.installation_id.into()))?;
+        write(&mut output, &["github", "app", "private_key"], Some(path_value(&app.private_key)))?;
+    }
+
+    write(&mut output, &["bitbucket"], Some(Value::Null))?;
+    write(&mut output, &["bitbucket", "username"], config.bitbucket.username.clone().map(Value::from))?;
+    write(&mut output, &["bitbucket", "app_password"], config.bitbucket.has_app_password().then(hidden))?;
+
+    write(&mut output, &["s3"], Some(Value::Null))?;
+    write(&mut output, &["s3", "region"], config.s3.region.clone().map(Value::from))?;
+    write(&mut output, &["s3", "endpoint"], config.s3.endpoint.as_ref().map(|url| Value::from(url.as_str())))?;
+    write(&mut output, &["s3", "access_key_id"], config.s3.access_key_id.clone().map(Value::from))?;
+    write(&mut output, &["s3", "secret_access_key"], config.s3.has_secret_access_key().then(hidden))?;
+
+    write(&mut output, &["tls"], Some(Value::Null))?;
+    write(&mut output, &["tls", "ca_file"], optional_path_value(&config.tls.ca_file))?;
+    write(&mut output, &["tls", "client_cert"], optional_path_value(&config.tls.client_cert))?;
+    write(&mut output, &["tls", "client_key"], optional_path_value(&config.tls.client_key))?;
+
+    write(&mut output, &["network"], Some(Value::Null))?;
+    write(&mut output, &["network", "ip_version"], Some(config.network.ip_version.to_string().into()))?;
+    write(&mut output, &["network", "resolve"], Some(Value::Mapping(config.network.resolve.iter().map(|(host, address)| {
+        (Value::from(host.as_str()), Value::from(address.to_string()))
+    }).collect())))?;
+
+    write(&mut output, &["tools"], Some(Value::Null))?;
+
+    for (name, spec) in &config.tools {
+        let mut raw = yaml::from_slice("")?;
+        spec.serialize(&mut raw.as_mut().make_mapping())?;
+
+        let mut options: Mapping = serde_yaml::from_str(&raw.to_string())?;
+        if let Some(Value::Mapping(headers)) = options.get_mut("download_headers") {
+            headers.values_mut().for_each(|value| *value = hidden());
+        }
+
+        let mut source = get_source(&["tools", name]).to_owned();
+        if !spec.only_on.as_ref().is_none_or(OnlyOn::matches) {
+            source += ", inactive on this machine";
+        }
+        write_option(&mut output, 2, name, &Value::Null, &source)?;
+
+        let inherited = get_template_options(&config.templates, &spec.extends)?;
+
+        for (key, value) in options {
+            let key = key.as_str().unwrap_or_default();
+
+            match get_source(&["tools", name, key]) {
+                "default" if inherited.contains_key(key) => write_option(&mut output, 4, key, &value, "template")?,
+                _ => write(&mut output, &["tools", name, key], Some(value))?,
             }
         }
+        write_option(&mut output, 4, "install_path", &path_value(&config.get_tool_path(name, spec)), "computed")?;
+    }
 
-        open_options.open(&temp_path)
-            .and_then(|mut file| {
-                file.write_all(data).inspect_err(|_| {
-                    if let Err(err) = fs::remove_file(&temp_path) {
-                        error!("Failed to delete {temp_path:?}: {err}.");
+    Ok(output)
+}
+
+fn write_option(output: &mut String, indent: usize, key: &str, value: &Value, source: &str) -> EmptyResult {
+    let padding = " ".repeat(indent);
+    let key = serde_yaml::to_string(key)?;
+    let key = key.trim_end();
+
+    let nested = match value {
+        Value::Mapping(mapping) => !mapping.is_empty(),
+        Value::Sequence(sequence) => !sequence.is_empty(),
+        _ => false,
+    };
+
+    if value.is_null() {
+        output.push_str(&format!("{padding}{key}:  # {source}\n"));
+    } else if nested {
+        output.push_str(&format!("{padding}{key}:  # {source}\n"));
+        for line in serde_yaml::to_string(value)?.lines() {
+            output.push_str(&format!("{padding}  {line}\n"));
+        }
+    } else {
+        let value = serde_yaml::to_string(value)?;
+        output.push_str(&format!("{padding}{key}: {}  # {source}\n", value.trim_end()));
+    }
+
+    Ok(())
+}
+
+fn backup(path: &Path, data: &[u8], max_backups: usize) -> EmptyResult {
+    if max_backups == 0 {
+        return Ok(());
+    }
+
+    save_copy(path, BACKUP_SUFFIX, data)?;
+
+    let backups = list_backups(path)?;
+    for backup_path in backups.iter().take(backups.len().saturating_sub(max_backups)) {
+        debug!("Deleting {backup_path:?}...");
+        fs::remove_file(backup_path).map_err(|e| format!(
+            "Unable to delete {backup_path:?}: {e}"))?;
+    }
+
+    Ok(())
+}
+
+// Saves a copy of the configuration file as $path.$suffix.$timestamp
+fn save_copy(path: &Path, suffix: &str, data: &[u8]) -> GenericResult<PathBuf> {
+    let mut copy_path = path.as_os_str().to_owned();
+    copy_path.push(format!(".{suffix}.{}", Local::now().format("%Y%m%d%H%M%S%.6f")));
+    let copy_path = PathBuf::from(copy_path);
+
+    debug!("Saving {path:?} to {copy_path:?}...");
+    util::write_file(&copy_path, data)?;
+
+    Ok(copy_path)
+}
+
+// Returns backups sorted from the oldest to the newest
+fn list_backups(path: &Path) -> GenericResult<Vec<PathBuf>> {
+    let (Some(directory), Some(prefix)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+        return Err!("Got an unexpected configuration file path: {path:?}");
+    };
+    let prefix = format!("{prefix}.{BACKUP_SUFFIX}.");
+
+    let mut backups = Vec::new();
+
+    for entry in fs::read_dir(directory).map_err(|e| format!("Unable to read {directory:?}: {e}"))? {
+        let entry = entry.map_err(|e| format!("Unable to read {directory:?}: {e}"))?;
+        let name = entry.file_name();
+
+        if name.to_str().is_some_and(|name| name.starts_with(&prefix) && !name.ends_with(".new")) {
+            backups.push(entry.path());
+        }
+    }
+
+    backups.sort();
+    Ok(backups)
+}
+
+// nondestructive doesn't support YAML anchors and aliases, so we replace them with placeholders which it's able to parse
+// (anchors of block collections become comments, the others become plain scalars) and restore them after editing.
+fn mask_anchors(data: &str) -> (String, Vec<(String, String)>) {
+    let mut masked = String::with_capacity(data.len());
+    let mut placeholders = Vec::new();
+
+    for line in data.split_inclusive('\n') {
+        let mut node_start = true;
+        let mut quote = None;
+        let mut chars = line.char_indices().peekable();
+
+        while let Some((index, char)) = chars.next() {
+            if let Some(quote_char) = quote {
+                if char == quote_char {
+                    quote = None;
+                }
+                masked.push(char);
+                continue;
+            }
+
+            if node_start && (char == '&' || char == '*') {
+                let name_len = line[index + 1..].find(|c: char| c.is_whitespace() || ",[]{}".contains(c))
+                    .unwrap_or(line.len() - index - 1);
+
+                if name_len != 0 {
+                    let end = index + 1 + name_len;
+                    let original = &line[index..end];
+                    let rest = line[end..].trim();
+
+                    let placeholder = if char == '*' {
+                        format!("__binup_alias_{}__", placeholders.len())
+                    } else if rest.is_empty() || rest.starts_with('#') {
+                        format!("#__binup_anchor_{}__", placeholders.len())
+                    } else {
+                        format!("__binup_anchor_{}__", placeholders.len())
+                    };
+
+                    masked.push_str(&placeholder);
+                    placeholders.push((placeholder, original.to_owned()));
+
+                    while chars.peek().is_some_and(|&(index, _)| index < end) {
+                        chars.next();
                     }
-                })
-            })
-            .and_then(|_| fs::rename(&temp_path, path))
-            .map_err(|e| format!("Failed to write {path:?}: {e}"))?;
 
-        Ok(())
+                    node_start = false;
+                    continue;
+                }
+            }
+
+            if char == '#' && (index == 0 || line[..index].ends_with(char::is_whitespace)) {
+                masked.push_str(&line[index..]);
+                break;
+            }
+
+            masked.push(char);
+
+            if char.is_whitespace() {
+                node_start = node_start || line[..index].trim().is_empty() || matches!(
+                    line[..index].chars().next_back(), Some(':' | '-' | '?' | ','));
+            } else {
+                node_start = matches!(char, '[' | '{' | ',');
+                if char == '"' || char == '\'' {
+                    quote = Some(char);
+                }
+            }
+        }
     }
+
+    (masked, placeholders)
+}
+
+fn unmask_anchors(mut data: String, placeholders: &[(String, String)]) -> String {
+    for (placeholder, original) in placeholders {
+        data = data.replace(placeholder, original);
+    }
+    data
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finish()
 }
 
 fn default_install_path() -> PathBuf {
     PathBuf::from(shellexpand::tilde("~/.local/bin").to_string())
 }
 
+fn default_cache_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.cache/binup").to_string())
+}
+
+fn default_versions_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.local/share/binup/versions").to_string())
+}
+
+fn default_config_backups() -> usize {
+    5
+}
+
+fn default_tree_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.local/opt").to_string())
+}
+
 #[derive(Clone, PartialEq)]
 struct ConfigSource {
     path: PathBuf,
     data: Vec<u8>,
     exists: bool,
+    layer: Layer,
 }
 
 struct ConfigReader {
@@ -214,4 +906,256 @@ impl Read for ConfigReader {
             self.data.extend_from_slice(&buf[..size]);
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use crate::network::IpVersion;
+    use super::*;
+
+    #[test]
+    fn restore_twice() {
+        let temp_dir = util::TempDir::new().unwrap();
+        let path = temp_dir.path.join("config.yaml");
+
+        backup(&path, b"first", 5).unwrap();
+        backup(&path, b"second", 5).unwrap();
+        util::write_file(&path, b"current").unwrap();
+
+        let mut replaced = Vec::new();
+
+        for expected in ["second", "first"] {
+            let backup_path = list_backups(&path).unwrap().pop().unwrap();
+            let data = fs::read(&backup_path).unwrap();
+            replaced.push(restore(&path, &backup_path, &data).unwrap().unwrap());
+            assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+        }
+
+        assert!(list_backups(&path).unwrap().is_empty());
+        assert_eq!(replaced.iter().map(|path| fs::read_to_string(path).unwrap()).collect::<Vec<_>>(), [
+            "current", "second"]);
+    }
+
+    #[test]
+    fn anchors() {
+        let data = indoc!(r#"
+            x-common: &common  # Shared options
+              release_matcher: app-*-linux-amd64.tar.gz
+              post: &restart systemctl --user restart app
+
+            tools:
+              first:
+                <<: *common
+                project: owner/first
+              second:
+                project: owner/second
+                post: *restart
+              third: &third {project: owner/third}
+        "#);
+
+        let (masked, placeholders) = mask_anchors(data);
+        assert_eq!(placeholders.len(), 5);
+        assert!(!masked.contains("&common") && !masked.contains("*restart"));
+        assert_eq!(unmask_anchors(masked.clone(), &placeholders), data);
+
+        let mut config = Config::read(data.as_bytes()).unwrap();
+        config.source.replace(ConfigSource {
+            path: PathBuf::new(),
+            data: data.as_bytes().to_vec(),
+            exists: true,
+            layer: Layer::User(None),
+        });
+
+        let first = config.tools.get("first").unwrap();
+        assert_eq!(first.project, ["owner/first"]);
+        assert_eq!(first.post.as_ref().unwrap().command, "systemctl --user restart app");
+        assert!(config.tools.get("second").unwrap().post == first.post);
+
+        let mut raw = yaml::from_slice(&masked).unwrap();
+        let spec = ToolSpec {
+            project: vec!["owner/fourth".to_owned()],
+            url: None,
+            ..first.clone()
+        };
+        config.update_tool(&mut raw, "fourth", &spec).unwrap();
+
+        let result = unmask_anchors(raw.to_string(), &placeholders);
+        assert!(result.starts_with(data));
+        assert_eq!(Config::read(result.as_bytes()).unwrap().tools.len(), 4);
+    }
+
+    #[test]
+    fn templates() {
+        let data = indoc!(r#"
+            templates:
+              exporter:
+                binary_matcher: bin/*_exporter
+                path: /opt/exporters
+                post: systemctl restart {name}
+              tree:
+                layout: tree
+
+            tools:
+              node_exporter:
+                extends: exporter
+                project: prometheus/node_exporter
+              blackbox_exporter:
+                extends: [exporter, tree]
+                project: prometheus/blackbox_exporter
+                post: systemctl restart blackbox
+        "#);
+
+        let mut config = Config::read(data.as_bytes()).unwrap();
+        config.source.replace(ConfigSource {
+            path: PathBuf::new(),
+            data: data.as_bytes().to_vec(),
+            exists: true,
+            layer: Layer::User(None),
+        });
+
+        let node = config.tools.get("node_exporter").unwrap();
+        assert_eq!(node.extends, ["exporter"]);
+        assert_eq!(node.binary_matcher.as_ref().unwrap().to_string(), "bin/*_exporter");
+        assert_eq!(node.path.as_deref(), Some(Path::new("/opt/exporters")));
+        assert!(node.layout == Layout::Binary);
+
+        let blackbox = config.tools.get("blackbox_exporter").unwrap();
+        assert!(blackbox.layout == Layout::Tree);
+        assert_eq!(blackbox.post.as_ref().unwrap().command, "systemctl restart blackbox");
+
+        let spec = ToolSpec {
+            project: vec!["owner/mysqld_exporter".to_owned()],
+            url: None,
+            ..node.clone()
+        };
+
+        let mut raw = yaml::from_slice(data).unwrap();
+        config.update_tool(&mut raw, "mysqld_exporter", &spec).unwrap();
+
+        let result = raw.to_string();
+        assert!(result.trim_end().ends_with(concat!(
+            "  mysqld_exporter:\n",
+            "    project: owner/mysqld_exporter\n",
+            "    extends: exporter",
+        )), "{result}");
+        assert!(Config::read(result.as_bytes()).unwrap().tools.get("mysqld_exporter").unwrap() == &spec);
+
+        for (data, error) in [
+            ("tools: {app: {project: owner/app, extends: unknown}}", r#"Invalid app tool: Unknown "unknown" template"#),
+            ("templates: {a: {extends: b}, b: {}}", "Invalid a template: templates can't extend other templates"),
+        ] {
+            assert_eq!(Config::read(data.as_bytes()).err().unwrap().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn effective() {
+        let data = indoc!(r#"
+            x-common: &common
+              binary_matcher: bin/app
+              download_headers:
+                Authorization: token secret
+
+            path: /opt/bin
+            state_path: /var/lib/binup/state.yaml
+
+            github:
+              token: secret
+
+            templates:
+              tree:
+                layout: tree
+
+            tools:
+              app:
+                <<: *common
+                extends: tree
+                project: owner/app
+        "#);
+
+        let mut config = Config::read(data.as_bytes()).unwrap();
+        config.network.ip_version = IpVersion::Ipv4;
+
+        let output = format_effective(&config, data.as_bytes(), &["network.ip_version"]).unwrap();
+        assert!(output.contains("    layout: tree  # template\n"), "{output}");
+        assert!(!output.contains("secret"));
+
+        for line in [
+            "path: /opt/bin  # config file\n",
+            "state_path: /var/lib/binup/state.yaml  # config file\n",
+            "config_backups: 5  # default\n",
+            "  token: <hidden>  # config file\n",
+            "  ip_version: ipv4  # command line\n",
+            "  app:  # config file\n",
+            "    project: owner/app  # config file\n",
+            "    extends: tree  # config file\n",
+            "    binary_matcher: bin/app  # merge key\n",
+            "    install_path: /opt/bin/app  # computed\n",
+        ] {
+            assert!(output.contains(line), "{line:?} is missing in:\n{output}");
+        }
+    }
+
+    #[test]
+    fn system() {
+        let system: Value = serde_yaml::from_str(indoc!(r#"
+            path: /usr/bin
+            netrc: true
+            tls:
+              ca_file: /etc/ssl/company.pem
+            network:
+              ip_version: ipv6
+            templates:
+              exporter:
+                path: /opt/exporters
+              tree:
+                layout: tree
+            tools:
+              shared:
+                project: owner/shared
+        "#)).unwrap();
+
+        let data = indoc!(r#"
+            network:
+              ip_version: ipv4
+            templates:
+              tree:
+                layout: binary
+            tools:
+              app:
+                extends: [exporter, tree]
+                project: owner/app
+        "#);
+
+        let layer = Layer::User(Some(system));
+        let config = Config::read_layer(data.as_bytes(), &layer).unwrap();
+
+        assert!(config.netrc);
+        assert_eq!(config.tls.ca_file.as_deref(), Some(Path::new("/etc/ssl/company.pem")));
+        assert!(config.network.ip_version == IpVersion::Ipv4);
+        assert_eq!(config.path, default_install_path());
+        assert_eq!(config.tools.keys().collect::<Vec<_>>(), ["app"]);
+
+        let app = config.tools.get("app").unwrap();
+        assert_eq!(app.path.as_deref(), Some(Path::new("/opt/exporters")));
+        assert!(app.layout == Layout::Binary);
+
+        let output = format_effective(&Config {
+            source: Some(ConfigSource {path: PathBuf::new(), data: data.as_bytes().to_vec(), exists: true, layer}),
+            ..config
+        }, data.as_bytes(), &[]).unwrap();
+
+        for line in [
+            "netrc: true  # system config\n",
+            "  ca_file: /etc/ssl/company.pem  # system config\n",
+            "  ip_version: ipv4  # config file\n",
+        ] {
+            assert!(output.contains(line), "{line:?} is missing in:\n{output}");
+        }
+
+        let config = Config::read_layer("tools: {}".as_bytes(), &Layer::System).unwrap();
+        assert_eq!(config.path, Path::new("/usr/local/bin"));
+        assert_eq!(config.cache_path, Path::new("/var/cache/binup"));
+    }
+}
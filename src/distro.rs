@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::env::consts;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+
+use log::debug;
+use serde::Deserialize;
+
+// Controls whether statically linked (static or musl) assets are preferred when a release has several builds for the
+// current platform
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StaticAssets {
+    // Prefer them on musl-based, very old glibc and unknown Linux distributions
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Display for StaticAssets {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            StaticAssets::Auto => "auto",
+            StaticAssets::Always => "always",
+            StaticAssets::Never => "never",
+        })
+    }
+}
+
+pub fn prefers_static_assets(mode: StaticAssets) -> bool {
+    match mode {
+        StaticAssets::Auto => {},
+        StaticAssets::Always => return true,
+        StaticAssets::Never => return false,
+    }
+
+    if consts::OS != "linux" {
+        return false;
+    }
+
+    let reason = if has_musl_loader() {
+        Some("musl dynamic loader is found")
+    } else {
+        match fs::read_to_string("/etc/os-release") {
+            Ok(data) => get_static_preference_reason(&data),
+            Err(err) => {
+                if err.kind() != ErrorKind::NotFound {
+                    debug!("Unable to read /etc/os-release: {err}.");
+                }
+                Some("the Linux distribution is unknown")
+            },
+        }
+    };
+
+    if let Some(reason) = reason {
+        debug!("Preferring statically linked assets: {reason}.");
+    }
+
+    reason.is_some()
+}
+
+fn has_musl_loader() -> bool {
+    fs::read_dir("/lib").map(|entries| entries.flatten().any(|entry| {
+        entry.file_name().to_str().is_some_and(|name| name.starts_with("ld-musl-"))
+    })).unwrap_or_default()
+}
+
+// Returns the reason to prefer statically linked assets on the distribution described by /etc/os-release
+fn get_static_preference_reason(os_release: &str) -> Option<&'static str> {
+    let info = parse_os_release(os_release);
+
+    let id = info.get("ID").map(String::as_str).unwrap_or_default();
+    let ids: Vec<&str> = [id].into_iter().chain(info.get("ID_LIKE").into_iter().flat_map(|ids| ids.split_whitespace()))
+        .filter(|id| !id.is_empty())
+        .collect();
+
+    if ids.is_empty() {
+        return Some("the Linux distribution is unknown");
+    }
+
+    if ids.iter().any(|id| matches!(*id, "alpine" | "chimera" | "postmarketos")) {
+        return Some("the Linux distribution is musl-based");
+    }
+
+    // The distributions which ship glibc older than 2.28 (most binaries are built against newer versions today)
+    let major_version = info.get("VERSION_ID")
+        .and_then(|version| version.split('.').next())
+        .and_then(|major| major.parse::<u32>().ok());
+
+    let min_version = if ids.iter().any(|id| matches!(*id, "rhel" | "centos")) {
+        8
+    } else if id == "debian" {
+        10
+    } else if id == "ubuntu" {
+        19
+    } else {
+        return None;
+    };
+
+    if major_version.is_some_and(|version| version < min_version) {
+        return Some("the Linux distribution has too old glibc");
+    }
+
+    None
+}
+
+fn parse_os_release(data: &str) -> BTreeMap<String, String> {
+    data.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let (key, value) = line.split_once('=')?;
+        let value = value.trim();
+        let value = value.strip_prefix('"').and_then(|value| value.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|value| value.strip_suffix('\'')))
+            .unwrap_or(value);
+
+        Some((key.trim().to_owned(), value.to_owned()))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(os_release, prefer,
+        case("ID=alpine\nVERSION_ID=3.20.3\n", true),
+        case("ID=\"centos\"\nID_LIKE=\"rhel fedora\"\nVERSION_ID=\"7\"\n", true),
+        case("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\nVERSION_ID=\"9.4\"\n", false),
+        case("ID=debian\nVERSION_ID=\"9\"\n", true),
+        case("ID=debian\nVERSION_ID=\"12\"\n", false),
+        case("ID=ubuntu\nID_LIKE=debian\nVERSION_ID=\"18.04\"\n", true),
+        case("ID=ubuntu\nID_LIKE=debian\nVERSION_ID=\"24.04\"\n", false),
+        case("ID=arch\n", false),
+        case("# Empty\n", true),
+    )]
+    fn static_preference(os_release: &str, prefer: bool) {
+        assert_eq!(get_static_preference_reason(os_release).is_some(), prefer);
+    }
+}
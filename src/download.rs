@@ -1,31 +1,238 @@
-use std::io::Read;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 
-use log::debug;
-use reqwest::blocking::ClientBuilder;
+use http::header;
+use log::{debug, warn};
+use openssl::sha::Sha256;
+use reqwest::blocking::{ClientBuilder, Response};
+use reqwest::redirect::Policy;
+use serde::Deserialize;
 use tar::{Archive, EntryType};
 use url::Url;
 
-use crate::core::{EmptyResult, GenericResult};
+use crate::core::{EmptyResult, GenericError, GenericResult};
+use crate::file_types;
+use crate::netrc::Netrc;
+use crate::network::NetworkConfig;
+use crate::tls::TlsConfig;
 use crate::util;
 
+// Receives regular files of the release archive. Implementations decide which files to extract and where to.
 pub trait Installer {
     fn on_file(&mut self, path: &Path, mode: u32, data: &mut dyn Read) -> EmptyResult;
+
+    // Called instead of on_file for raw binaries which are installed from a local file (the cache), so the installer
+    // may clone the file instead of writing its data
+    fn on_local_file(&mut self, path: &Path, mode: u32, data: &mut dyn Read, _source: &Path) -> EmptyResult {
+        self.on_file(path, mode, data)
+    }
+}
+
+const MAX_REDIRECTS: usize = 10;
+const MAX_CPIO_NAME_SIZE: u64 = 4096;
+
+// The number of bytes which is enough to check that the response looks like an archive
+const CONTENT_HEADER_SIZE: usize = 512;
+
+const DEB_MAGIC: &[u8; 8] = b"!<arch>\n";
+const RPM_MAGIC: &[u8; 4] = b"\xed\xab\xee\xdb";
+const RPM_HEADER_MAGIC: &[u8; 4] = b"\x8e\xad\xe8\x01";
+const XAR_MAGIC: &[u8; 4] = b"xar!";
+
+pub struct ExtractOptions<'a> {
+    pub decompressors: &'a BTreeMap<String, String>,
+    pub limits: &'a ExtractionLimits,
+}
+
+pub struct DownloadOptions<'a> {
+    pub headers: &'a BTreeMap<String, String>,
+    pub forward_auth_on_redirect: bool,
+    pub netrc: Option<&'a Netrc>,
+    pub tls: &'a TlsConfig,
+    pub network: &'a NetworkConfig,
+}
+
+// The downloaded archive is saved to the cache, but the cache isn't committed: it's up to the caller to commit it after
+// the archive is verified.
+pub fn download(
+    url: &Url, name: &str, download_options: &DownloadOptions, options: &ExtractOptions, cache: Option<&Cache>,
+    installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    let reader = ReleaseReaderBuilder::new(name, options.decompressors)?;
+    let mut response = send_request(url, download_options)?;
+
+    // Captive portals and authentication redirects return HTML pages with successful status
+    let content_type = response.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    if content_type.is_some_and(|content_type| content_type.trim_start().to_lowercase().starts_with("text/html")) {
+        return Err!("Got an HTML page instead of the archive (captive portal or authentication page?)");
+    }
+
+    let max_size = options.limits.max_download_size;
+    if let Some(size) = response.content_length().filter(|&size| size > max_size) {
+        return Err!("The archive is too big ({size} bytes). The maximum allowed size is {max_size} bytes");
+    }
+
+    let mut header = Vec::with_capacity(CONTENT_HEADER_SIZE);
+    response.by_ref().take(CONTENT_HEADER_SIZE as u64).read_to_end(&mut header)?;
+    reader.check_header(&header)?;
+
+    let data = SizeLimiter {
+        reader: io::Cursor::new(header).chain(response),
+        size: 0,
+        max_size,
+    };
+
+    let cache_file = cache.map(|cache| cache.file.try_clone()).transpose()?;
+    extract(reader, ChecksumReader::new(data, cache_file), None, options, installer)
+}
+
+// Downloads a small auxiliary release asset (like checksum file) into memory
+pub fn fetch(url: &Url, download_options: &DownloadOptions, max_size: u64) -> GenericResult<Vec<u8>> {
+    let response = send_request(url, download_options)?;
+
+    if let Some(size) = response.content_length().filter(|&size| size > max_size) {
+        return Err!("The file is too big ({size} bytes)");
+    }
+
+    let mut data = Vec::new();
+    response.take(max_size + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > max_size {
+        return Err!("The file is too big");
+    }
+
+    Ok(data)
 }
 
-pub fn download(url: &Url, name: &str, installer: &mut dyn Installer) -> EmptyResult {
-    let reader = ReleaseReaderBuilder::new(name)?;
-    let client = ClientBuilder::new().user_agent(util::USER_AGENT).build()?;
+fn send_request(url: &Url, download_options: &DownloadOptions) -> GenericResult<Response> {
+    let client = ClientBuilder::new()
+        .user_agent(util::USER_AGENT)
+        .redirect(Policy::none()); // We follow redirects manually to control which headers are forwarded
+    let client = download_options.network.configure_client(client);
+    let client = download_options.tls.configure_client(client)?.build()?;
 
     debug!("Downloading {url}...");
 
-    let response = client.get(url.to_owned()).send()?;
+    let mut url = url.to_owned();
+    let mut headers = download_options.headers.clone();
+    let mut redirects = 0;
+
+    let response = loop {
+        let mut request = client.get(url.clone());
+        for (name, value) in &headers {
+            request = request.header(name, value);
+        }
+
+        // Explicitly specified Authorization header takes precedence over .netrc
+        let credentials = download_options.netrc.filter(|_| !headers.keys().any(|name| name.eq_ignore_ascii_case(header::AUTHORIZATION.as_str())))
+            .and_then(|netrc| netrc.get(url.host_str()?));
+        if let Some(credentials) = credentials {
+            debug!("Using .netrc credentials for {}.", url.host_str().unwrap_or_default());
+            request = request.basic_auth(&credentials.login, credentials.password.as_ref());
+        }
+
+        let response = request.send()?;
+        if !response.status().is_redirection() {
+            break response;
+        }
+
+        let location = response.headers().get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| format!("The server returned {} without a valid redirect location", response.status()))?;
+
+        let location = url.join(location).map_err(|e| format!(
+            "The server returned an invalid redirect location ({location:?}): {e}"))?;
+
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err!("Too many redirects");
+        }
+
+        if should_strip_auth(&url, &location, download_options.forward_auth_on_redirect) {
+            let stripped: Vec<_> = headers.keys()
+                .filter(|name| is_sensitive_header(name))
+                .cloned().collect();
+
+            if !stripped.is_empty() {
+                debug!("Redirected to another origin. Stripping the following headers: {}.", stripped.join(", "));
+                headers.retain(|name, _| !is_sensitive_header(name));
+            }
+        }
+
+        debug!("{} -> {location} ({})", url, response.status());
+        url = location;
+    };
+
     if !response.status().is_success() {
         return Err!("The server returned and error: {}", response.status())
     }
 
-    let mut archive = reader.build(response);
+    Ok(response)
+}
+
+// Credentials are never sent over plain HTTP after HTTPS, even if forwarding to other origins is allowed
+fn should_strip_auth(url: &Url, location: &Url, forward_auth_on_redirect: bool) -> bool {
+    if url.scheme() == "https" && location.scheme() != "https" {
+        return true;
+    }
+
+    !forward_auth_on_redirect && (
+        location.scheme() != url.scheme() ||
+        location.host_str() != url.host_str() ||
+        location.port_or_known_default() != url.port_or_known_default()
+    )
+}
 
+// Extracts the archive which has been previously saved to the cache
+pub fn extract_cached(
+    path: &Path, name: &str, options: &ExtractOptions, installer: &mut dyn Installer,
+) -> GenericResult<String> {
+    debug!("Extracting {path:?}...");
+
+    let reader = ReleaseReaderBuilder::new(name, options.decompressors)?;
+    let file = File::open(path).map_err(|e| format!("Unable to open {path:?}: {e}"))?;
+
+    let (checksum, _) = extract(reader, ChecksumReader::new(file, None), Some(path), options, installer)?;
+    Ok(checksum)
+}
+
+pub fn get_checksum(path: &Path) -> GenericResult<String> {
+    let file = File::open(path).map_err(|e| format!("Unable to open {path:?}: {e}"))?;
+    let (checksum, _) = ChecksumReader::new(file, None).finish().map_err(|e| format!("Unable to read {path:?}: {e}"))?;
+    Ok(checksum)
+}
+
+fn extract(
+    reader: ReleaseReaderBuilder, checksum: ChecksumReader, source: Option<&Path>, options: &ExtractOptions,
+    installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    match reader.package {
+        Some(Package::Deb) => return extract_deb(checksum, options, installer),
+        Some(Package::Rpm) => return extract_rpm(checksum, options, installer),
+        Some(package @ (Package::Dmg | Package::Pkg)) => {
+            return extract_macos_package(package, &reader.name, checksum, source, options.limits, installer);
+        },
+        None => {},
+    }
+
+    if reader.decoder_builder.is_none() {
+        return extract_raw(reader, checksum, source, options.limits, installer);
+    } else if reader.single_file {
+        return extract_compressed_raw(reader, checksum, options.limits, installer);
+    }
+
+    let archive = reader.build(checksum.clone(), options.limits)?;
+    extract_tar(archive, options.limits, installer)?;
+
+    checksum.finish()
+}
+
+fn extract_tar<R: Read>(mut archive: Archive<R>, limits: &ExtractionLimits, installer: &mut dyn Installer) -> EmptyResult {
     for (index, entry) in archive.entries()?.enumerate() {
         let mut entry = entry?;
 
@@ -38,6 +245,13 @@ pub fn download(url: &Url, name: &str, installer: &mut dyn Installer) -> EmptyRe
         }
         debug!("* {path:?} ({entry_type:?})");
 
+        let size = header.size()?;
+        if size > limits.max_entry_size {
+            return Err!(
+                "{path:?} in the archive is too big ({size} bytes). The maximum allowed size is {} bytes",
+                limits.max_entry_size);
+        }
+
         if matches!(entry_type, EntryType::Regular | EntryType::Continuous) {
             let path = path.to_path_buf();
             let mode = header.mode()?;
@@ -48,34 +262,1068 @@ pub fn download(url: &Url, name: &str, installer: &mut dyn Installer) -> EmptyRe
     Ok(())
 }
 
-type DecoderBuilder = Box<dyn FnOnce(Box<dyn Read>) -> Box<dyn Read>>;
+// Debian packages are ar archives with the package files in (possibly compressed) data.tar member
+fn extract_deb(
+    checksum: ChecksumReader, options: &ExtractOptions, installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    let mut reader = checksum.clone();
+
+    let mut magic = [0; DEB_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != *DEB_MAGIC {
+        return Err!("Invalid Debian package: it's not an ar archive");
+    }
+
+    loop {
+        // Member header: name (16 bytes), modification time (12), owner (6), group (6), mode (8), size (10), magic (2)
+        let mut header = [0; 60];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => "Invalid Debian package: it has no data archive".into(),
+            _ => GenericError::from(e),
+        })?;
+
+        let name = String::from_utf8_lossy(&header[..16]).trim_end().trim_end_matches('/').to_owned();
+        let size: u64 = std::str::from_utf8(&header[48..58]).ok()
+            .and_then(|size| size.trim_end().parse().ok())
+            .ok_or_else(|| format!("Invalid Debian package: invalid size of {name:?} member"))?;
+
+        if !name.starts_with("data.tar") {
+            // Members are aligned to even offsets
+            io::copy(&mut (&mut reader).take(size + size % 2), &mut io::sink())?;
+            continue;
+        }
+
+        debug!("Processing {name:?} of the Debian package.");
+
+        let mut data_reader = ReleaseReaderBuilder::new(&name, options.decompressors)?;
+        if name == "data.tar" {
+            data_reader.decoder_builder = Some(Box::new(|reader| Ok(Box::new(reader))));
+        } else if data_reader.decoder_builder.is_none() {
+            return Err!("Unsupported Debian package data archive: {name:?}");
+        }
+
+        let archive = data_reader.build_from(Box::new(reader.take(size)), checksum.clone(), options.limits)?;
+        extract_tar(archive, options.limits, installer)?;
+        break;
+    }
+
+    checksum.finish()
+}
+
+// RPM packages consist of lead, signature and header sections followed by compressed cpio archive with the package files
+fn extract_rpm(
+    checksum: ChecksumReader, options: &ExtractOptions, installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    let mut reader = checksum.clone();
+
+    let mut lead = [0; 96];
+    read_package(&mut reader, &mut lead)?;
+    if !lead.starts_with(RPM_MAGIC) {
+        return Err!("Invalid RPM package: invalid lead");
+    }
+
+    // The signature section is padded to 8 bytes
+    let signature_size = skip_rpm_header(&mut reader)?;
+    io::copy(&mut (&mut reader).take((8 - signature_size % 8) % 8), &mut io::sink())?;
+    skip_rpm_header(&mut reader)?;
+
+    let mut magic = Vec::with_capacity(6);
+    (&mut reader).take(6).read_to_end(&mut magic)?;
+
+    let extension = if magic.starts_with(b"\x1f\x8b") {
+        "gz"
+    } else if magic.starts_with(b"\xfd7zXZ\x00") {
+        "xz"
+    } else if magic.starts_with(b"BZh") {
+        "bz2"
+    } else if magic.starts_with(b"\x28\xb5\x2f\xfd") {
+        "zst"
+    } else if magic.starts_with(b"\x5d\x00\x00") {
+        "lzma"
+    } else {
+        "cpio"
+    };
+
+    let decoder_builder: DecoderBuilder = match extension {
+        "cpio" => Box::new(|reader| Ok(Box::new(reader))),
+        _ => get_external_decoder(&format!("payload.cpio.{extension}"), options.decompressors)
+            .or_else(|| get_builtin_decoder(extension).map(|(decoder, _)| decoder))
+            .ok_or_else(|| format!(concat!(
+                "Unsupported RPM payload compression: {}. ",
+                "It may be decompressed by an external decompressor configured for cpio.{}"), extension, extension))?,
+    };
+
+    debug!("Processing {extension} payload of the RPM package.");
+
+    let mut payload = RatioLimiter {
+        reader: decoder_builder(Box::new(io::Cursor::new(magic).chain(reader)))?,
+        compressed: checksum.clone(),
+        decompressed: 0,
+        max_ratio: options.limits.max_compression_ratio,
+    };
+    extract_cpio(&mut payload, options.limits, installer)?;
+
+    drop(payload);
+    checksum.finish()
+}
+
+// Skips RPM header structure and returns its size
+fn skip_rpm_header(reader: &mut dyn Read) -> GenericResult<u64> {
+    let mut header = [0; 16];
+    read_package(reader, &mut header)?;
+    if !header.starts_with(RPM_HEADER_MAGIC) {
+        return Err!("Invalid RPM package: invalid header");
+    }
+
+    let entries = u64::from(u32::from_be_bytes(header[8..12].try_into().unwrap()));
+    let data_size = u64::from(u32::from_be_bytes(header[12..16].try_into().unwrap()));
+    let size = entries * 16 + data_size;
+
+    if io::copy(&mut reader.take(size), &mut io::sink())? != size {
+        return Err!("Invalid RPM package: unexpected end of file");
+    }
+
+    Ok(header.len() as u64 + size)
+}
+
+fn read_package(reader: &mut dyn Read, buf: &mut [u8]) -> EmptyResult {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => "Invalid RPM package: unexpected end of file".into(),
+        _ => GenericError::from(e),
+    })
+}
+
+// Passes regular files of the cpio archive in new ASCII format (the one which is used by RPM) to the installer
+fn extract_cpio(reader: &mut dyn Read, limits: &ExtractionLimits, installer: &mut dyn Installer) -> EmptyResult {
+    let padding = |size: u64| (4 - size % 4) % 4;
+    let mut index = 0;
+
+    loop {
+        let mut header = [0; 110];
+        reader.read_exact(&mut header)?;
+        if !header.starts_with(b"070701") && !header.starts_with(b"070702") {
+            return Err!("Invalid cpio archive");
+        }
+
+        let field = |index: usize| -> GenericResult<u64> {
+            let offset = 6 + index * 8;
+            std::str::from_utf8(&header[offset..offset + 8]).ok()
+                .and_then(|value| u64::from_str_radix(value, 16).ok())
+                .ok_or_else(|| "Invalid cpio archive: invalid entry header".into())
+        };
+        let (mode, size, name_size) = (field(1)?, field(6)?, field(11)?);
+        if name_size > MAX_CPIO_NAME_SIZE {
+            return Err!("Invalid cpio archive: too long entry name ({name_size} bytes)");
+        }
+
+        let mut name = vec![0; name_size as usize];
+        reader.read_exact(&mut name)?;
+        io::copy(&mut reader.take(padding(header.len() as u64 + name_size)), &mut io::sink())?;
+
+        let name = String::from_utf8_lossy(&name).trim_end_matches('\0').to_owned();
+        if name == "TRAILER!!!" {
+            break;
+        }
+
+        // RPM payloads store paths relative to the current directory, so normalize them like tar does
+        let path = Path::new(name.trim_start_matches("./"));
+        let regular = mode & 0o170000 == 0o100000;
+
+        if index == 0 {
+            debug!("Processing the archive:")
+        }
+        debug!("* {path:?} ({})", if regular { "regular" } else { "other" });
+        index += 1;
+
+        if size > limits.max_entry_size {
+            return Err!(
+                "{path:?} in the archive is too big ({size} bytes). The maximum allowed size is {} bytes",
+                limits.max_entry_size);
+        }
+
+        let mut data = reader.take(size);
+        if regular {
+            installer.on_file(path, (mode & 0o7777) as u32, &mut data)?;
+        }
+        io::copy(&mut data, &mut io::sink())?;
+        io::copy(&mut reader.take(padding(size)), &mut io::sink())?;
+    }
+
+    Ok(())
+}
+
+// macOS disk images and installer packages can't be streamed, so they are saved to a temporary directory and unpacked
+// by the system tools: disk images are mounted with hdiutil and installer packages are expanded with pkgutil
+fn extract_macos_package(
+    package: Package, name: &str, checksum: ChecksumReader, source: Option<&Path>, limits: &ExtractionLimits,
+    installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    if !cfg!(target_os = "macos") {
+        return Err!("{name:?} can be installed only on macOS");
+    }
+
+    let temp_dir = util::TempDir::new()?;
+
+    let path = match source {
+        Some(source) => source.to_owned(),
+        None => {
+            let path = temp_dir.path.join(name);
+            let mut file = File::create(&path).map_err(|e| format!("Unable to create {path:?}: {e}"))?;
+            io::copy(&mut checksum.clone(), &mut file)?;
+            path
+        },
+    };
+
+    match package {
+        Package::Dmg => {
+            let mount_path = temp_dir.path.join("mount");
+            debug!("Mounting {name:?} to {mount_path:?}...");
+
+            // Disk images with license agreement ask to accept it on stdin
+            run_command(Command::new("hdiutil")
+                .args(["attach", "-readonly", "-nobrowse", "-noautoopen", "-noverify", "-mountpoint"])
+                .args([&mount_path, &path]), b"Y\n")?;
+
+            let result = extract_dir(&mount_path, limits, installer);
+            if let Err(err) = run_command(Command::new("hdiutil").args(["detach", "-force"]).arg(&mount_path), b"") {
+                warn!("Failed to unmount {mount_path:?}: {err}.");
+            }
+            result?;
+        },
+
+        Package::Pkg => {
+            let expanded_path = temp_dir.path.join("expanded");
+            debug!("Expanding {name:?} to {expanded_path:?}...");
+
+            // Expands all component packages including their payload archives
+            run_command(Command::new("pkgutil").arg("--expand-full").args([&path, &expanded_path]), b"")?;
+            extract_dir(&expanded_path, limits, installer)?;
+        },
+
+        Package::Deb | Package::Rpm => unreachable!(),
+    }
+
+    checksum.finish()
+}
+
+fn run_command(command: &mut Command, input: &[u8]) -> EmptyResult {
+    let name = command.get_program().to_string_lossy().to_string();
+
+    let mut process = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn()
+        .map_err(|e| format!("Unable to execute {name}: {e}"))?;
+
+    // The process may exit without reading the input
+    let _ = process.stdin.take().unwrap().write_all(input);
+
+    let output = process.wait_with_output().map_err(|e| format!("Failed to execute {name}: {e}"))?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err!("{name} returned an error ({}): {}", output.status, error.trim());
+    }
+
+    Ok(())
+}
+
+// Passes regular files of the unpacked directory tree to the installer as archive entries. Symlinks are skipped.
+fn extract_dir(root: &Path, limits: &ExtractionLimits, installer: &mut dyn Installer) -> EmptyResult {
+    debug!("Processing the unpacked files:");
+
+    let mut directories = vec![root.to_owned()];
+
+    while let Some(directory) = directories.pop() {
+        let mut entries = fs::read_dir(&directory)
+            .and_then(|entries| entries.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>())
+            .map_err(|e| format!("Unable to read {directory:?}: {e}"))?;
+        entries.sort();
+
+        for path in entries.into_iter().rev() {
+            let metadata = fs::symlink_metadata(&path).map_err(|e| format!("Unable to stat {path:?}: {e}"))?;
+            let relative_path = path.strip_prefix(root).unwrap().to_owned();
+            debug!("* {relative_path:?} ({:?})", metadata.file_type());
+
+            if metadata.is_dir() {
+                directories.push(path);
+                continue;
+            } else if !metadata.is_file() {
+                continue;
+            }
+
+            let size = metadata.len();
+            if size > limits.max_entry_size {
+                return Err!(
+                    "{relative_path:?} in the archive is too big ({size} bytes). The maximum allowed size is {} bytes",
+                    limits.max_entry_size);
+            }
+
+            let mut file = File::open(&path).map_err(|e| format!("Unable to open {path:?}: {e}"))?;
+            installer.on_file(&relative_path, metadata.permissions().mode() & 0o7777, &mut file)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Passes the raw (unarchived) binary to the installer as the only file named after the asset
+fn extract_raw(
+    reader: ReleaseReaderBuilder, checksum: ChecksumReader, source: Option<&Path>, limits: &ExtractionLimits,
+    installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    let mut header = Vec::with_capacity(CONTENT_HEADER_SIZE);
+    checksum.clone().take(CONTENT_HEADER_SIZE as u64).read_to_end(&mut header)?;
+    reader.check_header(&header)?;
+
+    debug!("Processing {:?} as a raw binary.", reader.name);
+
+    let mut data = SizeLimiter {
+        reader: io::Cursor::new(header).chain(checksum.clone()),
+        size: 0,
+        max_size: limits.max_entry_size,
+    };
+    match source {
+        Some(source) => {
+            // The installer may clone the local file without reading its data, so the size limit is checked in advance
+            let size = fs::metadata(source).map_err(|e| format!("Unable to stat {source:?}: {e}"))?.len();
+            if size > limits.max_entry_size {
+                return Err!("The archive is too big. The maximum allowed size is {} bytes", limits.max_entry_size);
+            }
+            installer.on_local_file(Path::new(&reader.name), 0o755, &mut data, source)?
+        },
+        None => installer.on_file(Path::new(&reader.name), 0o755, &mut data)?,
+    }
+
+    drop(data);
+    checksum.finish()
+}
+
+fn extract_compressed_raw(
+    reader: ReleaseReaderBuilder, checksum: ChecksumReader, limits: &ExtractionLimits, installer: &mut dyn Installer,
+) -> GenericResult<(String, u64)> {
+    // The decompressed file is named as the asset without the compression extension
+    let name = reader.name.rsplit_once('.').map(|(name, _)| name.to_owned()).unwrap_or_default();
+    debug!("Processing {:?} as a compressed raw binary.", reader.name);
+
+    let mut data = reader.build(checksum.clone(), limits)?.into_inner();
+
+    let mut header = Vec::with_capacity(CONTENT_HEADER_SIZE);
+    (&mut data).take(CONTENT_HEADER_SIZE as u64).read_to_end(&mut header)?;
+    if !file_types::is_executable(&header) {
+        return Err!("Unsupported file type: decompressed {name:?} is not an executable");
+    }
+
+    let mut data = SizeLimiter {
+        reader: io::Cursor::new(header).chain(data),
+        size: 0,
+        max_size: limits.max_entry_size,
+    };
+    installer.on_file(Path::new(&name), 0o755, &mut data)?;
+
+    drop(data);
+    checksum.finish()
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    [header::AUTHORIZATION, header::COOKIE, header::PROXY_AUTHORIZATION].iter()
+        .any(|sensitive| sensitive.as_str().eq_ignore_ascii_case(name))
+}
+
+// Calculates SHA-256 of the whole downloaded file including the trailing data which archive reader might not consume
+#[derive(Clone)]
+struct ChecksumReader {
+    state: Arc<Mutex<ChecksumState>>,
+}
+
+struct ChecksumState {
+    reader: Box<dyn Read + Send>,
+    hasher: Sha256,
+    size: u64,
+    copy: Option<File>,
+}
+
+impl ChecksumReader {
+    fn new<R: Read + Send + 'static>(reader: R, copy: Option<File>) -> ChecksumReader {
+        ChecksumReader {
+            state: Arc::new(Mutex::new(ChecksumState {
+                reader: Box::new(reader),
+                hasher: Sha256::new(),
+                size: 0,
+                copy,
+            })),
+        }
+    }
+
+    // Returns checksum and size of the data
+    fn finish(mut self) -> GenericResult<(String, u64)> {
+        io::copy(&mut self, &mut io::sink())?;
+
+        let state = Arc::into_inner(self.state).expect("Checksum reader is still in use")
+            .into_inner().unwrap();
+        if let Some(mut copy) = state.copy {
+            copy.flush()?;
+        }
+
+        Ok((state.hasher.finish().iter().map(|byte| format!("{byte:02x}")).collect(), state.size))
+    }
+
+    fn size(&self) -> u64 {
+        self.state.lock().unwrap().size
+    }
+}
+
+impl Read for ChecksumReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        let size = state.reader.read(buf)?;
+        state.hasher.update(&buf[..size]);
+        state.size += size as u64;
+        if let Some(copy) = state.copy.as_mut() {
+            copy.write_all(&buf[..size])?;
+        }
+        Ok(size)
+    }
+}
+
+// Saves a copy of the downloaded archive to be able to reinstall the tool without network transfer. Only the last
+// downloaded archive is kept. The previous one is replaced only on commit.
+pub struct Cache {
+    path: PathBuf,
+    temp_path: PathBuf,
+    file: File,
+    committed: bool,
+}
+
+impl Cache {
+    pub fn new(path: &Path) -> GenericResult<Cache> {
+        let directory = path.parent().ok_or_else(|| format!("Invalid cache path: {path:?}"))?;
+        fs::create_dir_all(directory).map_err(|e| format!("Unable to create {directory:?}: {e}"))?;
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".new");
+        let temp_path = PathBuf::from(temp_path);
+
+        let file = File::create(&temp_path).map_err(|e| format!("Unable to create {temp_path:?}: {e}"))?;
+
+        Ok(Cache {
+            path: path.to_owned(),
+            temp_path,
+            file,
+            committed: false,
+        })
+    }
+
+    pub fn commit(mut self) -> EmptyResult {
+        let directory = self.path.parent().unwrap();
+
+        for entry in fs::read_dir(directory).map_err(|e| format!("Unable to read {directory:?}: {e}"))? {
+            let path = entry.map_err(|e| format!("Unable to read {directory:?}: {e}"))?.path();
+            if path != self.temp_path {
+                fs::remove_file(&path).map_err(|e| format!("Unable to delete {path:?}: {e}"))?;
+            }
+        }
+
+        fs::rename(&self.temp_path, &self.path).map_err(|e| format!(
+            "Unable to rename {:?} to {:?}: {e}", self.temp_path, self.path))?;
+
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(err) = fs::remove_file(&self.temp_path) {
+                debug!("Unable to delete {:?}: {err}.", self.temp_path);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractionLimits {
+    #[serde(default = "default_max_compression_ratio")]
+    pub max_compression_ratio: u64,
+    #[serde(default = "default_max_entry_size")]
+    pub max_entry_size: u64,
+    #[serde(default = "default_max_download_size")]
+    pub max_download_size: u64,
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> ExtractionLimits {
+        ExtractionLimits {
+            max_compression_ratio: default_max_compression_ratio(),
+            max_entry_size: default_max_entry_size(),
+            max_download_size: default_max_download_size(),
+        }
+    }
+}
+
+fn default_max_compression_ratio() -> u64 {
+    100
+}
+
+fn default_max_entry_size() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_max_download_size() -> u64 {
+    1024 * 1024 * 1024
+}
+
+// Limits the downloaded data size when the server doesn't specify it or lies about it
+struct SizeLimiter<R: Read> {
+    reader: R,
+    size: u64,
+    max_size: u64,
+}
+
+impl<R: Read> Read for SizeLimiter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.size += size as u64;
+
+        if self.size > self.max_size {
+            return Err(io::Error::other(format!(
+                "The archive is too big. The maximum allowed size is {} bytes", self.max_size)));
+        }
+
+        Ok(size)
+    }
+}
+
+// Protects from decompression bombs by limiting decompressed to compressed data ratio
+struct RatioLimiter<R: Read> {
+    reader: R,
+    compressed: ChecksumReader,
+    decompressed: u64,
+    max_ratio: u64,
+}
+
+impl<R: Read> RatioLimiter<R> {
+    // Small archives may have a high compression ratio, so start to check the ratio only after this threshold
+    const THRESHOLD: u64 = 10 * 1024 * 1024;
+}
+
+impl<R: Read> Read for RatioLimiter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.decompressed += size as u64;
+
+        if self.decompressed > Self::THRESHOLD {
+            let compressed = self.compressed.size();
+
+            if self.decompressed > compressed.saturating_mul(self.max_ratio) {
+                return Err(io::Error::other(format!(
+                    "The archive has a suspiciously high compression ratio (more than {}:1)", self.max_ratio)));
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+type DecoderBuilder = Box<dyn FnOnce(Box<dyn Read + Send>) -> GenericResult<Box<dyn Read>>>;
 
 struct ReleaseReaderBuilder {
-    decoder_builder: DecoderBuilder,
+    name: String,
+    // None for files without a known archive extension, which may be raw (unarchived) binaries
+    decoder_builder: Option<DecoderBuilder>,
+    // Magic bytes of the compressed data if it's decompressed by a builtin decoder
+    magic: Option<&'static [u8]>,
+    // Package which is extracted from its payload archive
+    package: Option<Package>,
+    // Compressed single file (like tool-linux-amd64.gz) which is decompressed into a raw binary instead of tar archive
+    single_file: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Package {
+    Deb,
+    Rpm,
+    // macOS disk image
+    Dmg,
+    // macOS installer package
+    Pkg,
 }
 
 impl ReleaseReaderBuilder {
-    fn new(name: &str) -> GenericResult<ReleaseReaderBuilder> {
-        let decoder_builder = name.rsplit_once('.').and_then(|(name, extension)| {
-            let decoder: DecoderBuilder = match extension {
-                "bz2" => Box::new(|reader| Box::new(bzip2::read::BzDecoder::new(reader))),
-                "gz" => Box::new(|reader| Box::new(flate2::read::GzDecoder::new(reader))),
-                "xz" => Box::new(|reader| Box::new(xz2::read::XzDecoder::new(reader))),
-                _ => return None,
-            };
-
-            if name.rsplit_once('.')?.1 != "tar" {
-                return None;
+    fn new(name: &str, decompressors: &BTreeMap<String, String>) -> GenericResult<ReleaseReaderBuilder> {
+        // External decompressors are checked first to allow to override the builtin ones
+        if let Some(decoder_builder) = get_external_decoder(name, decompressors) {
+            return Ok(ReleaseReaderBuilder {
+                name: name.to_owned(),
+                decoder_builder: Some(decoder_builder),
+                magic: None,
+                package: None,
+                single_file: false,
+            });
+        }
+
+        // Disk images have no magic bytes at the beginning of the file
+        for (extension, package, magic) in [
+            ("deb", Package::Deb, Some(DEB_MAGIC.as_slice())),
+            ("rpm", Package::Rpm, Some(RPM_MAGIC.as_slice())),
+            ("dmg", Package::Dmg, None),
+            ("pkg", Package::Pkg, Some(XAR_MAGIC.as_slice())),
+        ] {
+            if name.strip_suffix(extension).is_some_and(|name| name.ends_with('.')) {
+                return Ok(ReleaseReaderBuilder {
+                    name: name.to_owned(),
+                    decoder_builder: None,
+                    magic,
+                    package: Some(package),
+                    single_file: false,
+                });
             }
+        }
+
+        let (decoder, single_file) = match name.rsplit_once('.') {
+            Some((stem, extension)) => {
+                let single_file = stem.rsplit_once('.').is_none_or(|(_, extension)| extension != "tar");
+                (get_builtin_decoder(extension), single_file)
+            },
+            None => (None, false),
+        };
 
-            Some(decoder)
-        }).ok_or_else(|| format!("Unsupported file type: {name:?}"))?;
+        let (decoder_builder, magic) = decoder.unzip();
 
-        Ok(ReleaseReaderBuilder {decoder_builder})
+        Ok(ReleaseReaderBuilder {
+            name: name.to_owned(),
+            single_file: single_file && decoder_builder.is_some(),
+            decoder_builder,
+            magic,
+            package: None,
+        })
     }
 
-    fn build<R: Read + 'static>(self, reader: R) -> Archive<impl Read> {
-        let reader = (self.decoder_builder)(Box::new(reader));
-        Archive::new(reader)
+    // Checks that the data looks like an archive to produce a clear error instead of a decoder one
+    fn check_header(&self, header: &[u8]) -> EmptyResult {
+        let text = String::from_utf8_lossy(header).trim_start().to_lowercase();
+        if text.starts_with("<!doctype html") || text.starts_with("<html") {
+            return Err!("Got an HTML page instead of the archive (captive portal or authentication page?)");
+        }
+
+        if let Some(magic) = self.magic {
+            if !header.starts_with(magic) {
+                return Err!("The downloaded file doesn't look like an archive of the expected type");
+            }
+        }
+
+        if self.decoder_builder.is_none() && self.package.is_none() && !file_types::is_executable(header) {
+            return Err!("Unsupported file type: {:?} is neither a supported archive nor an executable", self.name);
+        }
+
+        Ok(())
+    }
+
+    fn build(self, reader: ChecksumReader, limits: &ExtractionLimits) -> GenericResult<Archive<impl Read>> {
+        self.build_from(Box::new(reader.clone()), reader, limits)
+    }
+
+    // Builds the archive reader for the data which may be a part of the downloaded file (like Debian package member)
+    fn build_from(
+        self, data: Box<dyn Read + Send>, compressed: ChecksumReader, limits: &ExtractionLimits,
+    ) -> GenericResult<Archive<impl Read>> {
+        let decoder_builder = self.decoder_builder.ok_or_else(|| format!("{:?} is not an archive", self.name))?;
+        let decoder = decoder_builder(data)?;
+
+        Ok(Archive::new(RatioLimiter {
+            reader: decoder,
+            compressed,
+            decompressed: 0,
+            max_ratio: limits.max_compression_ratio,
+        }))
+    }
+}
+
+fn get_external_decoder(name: &str, decompressors: &BTreeMap<String, String>) -> Option<DecoderBuilder> {
+    let command = decompressors.iter()
+        .find(|(extension, _)| name.strip_suffix(extension.as_str()).is_some_and(|name| name.ends_with('.')))
+        .map(|(_, command)| command.clone())?;
+
+    Some(Box::new(move |reader| {
+        Ok(Box::new(ExternalDecoder::new(&command, reader)?))
+    }))
+}
+
+// Returns the decoder for the compression format extension and its magic bytes
+fn get_builtin_decoder(extension: &str) -> Option<(DecoderBuilder, &'static [u8])> {
+    Some(match extension {
+        "bz2" => (Box::new(|reader| Ok(Box::new(bzip2::read::BzDecoder::new(reader)))), b"BZh"),
+        "gz" => (Box::new(|reader| Ok(Box::new(flate2::read::GzDecoder::new(reader)))), b"\x1f\x8b"),
+        "xz" => (Box::new(|reader| Ok(Box::new(xz2::read::XzDecoder::new(reader)))), b"\xfd7zXZ\x00"),
+        _ => return None,
+    })
+}
+
+// Decompresses the data by piping it through an external command
+struct ExternalDecoder {
+    command: String,
+    process: Child,
+    stdout: Option<ChildStdout>,
+    writer: Option<JoinHandle<io::Result<u64>>>,
+}
+
+impl ExternalDecoder {
+    fn new(command: &str, mut reader: Box<dyn Read + Send>) -> GenericResult<ExternalDecoder> {
+        debug!("Decompressing the archive with `{command}`...");
+
+        let mut process = Command::new("bash").args(["-c", command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to execute `{command}`: unable to spawn bash process: {e}"))?;
+
+        let mut stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let writer = thread::spawn(move || io::copy(&mut reader, &mut stdin));
+
+        Ok(ExternalDecoder {
+            command: command.to_owned(),
+            process,
+            stdout: Some(stdout),
+            writer: Some(writer),
+        })
     }
-}
\ No newline at end of file
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.stdout.take();
+
+        let Some(writer) = self.writer.take() else {
+            return Ok(());
+        };
+
+        let result = writer.join().map_err(|_| io::Error::other("Decompressor writer thread has panicked"))?;
+        let status = self.process.wait()?;
+
+        if !status.success() {
+            return Err(io::Error::other(format!("`{}` returned an error: {status}", self.command)));
+        }
+
+        match result {
+            Ok(_) => Ok(()),
+            // The decompressor may not read the trailing data
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl Read for ExternalDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(stdout) = self.stdout.as_mut() else {
+            return Ok(0);
+        };
+
+        let size = stdout.read(buf)?;
+        if size == 0 && !buf.is_empty() {
+            self.finish()?;
+        }
+
+        Ok(size)
+    }
+}
+
+impl Drop for ExternalDecoder {
+    fn drop(&mut self) {
+        if let Err(err) = self.finish() {
+            debug!("{err}.");
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use rstest::rstest;
+    use tar::{Builder, Header};
+
+    use super::*;
+
+    #[rstest(url, location, forward_auth_on_redirect, expected,
+        case("https://example.com/file", "https://example.com/other", false, false),
+        case("https://example.com/file", "https://example.com:443/other", false, false),
+        case("https://example.com/file", "https://other.com/file", false, true),
+        case("https://example.com/file", "https://other.com/file", true, false),
+        case("https://example.com/file", "https://example.com:8443/file", false, true),
+        case("http://example.com/file", "https://example.com/file", false, true),
+        case("http://example.com/file", "https://example.com/file", true, false),
+        case("https://example.com/file", "http://example.com/file", false, true),
+        case("https://example.com/file", "http://example.com/file", true, true),
+    )]
+    fn redirect_auth_stripping(url: &str, location: &str, forward_auth_on_redirect: bool, expected: bool) {
+        let (url, location) = (Url::parse(url).unwrap(), Url::parse(location).unwrap());
+        assert_eq!(should_strip_auth(&url, &location, forward_auth_on_redirect), expected);
+    }
+
+    #[test]
+    fn external_decompressor() {
+        let mut archive = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+
+        let data = b"#!/bin/sh\n";
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        archive.append_data(&mut header, "tool", data.as_slice()).unwrap();
+
+        let mut compressed = archive.into_inner().unwrap().finish().unwrap();
+        compressed.write_all(b"trailing data").unwrap();
+
+        let decompressors = BTreeMap::from([("tar.gz".to_owned(), "gzip -dq".to_owned())]);
+        let reader = ReleaseReaderBuilder::new("tool.tar.gz", &decompressors).unwrap();
+        let checksum = ChecksumReader::new(io::Cursor::new(compressed.clone()), None);
+        let mut archive = reader.build(checksum.clone(), &ExtractionLimits::default()).unwrap();
+
+        let mut files = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).unwrap();
+            files.push((entry.path().unwrap().to_path_buf(), contents));
+        }
+        assert_eq!(files, vec![(Path::new("tool").to_owned(), data.to_vec())]);
+
+        drop(archive);
+
+        let mut expected = Sha256::new();
+        expected.update(&compressed);
+        let expected: String = expected.finish().iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(checksum.finish().unwrap(), (expected, compressed.len() as u64));
+    }
+
+    #[test]
+    fn content_check() {
+        let reader = ReleaseReaderBuilder::new("tool.tar.gz", &BTreeMap::new()).unwrap();
+        reader.check_header(b"\x1f\x8b\x08\x00").unwrap();
+        assert!(reader.check_header(b"PK\x03\x04").is_err());
+
+        let error = reader.check_header(b"\n  <!DOCTYPE html>\n<html>").unwrap_err().to_string();
+        assert!(error.contains("HTML page"), "{error}");
+    }
+
+    #[derive(Default)]
+    struct Collector {
+        files: Vec<(PathBuf, Vec<u8>)>,
+    }
+
+    impl Installer for Collector {
+        fn on_file(&mut self, path: &Path, _mode: u32, data: &mut dyn Read) -> EmptyResult {
+            let mut contents = Vec::new();
+            data.read_to_end(&mut contents)?;
+            self.files.push((path.to_owned(), contents));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn raw_binary() {
+        let data = b"#!/bin/sh\n";
+        let reader = ReleaseReaderBuilder::new("tool-linux-amd64", &BTreeMap::new()).unwrap();
+        let checksum = ChecksumReader::new(io::Cursor::new(data.to_vec()), None);
+
+        let options = ExtractOptions {decompressors: &BTreeMap::new(), limits: &ExtractionLimits::default()};
+
+        let mut collector = Collector::default();
+        extract(reader, checksum, None, &options, &mut collector).unwrap();
+        assert_eq!(collector.files, vec![(Path::new("tool-linux-amd64").to_owned(), data.to_vec())]);
+
+        let reader = ReleaseReaderBuilder::new("tool-linux-amd64.zip", &BTreeMap::new()).unwrap();
+        assert!(reader.check_header(b"PK\x03\x04").is_err());
+    }
+
+    #[test]
+    fn uncommitted_cache() {
+        let temp_dir = util::TempDir::new().unwrap();
+        let previous_path = temp_dir.path.join("tool-1.0.0-linux-amd64");
+        let path = temp_dir.path.join("tool-1.0.1-linux-amd64");
+        fs::write(&previous_path, "previous").unwrap();
+
+        let data = b"#!/bin/sh\n";
+        let options = ExtractOptions {decompressors: &BTreeMap::new(), limits: &ExtractionLimits::default()};
+
+        for commit in [false, true] {
+            let cache = Cache::new(&path).unwrap();
+            let reader = ReleaseReaderBuilder::new("tool-1.0.1-linux-amd64", &BTreeMap::new()).unwrap();
+            let checksum = ChecksumReader::new(io::Cursor::new(data.to_vec()), Some(cache.file.try_clone().unwrap()));
+            extract(reader, checksum, None, &options, &mut Collector::default()).unwrap();
+
+            // Checksum mismatch or any other verification failure drops the cache without commit
+            if commit {
+                cache.commit().unwrap();
+            } else {
+                drop(cache);
+            }
+
+            let mut files: Vec<_> = fs::read_dir(&temp_dir.path).unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect();
+            files.sort();
+
+            if commit {
+                assert_eq!(files, vec![path.clone()]);
+                assert_eq!(fs::read(&path).unwrap(), data);
+            } else {
+                assert_eq!(files, vec![previous_path.clone()]);
+                assert_eq!(fs::read_to_string(&previous_path).unwrap(), "previous");
+            }
+        }
+    }
+
+    #[test]
+    fn local_raw_binary_size_limit() {
+        // The test binary itself is a local file which exceeds the limit
+        let path = std::env::current_exe().unwrap();
+        let options = ExtractOptions {decompressors: &BTreeMap::new(), limits: &ExtractionLimits {
+            max_entry_size: 1,
+            ..Default::default()
+        }};
+
+        let mut collector = Collector::default();
+        let reader = ReleaseReaderBuilder::new("tool-linux-amd64", &BTreeMap::new()).unwrap();
+        let checksum = ChecksumReader::new(File::open(&path).unwrap(), None);
+        assert!(extract(reader, checksum, Some(&path), &options, &mut collector).is_err());
+        assert!(collector.files.is_empty());
+    }
+
+    #[test]
+    fn compressed_raw_binary() {
+        let data = b"#!/bin/sh\n";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let reader = ReleaseReaderBuilder::new("tool-linux-amd64.gz", &BTreeMap::new()).unwrap();
+        reader.check_header(&compressed).unwrap();
+
+        let checksum = ChecksumReader::new(io::Cursor::new(compressed.clone()), None);
+        let options = ExtractOptions {decompressors: &BTreeMap::new(), limits: &ExtractionLimits::default()};
+
+        let mut collector = Collector::default();
+        let (_, size) = extract(reader, checksum, None, &options, &mut collector).unwrap();
+        assert_eq!(collector.files, vec![(Path::new("tool-linux-amd64").to_owned(), data.to_vec())]);
+        assert_eq!(size, compressed.len() as u64);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"README").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let reader = ReleaseReaderBuilder::new("tool-linux-amd64.gz", &BTreeMap::new()).unwrap();
+        let checksum = ChecksumReader::new(io::Cursor::new(compressed), None);
+        assert!(extract(reader, checksum, None, &options, &mut Collector::default()).is_err());
+    }
+
+    #[test]
+    fn macos_package() {
+        let reader = ReleaseReaderBuilder::new("Tool-1.0.0.pkg", &BTreeMap::new()).unwrap();
+        reader.check_header(b"xar!\x00\x1c").unwrap();
+        assert!(reader.check_header(b"PK\x03\x04").is_err());
+
+        let temp_dir = util::TempDir::new().unwrap();
+        let root = temp_dir.path.join("expanded");
+        let binary_path = root.join("tool.pkg/Payload/usr/local/bin");
+        fs::create_dir_all(&binary_path).unwrap();
+        fs::write(root.join("Distribution"), b"<xml/>").unwrap();
+        fs::write(binary_path.join("tool"), b"#!/bin/sh\n").unwrap();
+        std::os::unix::fs::symlink("tool", binary_path.join("tool-link")).unwrap();
+
+        let mut collector = Collector::default();
+        extract_dir(&root, &ExtractionLimits::default(), &mut collector).unwrap();
+        assert_eq!(collector.files, vec![
+            (Path::new("Distribution").to_owned(), b"<xml/>".to_vec()),
+            (Path::new("tool.pkg/Payload/usr/local/bin/tool").to_owned(), b"#!/bin/sh\n".to_vec()),
+        ]);
+
+        let path = temp_dir.path.clone();
+        drop(temp_dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn debian_package() {
+        let data = b"#!/bin/sh\n";
+
+        let mut archive = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        archive.append_data(&mut header, "./usr/bin/tool", data.as_slice()).unwrap();
+        let data_archive = archive.into_inner().unwrap().finish().unwrap();
+
+        let mut package = DEB_MAGIC.to_vec();
+        for (name, contents) in [("debian-binary", b"2.0\n".as_slice()), ("control.tar.gz", b"odd"), ("data.tar.gz", &data_archive)] {
+            package.extend(format!("{name:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", 0, 0, 0, 100644, contents.len()).as_bytes());
+            package.extend(contents);
+            if contents.len() % 2 != 0 {
+                package.push(b'\n');
+            }
+        }
+
+        let reader = ReleaseReaderBuilder::new("tool_1.0.0_amd64.deb", &BTreeMap::new()).unwrap();
+        reader.check_header(&package).unwrap();
+
+        let checksum = ChecksumReader::new(io::Cursor::new(package.clone()), None);
+        let options = ExtractOptions {decompressors: &BTreeMap::new(), limits: &ExtractionLimits::default()};
+
+        let mut collector = Collector::default();
+        let (_, size) = extract(reader, checksum, None, &options, &mut collector).unwrap();
+        assert_eq!(collector.files, vec![(Path::new("usr/bin/tool").to_owned(), data.to_vec())]);
+        assert_eq!(size, package.len() as u64);
+    }
+
+    #[test]
+    fn rpm_package() {
+        let data = b"#!/bin/sh\n";
+
+        let mut cpio = Vec::new();
+        for (name, mode, contents) in [("./usr/bin", 0o40755, b"".as_slice()), ("./usr/bin/tool", 0o100755, data), ("TRAILER!!!", 0, b"")] {
+            let name_size = name.len() + 1;
+            cpio.extend(format!("070701{:08x}{mode:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{name_size:08x}{:08x}",
+                0, 0, 0, 1, 0, contents.len(), 0, 0, 0, 0, 0).as_bytes());
+            cpio.extend(name.as_bytes());
+            cpio.push(0);
+            cpio.resize(cpio.len().next_multiple_of(4), 0);
+            cpio.extend(contents);
+            cpio.resize(cpio.len().next_multiple_of(4), 0);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&cpio).unwrap();
+
+        let mut package = RPM_MAGIC.to_vec();
+        package.resize(96, 0);
+        for (entries, data_size) in [(1_u32, 3_u32), (0, 0)] {
+            package.extend(RPM_HEADER_MAGIC);
+            package.extend([0; 4]);
+            package.extend(entries.to_be_bytes());
+            package.extend(data_size.to_be_bytes());
+            package.resize(package.len() + (entries * 16 + data_size) as usize, 0);
+            package.resize(package.len().next_multiple_of(8), 0);
+        }
+        package.extend(encoder.finish().unwrap());
+
+        let reader = ReleaseReaderBuilder::new("tool-1.0.0-1.x86_64.rpm", &BTreeMap::new()).unwrap();
+        reader.check_header(&package).unwrap();
+
+        let checksum = ChecksumReader::new(io::Cursor::new(package.clone()), None);
+        let options = ExtractOptions {decompressors: &BTreeMap::new(), limits: &ExtractionLimits::default()};
+
+        let mut collector = Collector::default();
+        let (_, size) = extract(reader, checksum, None, &options, &mut collector).unwrap();
+        assert_eq!(collector.files, vec![(Path::new("usr/bin/tool").to_owned(), data.to_vec())]);
+        assert_eq!(size, package.len() as u64);
+    }
+
+    #[test]
+    fn cpio_name_size_limit() {
+        let cpio = format!("070701{:0>88}{:08x}{:08x}", "", MAX_CPIO_NAME_SIZE + 1, 0);
+
+        let mut collector = Collector::default();
+        let err = extract_cpio(&mut cpio.as_bytes(), &ExtractionLimits::default(), &mut collector).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid cpio archive: too long entry name (4097 bytes)");
+    }
+}
@@ -0,0 +1,195 @@
+use std::env::consts;
+use std::str::FromStr;
+
+use platforms::{Arch, OS};
+
+// The number of bytes which is enough to detect all supported file types
+pub const HEADER_SIZE: usize = 4096;
+
+#[derive(Debug, PartialEq)]
+pub enum FileType {
+    Elf(Option<Arch>),
+    MachO(Option<Arch>),
+    UniversalMachO(Vec<Option<Arch>>),
+}
+
+impl FileType {
+    pub fn detect(header: &[u8]) -> Option<FileType> {
+        if header.starts_with(b"\x7fELF") {
+            return Some(FileType::Elf(parse_elf_arch(header)));
+        }
+
+        if let Some(magic) = read_u32(header, 0, false) {
+            match magic {
+                0xfeedface | 0xfeedfacf => return Some(FileType::MachO(
+                    read_u32(header, 4, false).and_then(parse_mach_o_arch))),
+                0xcefaedfe | 0xcffaedfe => return Some(FileType::MachO(
+                    read_u32(header, 4, true).and_then(parse_mach_o_arch))),
+                0xcafebabe | 0xcafebabf => return parse_universal_mach_o(header, magic == 0xcafebabf),
+                _ => {},
+            }
+        }
+
+        None
+    }
+
+    pub fn is_executable_for(&self, os: OS, arch: Arch) -> bool {
+        let runnable = |binary_arch: &Option<Arch>| binary_arch.is_some_and(|binary_arch| {
+            get_runnable_archs(os, arch).contains(&binary_arch)
+        });
+
+        match self {
+            FileType::Elf(binary_arch) => os != OS::MacOS && runnable(binary_arch),
+            FileType::MachO(binary_arch) => os == OS::MacOS && runnable(binary_arch),
+            FileType::UniversalMachO(archs) => os == OS::MacOS && archs.iter().any(runnable),
+        }
+    }
+
+    // Returns None if we don't know the current platform well enough to judge
+    pub fn is_executable_for_current_platform(&self) -> Option<bool> {
+        let os = OS::from_str(consts::OS).ok()?;
+        let arch = Arch::from_str(consts::ARCH).ok()?;
+
+        if !matches!(os, OS::Linux | OS::MacOS) || !matches!(arch, Arch::AArch64 | Arch::X86_64) {
+            return None;
+        }
+
+        Some(self.is_executable_for(os, arch))
+    }
+}
+
+// Apple Silicon runs x86_64 binaries under Rosetta 2 and x86_64 Linux runs i386 binaries in compatibility mode
+fn get_runnable_archs(os: OS, arch: Arch) -> &'static [Arch] {
+    match (os, arch) {
+        (OS::MacOS, Arch::AArch64) => &[Arch::AArch64, Arch::X86_64],
+        (OS::MacOS, Arch::X86_64) => &[Arch::X86_64],
+        (_, Arch::X86_64) => &[Arch::X86_64, Arch::X86],
+        (_, Arch::AArch64) => &[Arch::AArch64],
+        _ => &[],
+    }
+}
+
+// Checks whether the file contents look like a script or a binary which can be executed on the current platform
+pub fn is_executable(header: &[u8]) -> bool {
+    if header.starts_with(b"#!") {
+        return true;
+    }
+
+    FileType::detect(header)
+        .map(|file_type| file_type.is_executable_for_current_platform() != Some(false))
+        .unwrap_or_default()
+}
+
+fn parse_elf_arch(header: &[u8]) -> Option<Arch> {
+    let big_endian = match header.get(5)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+
+    let machine = header.get(18..20)?;
+    let machine = if big_endian {
+        u16::from_be_bytes([machine[0], machine[1]])
+    } else {
+        u16::from_le_bytes([machine[0], machine[1]])
+    };
+
+    Some(match machine {
+        3 => Arch::X86,
+        62 => Arch::X86_64,
+        183 => Arch::AArch64,
+        _ => return None,
+    })
+}
+
+fn parse_mach_o_arch(cpu_type: u32) -> Option<Arch> {
+    Some(match cpu_type {
+        0x0100_0007 => Arch::X86_64,
+        0x0100_000c => Arch::AArch64,
+        _ => return None,
+    })
+}
+
+fn parse_universal_mach_o(header: &[u8], is_64: bool) -> Option<FileType> {
+    let count = read_u32(header, 4, false)?;
+
+    // Java class files have the same magic, but their version is always much bigger
+    if count == 0 || count > 20 {
+        return None;
+    }
+
+    let entry_size = if is_64 { 32 } else { 20 };
+    let mut archs = Vec::new();
+
+    for index in 0..count as usize {
+        let cpu_type = read_u32(header, 8 + index * entry_size, false)?;
+        archs.push(parse_mach_o_arch(cpu_type));
+    }
+
+    Some(FileType::UniversalMachO(archs))
+}
+
+fn read_u32(data: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if little_endian {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(header, file_type,
+        case(b"#!/bin/bash\n", None),
+        case(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00\x3e\x00", Some(FileType::Elf(Some(Arch::X86_64)))),
+        case(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00\xb7\x00", Some(FileType::Elf(Some(Arch::AArch64)))),
+        case(b"\x7fELF\x01\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00\x03\x00", Some(FileType::Elf(Some(Arch::X86)))),
+        case(b"\xcf\xfa\xed\xfe\x07\x00\x00\x01", Some(FileType::MachO(Some(Arch::X86_64)))),
+        case(b"\xcf\xfa\xed\xfe\x0c\x00\x00\x01", Some(FileType::MachO(Some(Arch::AArch64)))),
+        case(&[
+            0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x02,
+            0x01, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x0e,
+            0x01, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x0e,
+        ], Some(FileType::UniversalMachO(vec![Some(Arch::X86_64), Some(Arch::AArch64)]))),
+        case(b"\xca\xfe\xba\xbe\x00\x00\x00\x41", None),
+    )]
+    fn detect(header: &[u8], file_type: Option<FileType>) {
+        assert_eq!(FileType::detect(header), file_type);
+    }
+
+    #[rstest(header, expected,
+        case(b"#!/bin/sh\n", true),
+        case(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00\x08\x00", false),
+        case(b"Some text\n", false),
+        case(b"", false),
+    )]
+    fn executable(header: &[u8], expected: bool) {
+        assert_eq!(is_executable(header), expected);
+    }
+
+    #[rstest(file_type, os, arch, expected,
+        case(FileType::MachO(Some(Arch::X86_64)), OS::MacOS, Arch::AArch64, true),
+        case(FileType::MachO(Some(Arch::AArch64)), OS::MacOS, Arch::X86_64, false),
+        case(FileType::UniversalMachO(vec![Some(Arch::X86_64)]), OS::MacOS, Arch::AArch64, true),
+        case(FileType::Elf(Some(Arch::X86)), OS::Linux, Arch::X86_64, true),
+        case(FileType::Elf(Some(Arch::X86_64)), OS::Linux, Arch::AArch64, false),
+        case(FileType::Elf(Some(Arch::X86)), OS::Linux, Arch::AArch64, false),
+        case(FileType::Elf(Some(Arch::X86_64)), OS::MacOS, Arch::X86_64, false),
+        case(FileType::Elf(None), OS::Linux, Arch::X86_64, false),
+    )]
+    fn compatibility(file_type: FileType, os: OS, arch: Arch, expected: bool) {
+        assert_eq!(file_type.is_executable_for(os, arch), expected);
+    }
+
+    #[test]
+    fn universal() {
+        let file_type = FileType::UniversalMachO(vec![Some(Arch::X86_64), Some(Arch::AArch64)]);
+        assert!(file_type.is_executable_for(OS::MacOS, Arch::X86_64));
+        assert!(file_type.is_executable_for(OS::MacOS, Arch::AArch64));
+        assert!(!file_type.is_executable_for(OS::Linux, Arch::X86_64));
+    }
+}
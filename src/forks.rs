@@ -0,0 +1,130 @@
+use std::process::ExitCode;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use log::{debug, info, warn};
+use tabled::{Table, Tabled};
+use tabled::settings::style::Style;
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::i18n;
+use crate::porcelain::{self, Porcelain};
+use crate::provider::Providers;
+use crate::release::{Asset, Release};
+use crate::tool::ToolSpec;
+
+// Forks are checked in order of their popularity, and each of them costs at least one API request
+const MAX_FORKS: u8 = 30;
+
+#[derive(Tabled)]
+struct ForkInfo {
+    #[tabled(rename = "Project")]
+    name: String,
+    #[tabled(rename = "Stars")]
+    stars: u32,
+    #[tabled(rename = "Latest release")]
+    version: String,
+    #[tabled(rename = "Released")]
+    time: String,
+    #[tabled(skip)]
+    release_time: DateTime<Utc>,
+}
+
+// Looks for forks with newer releases of a project which has stopped releasing
+pub fn suggest_forks(
+    config: &Config, name: &str, inactive_for: Duration, porcelain: Option<Porcelain>,
+) -> GenericResult<ExitCode> {
+    let spec = config.tools.get(name).ok_or_else(|| format!(
+        "{name} isn't specified in the configuration file"))?;
+
+    if spec.artifact.is_some() {
+        return Err!("{name} is installed from workflow artifacts, which aren't supported by this command");
+    } else if spec.url.is_some() {
+        return Err!("{name} is installed from URL, which isn't supported by this command");
+    }
+
+    let providers = Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?;
+    let (release, asset) = providers.get_tool_release(name, spec)?;
+    let project = release.project.full_name();
+    let release_time = get_release_time(&release, asset.ok().as_ref());
+
+    if let Some(time) = release_time {
+        let age = Utc::now().signed_duration_since(time).to_std().unwrap_or_default();
+        if age < inactive_for {
+            info!("{project} has released {} {} days ago, so it's considered active. Not looking for forks.",
+                release.version, age.as_secs() / 60 / 60 / 24);
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+
+    info!("Looking for {project} forks with newer releases...");
+    let mut forks = Vec::new();
+
+    for fork in providers.get_forks(&release.project, MAX_FORKS)? {
+        // A fork which hasn't been pushed to since the upstream release can't have newer releases
+        if let (Some(pushed_at), Some(release_time)) = (fork.pushed_at, release_time) {
+            if pushed_at <= release_time {
+                debug!("Skipping {}: it hasn't been updated since {project} {} release.", fork.name, release.version);
+                continue;
+            }
+        }
+
+        // Only the forks with releases which are installable with the current tool configuration are suggested
+        let fork_spec = ToolSpec {
+            project: vec![fork.name.clone()],
+            ..spec.clone()
+        };
+
+        let (fork_release, fork_asset) = match providers.get_tool_release(name, &fork_spec) {
+            Ok((release, Ok(asset))) => (release, asset),
+            Ok((_, Err(err))) | Err(err) => {
+                debug!("Skipping {}: {err}.", fork.name);
+                continue;
+            },
+        };
+
+        let Some(fork_time) = get_release_time(&fork_release, Some(&fork_asset)) else {
+            continue;
+        };
+
+        if release_time.is_some_and(|release_time| fork_time <= release_time) {
+            debug!("Skipping {}: its latest release is older than {project} {}.", fork.name, release.version);
+            continue;
+        }
+
+        forks.push(ForkInfo {
+            name: fork.name,
+            stars: fork.stars,
+            version: fork_release.version.to_string(),
+            time: DateTime::<Local>::from(fork_time).format("%Y.%m.%d").to_string(),
+            release_time: fork_time,
+        });
+    }
+
+    if forks.is_empty() {
+        warn!("No {project} forks with newer releases have been found.");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if let Some(Porcelain::V1) = porcelain {
+        for fork in &forks {
+            print!("{}", porcelain::format_record("fork", &[
+                &fork.name, &fork.stars.to_string(), &fork.version, &porcelain::format_time(fork.release_time),
+            ]));
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut table = Table::new(&forks);
+    table.with(Style::blank());
+    i18n::localize_header(&mut table);
+    println!("{table}");
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// GitHub doesn't return release publication time in our release model, so the newest asset update time is used instead
+fn get_release_time(release: &Release, asset: Option<&Asset>) -> Option<DateTime<Utc>> {
+    asset.map(|asset| asset.time).or_else(|| release.assets.iter().map(|asset| asset.time).max())
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use log::{debug, error, info, warn};
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::i18n;
+use crate::install;
+use crate::shims;
+use crate::state::State;
+use crate::util;
+
+struct Version {
+    tool: String,
+    version: String,
+    path: PathBuf,
+    time: SystemTime,
+    size: u64,
+}
+
+// Deletes the tool versions which are kept in shim mode, but aren't referenced anymore: they aren't the latest installed
+// ones, aren't pinned by the known projects, aren't targets of symlinks in the install directories and aren't retained
+// by --keep option.
+pub fn gc(config: &Config, keep: usize) -> GenericResult<ExitCode> {
+    if !config.shims {
+        return Err!("Shim mode is disabled. Enable shims option in the configuration file to use it");
+    }
+
+    let mut state = State::load(config)?;
+    let mut referenced: BTreeSet<(String, String)> = state.tools.iter()
+        .map(|(name, info)| (name.clone(), info.version.clone()))
+        .collect();
+
+    let mut missing = Vec::new();
+    for path in &state.pin_files {
+        match shims::read_pins(path)? {
+            Some(pins) => referenced.extend(pins.into_iter().map(|pin| (pin.tool, pin.version))),
+            None => {
+                debug!("{path:?} doesn't exist anymore.");
+                missing.push(path.clone());
+            },
+        }
+    }
+    referenced.extend(shims::find_current_pins()?.into_iter().map(|pin| (pin.tool, pin.version)));
+    referenced.extend(get_symlinked_versions(config)?);
+
+    let mut candidates: BTreeMap<String, Vec<Version>> = BTreeMap::new();
+    for version in get_versions(&config.versions_path)? {
+        if referenced.contains(&(version.tool.clone(), version.version.clone())) {
+            debug!("{} {} is in use.", version.tool, version.version);
+        } else {
+            candidates.entry(version.tool.clone()).or_default().push(version);
+        }
+    }
+
+    let mut garbage = Vec::new();
+    for (_, mut versions) in candidates {
+        versions.sort_by_key(|version| std::cmp::Reverse(version.time));
+        garbage.extend(versions.into_iter().skip(keep));
+    }
+
+    if garbage.is_empty() {
+        info!("There are no unused tool versions.");
+    } else {
+        let total: u64 = garbage.iter().map(|version| version.size).sum();
+
+        info!("The following tool versions aren't used anymore:");
+        for version in &garbage {
+            info!("* {} {} ({})", version.tool, version.version, util::format_size(version.size));
+        }
+
+        if !util::confirm(i18n::trf("Delete them to free {}?", &[&util::format_size(total)]))? {
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let mut reclaimed = 0;
+        let mut failed = false;
+
+        for version in garbage {
+            match install::remove_tree(&version.path) {
+                Ok(()) => reclaimed += version.size,
+                Err(err) => {
+                    error!("{err}.");
+                    failed = true;
+                },
+            }
+        }
+
+        info!("{} of disk space is reclaimed.", util::format_size(reclaimed));
+        if failed {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    if !missing.is_empty() {
+        for path in missing {
+            state.pin_files.remove(&path);
+        }
+        state.save()?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn get_versions(versions_path: &Path) -> GenericResult<Vec<Version>> {
+    let mut versions = Vec::new();
+
+    for (tool, tool_path) in read_dirs(versions_path)? {
+        for (version, path) in read_dirs(&tool_path)? {
+            let metadata = fs::metadata(&path).map_err(|e| format!("Unable to stat {path:?}: {e}"))?;
+            versions.push(Version {
+                tool: tool.clone(),
+                version,
+                time: metadata.modified().map_err(|e| format!("Unable to stat {path:?}: {e}"))?,
+                size: get_size(&path)?,
+                path,
+            });
+        }
+    }
+
+    Ok(versions)
+}
+
+fn read_dirs(path: &Path) -> GenericResult<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err!("Unable to read {path:?}: {err}"),
+    };
+
+    let mut dirs = Vec::new();
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Unable to read {path:?}: {e}"))?;
+        let file_type = entry.file_type().map_err(|e| format!("Unable to stat {:?}: {e}", entry.path()))?;
+
+        if !file_type.is_dir() {
+            continue;
+        }
+
+        match entry.file_name().into_string() {
+            Ok(name) => dirs.push((name, entry.path())),
+            Err(name) => warn!("Skipping {name:?} in {path:?}: invalid name."),
+        }
+    }
+
+    Ok(dirs)
+}
+
+fn get_size(path: &Path) -> GenericResult<u64> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| format!("Unable to stat {path:?}: {e}"))?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path).map_err(|e| format!("Unable to read {path:?}: {e}"))? {
+        let entry = entry.map_err(|e| format!("Unable to read {path:?}: {e}"))?;
+        size += get_size(&entry.path())?;
+    }
+
+    Ok(size)
+}
+
+// Users may link a specific version to the install directory manually (for example, as `tool2`)
+fn get_symlinked_versions(config: &Config) -> GenericResult<Vec<(String, String)>> {
+    let directories: BTreeSet<PathBuf> = config.tools.iter()
+        .filter_map(|(name, spec)| config.get_command_path(name, spec).parent().map(Path::to_owned))
+        .collect();
+
+    let mut versions = Vec::new();
+
+    for directory in directories {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err!("Unable to read {directory:?}: {err}"),
+        };
+
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Unable to read {directory:?}: {e}"))?;
+            let Ok(target) = fs::read_link(entry.path()) else {
+                continue;
+            };
+
+            let target = directory.join(target);
+            if let Some(version) = parse_version_path(&config.versions_path, &target) {
+                debug!("{:?} is a symlink to {} {}.", entry.path(), version.0, version.1);
+                versions.push(version);
+            }
+        }
+    }
+
+    Ok(versions)
+}
+
+fn parse_version_path(versions_path: &Path, path: &Path) -> Option<(String, String)> {
+    let mut components = path.strip_prefix(versions_path).ok()?.iter();
+    let tool = components.next()?.to_str()?;
+    let version = components.next()?.to_str()?;
+    components.next()?;
+    Some((tool.to_owned(), version.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(path, expected,
+        case("/versions/tool/1.0.0/tool", Some(("tool", "1.0.0"))),
+        case("/versions/tool/tool", None),
+        case("/versions/tool/1.0.0", None),
+        case("/other/tool/1.0.0/tool", None),
+    )]
+    fn version_path(path: &str, expected: Option<(&str, &str)>) {
+        let expected = expected.map(|(tool, version)| (tool.to_owned(), version.to_owned()));
+        assert_eq!(parse_version_path(Path::new("/versions"), Path::new(path)), expected);
+    }
+}
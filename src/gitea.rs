@@ -0,0 +1,209 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use log::{debug, trace, warn};
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, ClientBuilder};
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::core::GenericResult;
+use crate::network::NetworkConfig;
+use crate::project::Project;
+use crate::provider::{Fork, ReleaseProvider, Renames, Repository};
+use crate::release::{Asset, Release};
+use crate::tls::TlsConfig;
+use crate::tool::ReleaseStrategy;
+use crate::version::ReleaseVersion;
+use crate::util;
+
+// Gitea limits page size to 50 by default
+const PAGE_SIZE: usize = 50;
+
+// Gitea API client which is used for projects hosted on Gitea-compatible services like Codeberg or Forgejo
+pub struct Gitea {
+    client: Client,
+    renames: Renames,
+}
+
+#[derive(Deserialize)]
+struct ReleaseModel {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    assets: Vec<AssetModel>,
+    body: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AssetModel {
+    name: String,
+    created_at: DateTime<Utc>,
+    browser_download_url: Url,
+}
+
+#[derive(Deserialize)]
+struct RepositoryModel {
+    full_name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    stars_count: u32,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl Gitea {
+    pub fn new(tls: &TlsConfig, network: &NetworkConfig) -> GenericResult<Gitea> {
+        let client = ClientBuilder::new().user_agent(util::USER_AGENT);
+        let client = network.configure_client(client);
+        let client = tls.configure_client(client)?.build()?;
+        Ok(Gitea {client, renames: Renames::default()})
+    }
+
+    // Returns all stable releases sorted by version in descending order
+    fn get_versioned_releases(&self, project: &Project, max_pages: usize) -> GenericResult<Vec<ReleaseModel>> {
+        let mut releases: Vec<(Version, ReleaseModel)> = Vec::new();
+
+        for page_number in 1.. {
+            let Some(page): Option<Vec<ReleaseModel>> = self.get(
+                project, &format!("releases?page={page_number}&limit={PAGE_SIZE}"),
+            )? else {
+                break;
+            };
+
+            debug!("Got page #{page_number} of {} releases ({} releases).", project.full_name(), page.len());
+            let last_page = page.len() < PAGE_SIZE;
+
+            for release in page {
+                if release.draft || release.prerelease {
+                    continue;
+                }
+
+                let ReleaseVersion::Version(version) = ReleaseVersion::new(&release.tag_name) else {
+                    continue;
+                };
+
+                if version.pre.is_empty() {
+                    releases.push((version, release));
+                }
+            }
+
+            if last_page {
+                break;
+            } else if page_number >= max_pages {
+                warn!(concat!(
+                    "{} has more than {} pages of releases. Only the first ones are checked ",
+                    "(see max_release_pages option)."
+                ), project.full_name(), max_pages);
+                break;
+            }
+        }
+
+        releases.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Ok(releases.into_iter().map(|(_, release)| release).collect())
+    }
+
+    fn get<T: DeserializeOwned>(&self, project: &Project, path: &str) -> GenericResult<Option<T>> {
+        let host = project.host.as_deref().expect("Gitea project without host");
+
+        let mut url = format!("https://{host}/api/v1/repos/{}/{}", project.owner, project.name);
+        if !path.is_empty() {
+            url = format!("{url}/{path}");
+        }
+
+        trace!("Sending GET {url}...");
+        let response = self.client.get(&url).send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(serde_json::from_slice(&response.bytes()?).map_err(|e| format!(
+                "Got an invalid response from {host}: {e}"))?)),
+            status => Err!("{host} returned an error: {status}"),
+        }
+    }
+}
+
+impl ReleaseProvider for Gitea {
+    fn get_releases(
+        &self, project: &Project, tag: Option<&str>, strategy: ReleaseStrategy, max_pages: usize,
+    ) -> GenericResult<Vec<Release>> {
+        let releases: Vec<ReleaseModel> = match (tag, strategy) {
+            (Some(tag), _) => {
+                debug!("Getting {} {tag} release info...", project.full_name());
+                self.get(project, &format!("releases/tags/{tag}"))?.into_iter().collect()
+            },
+            (None, ReleaseStrategy::GithubLatest) => {
+                debug!("Getting {} release info...", project.full_name());
+                self.get(project, "releases/latest")?.into_iter().collect()
+            },
+            (None, ReleaseStrategy::HighestVersion) => {
+                debug!("Getting {} releases...", project.full_name());
+                self.get_versioned_releases(project, max_pages)?
+            },
+        };
+
+        if releases.is_empty() {
+            if self.get::<RepositoryModel>(project, "")?.is_none() {
+                return Err!("The project doesn't exist");
+            }
+            return match tag {
+                Some(tag) => Err!("The project has no {tag} release"),
+                None => Err!("The project has no releases"),
+            };
+        }
+
+        Ok(releases.into_iter().map(|release| {
+            Release::new(project.clone(), &release.tag_name, release.assets.into_iter().map(|asset| {
+                Asset {
+                    name: asset.name,
+                    time: asset.created_at,
+                    url: asset.browser_download_url,
+                }
+            }).collect()).with_notes(release.body)
+        }).collect())
+    }
+
+    fn get_repository(&self, project: &Project) -> GenericResult<Repository> {
+        debug!("Getting {} repository info...", project.full_name());
+
+        let repository: RepositoryModel = self.get(project, "")?.ok_or("The project doesn't exist")?;
+
+        self.renames.check(project, &repository.full_name);
+
+        Ok(Repository {
+            description: repository.description.trim().to_owned(),
+            archived: repository.archived,
+        })
+    }
+
+    fn get_forks(&self, project: &Project, limit: u8) -> GenericResult<Vec<Fork>> {
+        debug!("Getting {} forks...", project.full_name());
+
+        // The API has no sorting options, so sort the first page locally
+        let mut forks: Vec<RepositoryModel> = self.get(project, &format!("forks?limit={PAGE_SIZE}"))?
+            .ok_or("The project doesn't exist")?;
+        forks.sort_by_key(|fork| Reverse(fork.stars_count));
+
+        Ok(forks.into_iter().take(limit.into()).map(|repository| Fork {
+            name: format_full_name(project, &repository.full_name),
+            stars: repository.stars_count,
+            pushed_at: repository.updated_at,
+        }).collect())
+    }
+
+    fn get_renamed_projects(&self) -> BTreeMap<String, String> {
+        self.renames.get()
+    }
+}
+
+fn format_full_name(project: &Project, name: &str) -> String {
+    format!("{}/{name}", project.host.as_deref().unwrap_or_default())
+}
@@ -1,110 +1,610 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::error::Error as _;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
+use chrono::{DateTime, TimeDelta, Utc};
 use http::{StatusCode, header};
-use log::{debug, trace};
-use octocrab::{Octocrab, OctocrabBuilder, Error};
+use log::{debug, trace, warn};
+use http::{HeaderValue, Uri};
+use hyper_rustls::HttpsConnectorBuilder;
+use hyper_util::rt::TokioExecutor;
+use jsonwebtoken::EncodingKey;
+use octocrab::{AuthState, Octocrab, OctocrabBuilder, Error};
+use octocrab::auth::AppAuth;
+use octocrab::models::{AppId, InstallationId, InstallationToken};
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::base_uri::BaseUriLayer;
+use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
 use octocrab::models::repos::Release as ReleaseModel;
+use octocrab::params::repos::forks::Sort as ForkSort;
+use semver::Version;
 use serde::Deserialize;
+use rustls::ClientConfig;
 use tokio::runtime::Runtime;
+use tower_http::follow_redirect::FollowRedirectLayer;
 use url::Url;
 
-use crate::core::GenericResult;
+use crate::core::{GenericError, GenericResult};
+use crate::network::NetworkConfig;
 use crate::project::Project;
+use crate::provider::{self, Fork, ReleaseProvider, Renames, Repository};
+use crate::rate_limit::RateLimitLayer;
 use crate::release::{Release, Asset};
+use crate::s3;
+use crate::tls::TlsConfig;
+use crate::tool::{ArtifactSpec, ReleaseStrategy};
+use crate::version::ReleaseVersion;
 use crate::util;
 
+pub const PUBLIC_HOST: &str = "github.com";
+const PUBLIC_URL: &str = "https://github.com/";
+
+// Installation tokens are refreshed a bit before their expiration, so they don't expire during a download
+const APP_TOKEN_REFRESH_MARGIN: TimeDelta = TimeDelta::minutes(5);
+
 #[derive(Clone, Default, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct GithubConfig {
     token: Option<String>,
+    // GitHub Enterprise Server instance which is used for projects without host instead of github.com
+    pub base_url: Option<Url>,
+    // Tokens of the other GitHub instances (host -> token). Projects prefixed with these hosts are considered to be
+    // hosted on the GitHub instances instead of Gitea-compatible services.
+    #[serde(default)]
+    pub tokens: BTreeMap<String, String>,
+    // GitHub App which is used instead of the token for organizations which forbid personal access tokens
+    pub app: Option<GithubAppConfig>,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GithubAppConfig {
+    pub id: u64,
+    pub installation_id: u64,
+    #[serde(deserialize_with = "util::deserialize_path")]
+    pub private_key: PathBuf,
+}
+
+impl GithubAppConfig {
+    fn load(&self) -> GenericResult<(AppAuth, InstallationId)> {
+        let key = fs::read(&self.private_key).map_err(|e| format!(
+            "Unable to read GitHub App private key from {:?}: {e}", self.private_key))?;
+
+        let key = EncodingKey::from_rsa_pem(&key).map_err(|e| format!(
+            "Invalid GitHub App private key ({:?}): {e}", self.private_key))?;
+
+        Ok((AppAuth {app_id: AppId(self.id), key}, InstallationId(self.installation_id)))
+    }
+}
+
+impl GithubConfig {
+    pub fn has_token(&self) -> bool {
+        self.has_default_token() || !self.tokens.is_empty()
+    }
+
+    pub fn has_default_token(&self) -> bool {
+        self.token.is_some()
+    }
 }
 
 pub struct Github {
     runtime: Runtime,
+    // GitHub instances by host
+    instances: BTreeMap<String, Instance>,
+    default_host: String,
+    requests: Cell<usize>,
+    renames: Renames,
+}
+
+struct Instance {
     client: Octocrab,
+    token: Option<String>,
+    // GitHub App client and installation which the instance client is authenticated as. Installation tokens are
+    // short-lived, so octocrab caches and refreshes them by itself.
+    app: Option<(Octocrab, InstallationId)>,
+    // Installation token with its expiration time for the requests which are sent outside of octocrab
+    app_token: Mutex<Option<(String, DateTime<Utc>)>>,
+    web_url: Url,
 }
 
 impl Github {
-    pub fn new(config: &GithubConfig) -> GenericResult<Github> {
+    pub fn new(config: &GithubConfig, tls: &TlsConfig, network: &NetworkConfig) -> GenericResult<Github> {
         let runtime = create_runtime()?;
 
-        let client = runtime.block_on(async {
-            let mut builder = OctocrabBuilder::new()
-                .add_header(header::USER_AGENT, util::USER_AGENT.to_owned());
+        let rustls = tls.get_rustls_config()?;
+
+        let default_url = match config.base_url.as_ref() {
+            Some(url) => get_instance_url(url)?,
+            None => Url::parse(PUBLIC_URL).unwrap(),
+        };
+        let default_host = default_url.host_str().ok_or("Invalid GitHub base URL")?.to_owned();
+
+        let mut urls = BTreeMap::new();
+        urls.insert(default_host.clone(), (default_url, config.token.clone()));
+        urls.entry(PUBLIC_HOST.to_owned()).or_insert_with(|| (Url::parse(PUBLIC_URL).unwrap(), None));
 
-            if let Some(token) = config.token.as_ref() {
-                builder = builder.user_access_token(token.to_owned());
+        for (host, token) in &config.tokens {
+            if let Some((_, instance_token)) = urls.get_mut(host) {
+                instance_token.replace(token.clone());
+                continue;
             }
 
-            builder.build()
-        })?;
+            let url = Url::parse(&format!("https://{host}/")).ok()
+                .filter(|url| url.host_str().is_some_and(|actual| actual.eq_ignore_ascii_case(host)))
+                .ok_or_else(|| format!("Invalid GitHub host: {host:?}"))?;
+
+            urls.insert(host.clone(), (url, Some(token.clone())));
+        }
+
+        let mut app = match config.app.as_ref() {
+            Some(_) if config.token.is_some() => return Err!("GitHub token and app can't be specified at the same time"),
+            Some(app) => Some(app.load()?),
+            None => None,
+        };
+
+        let mut instances = BTreeMap::new();
+
+        for (host, (web_url, token)) in urls {
+            let app = if host == default_host { app.take() } else { None };
+
+            let (client, app) = runtime.block_on(async {
+                let client = build_client(
+                    &web_url, token.as_deref(), app.as_ref().map(|(auth, _)| auth.clone()), rustls.clone(), network)?;
+
+                Ok::<_, GenericError>(match app {
+                    Some((_, id)) => (client.installation(id)?, Some((client, id))),
+                    None => (client, None),
+                })
+            }).map_err(|e| format!("{host}: {e}"))?;
+
+            instances.insert(host, Instance {
+                client, token, app, web_url,
+                app_token: Mutex::new(None),
+            });
+        }
+
+        Ok(Github {
+            runtime, instances, default_host,
+            requests: Cell::new(0),
+            renames: Renames::default(),
+        })
+    }
+
+    // Returns hosts of the configured GitHub instances
+    pub fn get_hosts(&self) -> impl Iterator<Item = &str> {
+        self.instances.keys().map(String::as_str)
+    }
+
+    // Returns host of the instance which hosts the projects specified without host
+    pub fn get_default_host(&self) -> &str {
+        &self.default_host
+    }
+
+    // Returns the number of API requests made by this client
+    pub fn get_request_count(&self) -> usize {
+        self.requests.get()
+    }
+
+    // Returns the remaining and total number of API requests (rate limit status requests aren't counted by GitHub)
+    pub fn get_rate_limit(&self) -> GenericResult<(usize, usize)> {
+        let rate = self.runtime.block_on(self.default_client().ratelimit().get()).map_err(humanize_error)?.resources.core;
+        Ok((rate.remaining, rate.limit))
+    }
+
+    // Returns names and descriptions of the most starred repositories matching the query
+    pub fn search_projects(&self, query: &str, limit: u8) -> GenericResult<Vec<(String, String)>> {
+        debug!("Searching for {query:?} projects...");
+        self.count_request();
+
+        let page = self.runtime.block_on(
+            self.default_client().search().repositories(query).sort("stars").order("desc").per_page(limit).send(),
+        ).map_err(humanize_error)?;
+
+        Ok(page.items.into_iter().filter_map(|repository| {
+            repository.full_name.map(|name| (name, repository.description.unwrap_or_default().trim().to_owned()))
+        }).collect())
+    }
 
-        Ok(Github {runtime, client})
+    // Returns logins of the users and organizations matching the query
+    pub fn search_owners(&self, query: &str, limit: u8) -> GenericResult<Vec<String>> {
+        debug!("Searching for {query:?} users...");
+        self.count_request();
+
+        let page = self.runtime.block_on(
+            self.default_client().search().users(query).sort("repositories").order("desc").per_page(limit).send(),
+        ).map_err(humanize_error)?;
+
+        Ok(page.items.into_iter().map(|user| user.login).collect())
     }
 
-    pub fn get_release(&self, project: &str) -> GenericResult<Release> {
-        self.runtime.block_on(self.get_release_async(project))
+    // Returns GitHub instance which hosts the project
+    fn get_instance(&self, project: &Project) -> GenericResult<&Instance> {
+        let host = project.host.as_deref().unwrap_or(&self.default_host);
+        Ok(self.instances.iter()
+            .find_map(|(instance_host, instance)| instance_host.eq_ignore_ascii_case(host).then_some(instance))
+            .ok_or_else(|| format!("{host} is not a configured GitHub instance"))?)
     }
 
-    async fn get_release_async(&self, project: &str) -> GenericResult<Release> {
-        let project = parse_project_name(project)?;
-        let repository = self.client.repos(&project.owner, &project.name);
+    fn default_client(&self) -> &Octocrab {
+        &self.instances[&self.default_host].client
+    }
 
-        debug!("Getting {} release info...", project.full_name());
+    async fn get_releases_async(
+        &self, instance: &Instance, project: Project, tag: Option<&str>, strategy: ReleaseStrategy, max_pages: usize,
+    ) -> GenericResult<Vec<Release>> {
+        let repository = instance.client.repos(&project.owner, &project.name);
+
+        let releases = match (tag, strategy) {
+            (Some(tag), _) => {
+                debug!("Getting {} {tag} release info...", project.full_name());
+                self.count_request();
+                optional(repository.releases().get_by_tag(tag).await)?.into_iter().collect()
+            },
+            (None, ReleaseStrategy::GithubLatest) => {
+                debug!("Getting {} release info...", project.full_name());
+                self.count_request();
+                optional(repository.releases().get_latest().await)?.into_iter().collect()
+            },
+            (None, ReleaseStrategy::HighestVersion) => {
+                debug!("Getting {} releases...", project.full_name());
+                self.get_versioned_releases(instance, &project, max_pages).await?
+            },
+        };
 
-        let release = repository.releases().get_latest().await
-            .map(Some)
-            .or_else(|err| -> GenericResult<Option<ReleaseModel>> {
+        if releases.is_empty() {
+            self.count_request();
+            repository.get().await.map_err(|err| {
                 match err {
-                    Error::GitHub {source, ..} if source.status_code == StatusCode::NOT_FOUND => Ok(None),
-                    _ => Err!("{}", humanize_error(err)),
+                    Error::GitHub {source, ..} if source.status_code == StatusCode::NOT_FOUND => {
+                        "The project doesn't exist".into()
+                    },
+                    _ => humanize_error(err),
                 }
             })?;
+            return match tag {
+                Some(tag) => Err!("The project has no {tag} release"),
+                None => Err!("The project has no releases"),
+            };
+        }
 
-        let release = match release {
-            Some(release) => release,
-            None => {
-                repository.get().await.map_err(|err| {
-                    match err {
-                        Error::GitHub {source, ..} if source.status_code == StatusCode::NOT_FOUND => {
-                            "The project doesn't exist".into()
-                        },
-                        _ => humanize_error(err),
-                    }
-                })?;
-                return Err!("The project has no releases");
-            },
+        trace!("The latest {} release:\n{:#?}", project.full_name(), releases[0]);
+
+        let mut path = releases[0].html_url.path_segments().into_iter().flatten();
+        if let (Some(owner), Some(name)) = (path.next(), path.next()) {
+            self.renames.check(&project, &format!("{owner}/{name}"));
+        }
+
+        Ok(releases.into_iter().map(|release| {
+            Release::new(project.clone(), &release.tag_name, release.assets.into_iter().map(|asset| {
+                Asset {
+                    name: asset.name,
+                    time: asset.updated_at,
+                    url: asset.browser_download_url,
+                }
+            }).collect()).with_notes(release.body)
+        }).collect())
+    }
+
+    fn count_request(&self) {
+        self.requests.set(self.requests.get() + 1);
+    }
+
+    // Returns all stable releases sorted by version in descending order. GitHub's latest release is the most recently
+    // created one, which might be a maintenance release of an older branch.
+    async fn get_versioned_releases(
+        &self, instance: &Instance, project: &Project, max_pages: usize,
+    ) -> GenericResult<Vec<ReleaseModel>> {
+        self.count_request();
+        let Some(mut page) = optional(
+            instance.client.repos(&project.owner, &project.name).releases().list().per_page(100).send().await,
+        )? else {
+            return Ok(Vec::new());
         };
 
-        trace!("The latest {} release:\n{release:#?}", project.full_name());
+        let mut releases: Vec<(Version, ReleaseModel)> = Vec::new();
+
+        for page_number in 1.. {
+            debug!("Got page #{page_number} of {} releases ({} releases).", project.full_name(), page.items.len());
+
+            for release in page.items.drain(..) {
+                if release.draft || release.prerelease {
+                    continue;
+                }
+
+                let ReleaseVersion::Version(version) = ReleaseVersion::new(&release.tag_name) else {
+                    continue;
+                };
+
+                if version.pre.is_empty() {
+                    releases.push((version, release));
+                }
+            }
+
+            if page.next.is_none() {
+                break;
+            } else if page_number >= max_pages {
+                warn!(concat!(
+                    "{} has more than {} pages of releases. Only the first ones are checked ",
+                    "(see max_release_pages option)."
+                ), project.full_name(), max_pages);
+                break;
+            }
+
+            self.count_request();
+
+            match instance.client.get_page(&page.next).await.map_err(humanize_error)? {
+                Some(next) => page = next,
+                None => break,
+            }
+        }
+
+        releases.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Ok(releases.into_iter().map(|(_, release)| release).collect())
+    }
+
+    async fn get_artifact_async(&self, instance: &Instance, project: Project, spec: &ArtifactSpec) -> GenericResult<Release> {
+        debug!("Getting the latest successful {} {} workflow run on {} branch...",
+            project.full_name(), spec.workflow, spec.branch);
+
+        self.count_request();
+        let runs = instance.client.workflows(&project.owner, &project.name)
+            .list_runs(&spec.workflow)
+            .branch(&spec.branch)
+            .status("success")
+            .exclude_pull_requests(true)
+            .per_page(1)
+            .send().await.map_err(humanize_error)?;
+
+        let run = runs.items.into_iter().next().ok_or_else(|| format!(
+            "There are no successful {} workflow runs on {} branch", spec.workflow, spec.branch))?;
+
+        trace!("The latest {} workflow run:\n{run:#?}", spec.workflow);
+
+        self.count_request();
+        let artifacts = instance.client.actions()
+            .list_workflow_run_artifacts(&project.owner, &project.name, run.id)
+            .per_page(100)
+            .send().await.map_err(humanize_error)?
+            .value.map(|page| page.items).unwrap_or_default();
+
+        let artifact = artifacts.into_iter()
+            .find(|artifact| artifact.name == spec.name)
+            .ok_or_else(|| format!("The latest {} workflow run (#{}) has no {:?} artifact",
+                spec.workflow, run.run_number, spec.name))?;
+
+        if artifact.expired {
+            return Err!("{:?} artifact of the latest {} workflow run (#{}) has expired",
+                spec.name, spec.workflow, run.run_number);
+        }
+
+        // Workflow artifacts are always zip archives
+        Ok(Release::new(project, &format!("run-{}", run.run_number), vec![Asset {
+            name: format!("{}.zip", artifact.name),
+            time: artifact.updated_at,
+            url: artifact.archive_download_url,
+        }]))
+    }
+
+    // Returns the instance token. For GitHub App returns the installation token, which is cached until it's about to
+    // expire.
+    fn get_token(&self, instance: &Instance) -> Option<String> {
+        let Some((app, installation)) = instance.app.as_ref() else {
+            return instance.token.clone();
+        };
 
-        Ok(Release::new(project, &release.tag_name, release.assets.into_iter().map(|asset| {
-            Asset {
-                name: asset.name,
-                time: asset.updated_at,
-                url: asset.browser_download_url,
+        let mut cached = instance.app_token.lock().unwrap();
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Utc::now() + APP_TOKEN_REFRESH_MARGIN < *expires_at {
+                return Some(token.clone());
             }
-        }).collect()))
+        }
+
+        debug!("Requesting GitHub App installation token...");
+        self.count_request();
+
+        let route = format!("/app/installations/{installation}/access_tokens");
+
+        match self.runtime.block_on(app.post::<_, InstallationToken>(route, None::<&()>)) {
+            Ok(token) => {
+                // Installation tokens are issued for an hour
+                let expires_at = token.expires_at.as_deref()
+                    .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                    .map_or_else(|| Utc::now() + TimeDelta::hours(1), Into::into);
+
+                cached.replace((token.token.clone(), expires_at));
+                Some(token.token)
+            },
+            Err(err) => {
+                warn!("Failed to get GitHub App installation token: {}.", humanize_error(err));
+                None
+            },
+        }
+    }
+}
+
+impl ReleaseProvider for Github {
+    fn get_releases(
+        &self, project: &Project, tag: Option<&str>, strategy: ReleaseStrategy, max_pages: usize,
+    ) -> GenericResult<Vec<Release>> {
+        let instance = self.get_instance(project)?;
+        self.runtime.block_on(self.get_releases_async(instance, project.clone(), tag, strategy, max_pages))
+    }
+
+    fn get_repository(&self, project: &Project) -> GenericResult<Repository> {
+        let instance = self.get_instance(project)?;
+
+        debug!("Getting {} repository info...", project.full_name());
+        self.count_request();
+
+        let repository = self.runtime.block_on(instance.client.repos(&project.owner, &project.name).get())
+            .map_err(humanize_error)?;
+
+        if let Some(full_name) = repository.full_name.as_ref() {
+            self.renames.check(project, full_name);
+        }
+
+        Ok(Repository {
+            description: repository.description.unwrap_or_default().trim().to_owned(),
+            archived: repository.archived.unwrap_or_default(),
+        })
+    }
+
+    fn get_forks(&self, project: &Project, limit: u8) -> GenericResult<Vec<Fork>> {
+        let instance = self.get_instance(project)?;
+
+        debug!("Getting {} forks...", project.full_name());
+        self.count_request();
+
+        let page = self.runtime.block_on(
+            instance.client.repos(&project.owner, &project.name).list_forks().sort(ForkSort::Stargazers).per_page(limit).send(),
+        ).map_err(humanize_error)?;
+
+        Ok(page.items.into_iter().filter_map(|repository| {
+            repository.full_name.map(|name| Fork {
+                name: provider::format_project_name(project, &name),
+                stars: repository.stargazers_count.unwrap_or_default(),
+                pushed_at: repository.pushed_at,
+            })
+        }).collect())
+    }
+
+    fn get_artifact(&self, project: &Project, artifact: &ArtifactSpec) -> GenericResult<Release> {
+        let instance = self.get_instance(project)?;
+        if instance.token.is_none() && instance.app.is_none() {
+            return Err!("GitHub token or app is required to download workflow artifacts");
+        }
+        self.runtime.block_on(self.get_artifact_async(instance, project.clone(), artifact))
+    }
+
+    // Project names without host refer to the default instance which may be GitHub Enterprise Server
+    fn get_changelog(&self, project: &Project) -> Option<Url> {
+        let instance = self.get_instance(project).ok()?;
+        instance.web_url.join(&format!("{}/{}/releases", project.owner, project.name)).ok()
     }
+
+    // Artifact download URL requires authorization and redirects to a storage on another host, where the Authorization
+    // header is stripped by default.
+    fn get_download_headers(&self, project: &Project, artifact: bool) -> BTreeMap<String, String> {
+        let token = self.get_instance(project).ok()
+            .filter(|_| artifact)
+            .and_then(|instance| self.get_token(instance));
+
+        token.into_iter().map(|token| (header::AUTHORIZATION.to_string(), format!("Bearer {token}"))).collect()
+    }
+
+    fn get_renamed_projects(&self) -> BTreeMap<String, String> {
+        self.renames.get()
+    }
+}
+
+// Returns the instance web URL normalized to be a base for joining paths
+fn get_instance_url(url: &Url) -> GenericResult<Url> {
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err!("Invalid GitHub base URL: {url}");
+    }
+
+    // The client is HTTPS-only, so plain HTTP instances would fail on each request with an obscure connector error
+    if url.scheme() != "https" {
+        return Err!("GitHub base URL must be an HTTPS one: {url}");
+    }
+
+    let mut url = url.clone();
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+
+    Ok(url)
+}
+
+// Octocrab doesn't allow to configure TLS and connection options of its default client, so build the same service
+// stack with our connector
+fn build_client(
+    web_url: &Url, token: Option<&str>, app: Option<AppAuth>, tls: ClientConfig, network: &NetworkConfig,
+) -> GenericResult<Octocrab> {
+    let connector = HttpsConnectorBuilder::new()
+        .with_tls_config(tls)
+        .https_only()
+        .enable_http1()
+        .wrap_connector(network.get_connector());
+
+    let client = hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(connector);
+
+    let auth_header = token.map(|token| format!("Bearer {token}").parse()).transpose()
+        .map_err(|_| "Invalid GitHub token")?;
+
+    // GitHub Enterprise Server serves API under the instance URL
+    let (base_uri, upload_uri) = if web_url.as_str() == PUBLIC_URL {
+        (Uri::from_static("https://api.github.com"), Uri::from_static("https://uploads.github.com"))
+    } else {
+        let uri = |path: &str| -> GenericResult<Uri> {
+            let url = web_url.join(path)?;
+            Ok(Uri::try_from(url.as_str()).map_err(|e| format!("Invalid GitHub URL ({url}): {e}"))?)
+        };
+        (uri("api/v3")?, uri("api/uploads")?)
+    };
+
+    Ok(OctocrabBuilder::new_empty()
+        .with_service(client)
+        .with_layer(&RateLimitLayer::default())
+        .with_layer(&FollowRedirectLayer::new())
+        .with_layer(&ExtraHeadersLayer::new(Arc::new(vec![
+            (header::USER_AGENT, HeaderValue::from_static(util::USER_AGENT)),
+        ])))
+        .with_layer(&BaseUriLayer::new(base_uri.clone()))
+        .with_layer(&AuthHeaderLayer::new(auth_header, base_uri, upload_uri))
+        .with_auth(match app {
+            Some(app) => AuthState::App(app),
+            None => AuthState::None,
+        })
+        .build()?)
 }
 
+fn optional<T>(result: octocrab::Result<T>) -> GenericResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::GitHub {source, ..}) if source.status_code == StatusCode::NOT_FOUND => Ok(None),
+        Err(err) => Err!("{}", humanize_error(err)),
+    }
+}
+
+// Parses GitHub (owner/name), Gitea-compatible service (host/owner/name) or S3 (s3://bucket/prefix) project name.
+// Project URLs copied from the browser (https://host/owner/name, possibly pointing to some project page) and clone URLs
+// are accepted as well.
 pub fn parse_project_name(full_name: &str) -> GenericResult<Project> {
-    let mut parts = full_name.split('/');
-
-    let owner = parts.next();
-    let name = parts.next();
-    let extra = parts.next();
-    let changelog = Url::parse(&format!("https://github.com/{}/releases", full_name)).ok();
-
-    Ok(match (owner, name, extra, changelog) {
-        (Some(owner), Some(name), None, Some(changelog)) => Project {
-            name: name.to_owned(),
-            owner: owner.to_owned(),
-            changelog,
-        },
-        _ => {
-            return Err!("Invalid GitHub project name");
-        },
+    let (is_url, path) = match full_name.split_once("://") {
+        Some((scheme, path)) if scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("http") => (true, path),
+        Some(("s3", _)) => return s3::parse_project(full_name),
+        Some(_) => return Err!("Invalid project name"),
+        None => (false, full_name),
+    };
+
+    let mut parts: Vec<_> = path.trim_end_matches('/').split('/').collect();
+    if is_url {
+        parts.truncate(3);
+    }
+
+    let (host, owner, name) = match parts.as_slice() {
+        [owner, name] if !is_url => (None, owner, name),
+        [host, owner, name] if host.contains('.') => (Some(host.to_string()), owner, name),
+        _ => return Err!("Invalid project name"),
+    };
+    let name = name.strip_suffix(".git").unwrap_or(name);
+
+    if owner.is_empty() || name.is_empty() {
+        return Err!("Invalid project name");
+    }
+
+    let changelog = Url::parse(&format!("https://{}/{owner}/{name}/releases", host.as_deref().unwrap_or("github.com")))
+        .map_err(|_| "Invalid project name")?;
+
+    Ok(Project {
+        host,
+        name: name.to_string(),
+        owner: owner.to_string(),
+        changelog,
     })
 }
 
@@ -136,4 +636,55 @@ fn humanize_error(err: Error) -> String {
     }
 
     message
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(name, host, full_name, changelog,
+        case("owner/app", None, "owner/app", "https://github.com/owner/app/releases"),
+        case("codeberg.org/owner/app", Some("codeberg.org"), "codeberg.org/owner/app", "https://codeberg.org/owner/app/releases"),
+        case("owner/app.git", None, "owner/app", "https://github.com/owner/app/releases"),
+        case("github.com/owner/app/", Some("github.com"), "github.com/owner/app", "https://github.com/owner/app/releases"),
+        case("https://github.com/owner/app", Some("github.com"), "github.com/owner/app", "https://github.com/owner/app/releases"),
+        case("https://github.com/owner/app.git", Some("github.com"), "github.com/owner/app", "https://github.com/owner/app/releases"),
+        case("https://codeberg.org/owner/app/releases/tag/v1.0.0", Some("codeberg.org"), "codeberg.org/owner/app", "https://codeberg.org/owner/app/releases"),
+        case("s3://bucket/tools/app/", Some("s3"), "s3://bucket/tools/app", "s3://bucket/tools/app/"),
+    )]
+    fn project_name(name: &str, host: Option<&str>, full_name: &str, changelog: &str) {
+        let project = parse_project_name(name).unwrap();
+        assert_eq!(project.host.as_deref(), host);
+        assert_eq!(project.full_name(), full_name);
+        assert_eq!(project.changelog.as_str(), changelog);
+    }
+
+    #[rstest(name,
+        case("app"),
+        case("owner/"),
+        case("owner/app/extra"),
+        case("a.b/owner/app/extra"),
+        case("https://owner/app"),
+        case("https://github.com/owner"),
+        case("ssh://github.com/owner/app"),
+        case("s3:///tools/app"),
+    )]
+    fn invalid_project_name(name: &str) {
+        assert!(parse_project_name(name).is_err());
+    }
+
+    #[rstest(url, expected,
+        case("https://github.example.com", "https://github.example.com/"),
+        case("https://example.com/github", "https://example.com/github/"),
+        case("http://github.example.com", "GitHub base URL must be an HTTPS one: http://github.example.com/"),
+        case("ftp://github.example.com", "Invalid GitHub base URL: ftp://github.example.com/"),
+    )]
+    fn instance_url(url: &str, expected: &str) {
+        let result = match get_instance_url(&Url::parse(url).unwrap()) {
+            Ok(url) => url.to_string(),
+            Err(err) => err.to_string(),
+        };
+        assert_eq!(result, expected);
+    }
+}
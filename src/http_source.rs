@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use chrono::{DateTime, Utc};
+use http::header;
+use log::{debug, trace};
+use regex::Regex;
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder};
+
+use crate::core::GenericResult;
+use crate::network::NetworkConfig;
+use crate::project::Project;
+use crate::provider::{ReleaseProvider, Repository};
+use crate::release::{Asset, Release};
+use crate::tls::TlsConfig;
+use crate::tool::{ReleaseStrategy, UrlSpec};
+use crate::util;
+use crate::version::ReleaseVersion;
+
+// Latest version URL is expected to return a small text file or an HTML page with links to the releases
+const MAX_CONTENTS_SIZE: u64 = 1024 * 1024;
+
+// Discovers releases of tools which are published on plain HTTP servers
+pub struct HttpSource {
+    client: Client,
+}
+
+impl HttpSource {
+    pub fn new(tls: &TlsConfig, network: &NetworkConfig) -> GenericResult<HttpSource> {
+        let client = ClientBuilder::new().user_agent(util::USER_AGENT);
+        let client = network.configure_client(client);
+        let client = tls.configure_client(client)?.build()?;
+        Ok(HttpSource {client})
+    }
+
+    // Plain HTTP source is specified per tool, so each tool gets its own provider
+    pub fn get_provider(&self, spec: &UrlSpec, headers: &BTreeMap<String, String>) -> UrlProvider {
+        UrlProvider {
+            client: self.client.clone(),
+            spec: spec.clone(),
+            headers: headers.clone(),
+        }
+    }
+}
+
+pub struct UrlProvider {
+    client: Client,
+    spec: UrlSpec,
+    headers: BTreeMap<String, String>,
+}
+
+impl UrlProvider {
+    // The tool has no project, so it's represented by a pseudo project named after the tool
+    pub fn get_project(&self, name: &str) -> Project {
+        let version_url = &self.spec.latest_version_url;
+
+        Project {
+            host: None,
+            owner: version_url.host_str().unwrap_or_default().to_owned(),
+            name: name.to_owned(),
+            changelog: version_url.clone(),
+        }
+    }
+
+    // Asset time identifies the cached asset and the installed binary, so use the server's modification time if it's
+    // available
+    fn get_modify_time(&self, url: &url::Url) -> DateTime<Utc> {
+        let time = add_headers(self.client.head(url.clone()), &self.headers).send().ok()
+            .filter(|response| response.status().is_success())
+            .and_then(|response| {
+                let value = response.headers().get(header::LAST_MODIFIED)?.to_str().ok()?.to_owned();
+                DateTime::parse_from_rfc2822(&value).ok()
+            });
+
+        match time {
+            Some(time) => time.into(),
+            None => {
+                debug!("Unable to get {url} modification time. Using the current time.");
+                Utc::now()
+            },
+        }
+    }
+}
+
+impl ReleaseProvider for UrlProvider {
+    // Represents the latest version as a release with a single asset. There are no other versions, so tag and release
+    // strategy don't matter.
+    fn get_releases(
+        &self, project: &Project, _tag: Option<&str>, _strategy: ReleaseStrategy, _max_pages: usize,
+    ) -> GenericResult<Vec<Release>> {
+        let version_url = &self.spec.latest_version_url;
+        debug!("Getting {} latest version from {version_url}...", project.name);
+
+        let response = add_headers(self.client.get(version_url.clone()), &self.headers).send()?;
+        if !response.status().is_success() {
+            return Err!("The server returned an error: {}", response.status());
+        }
+
+        let mut contents = Vec::new();
+        response.take(MAX_CONTENTS_SIZE).read_to_end(&mut contents)?;
+        let contents = String::from_utf8_lossy(&contents);
+        trace!("Got the following contents:{}", util::format_multiline(&contents));
+
+        let version = discover_version(&contents, self.spec.version_regex.as_deref())?;
+        let url = self.spec.get_url(&version)?;
+        debug!("The latest version is {version}: {url}.");
+
+        let asset_name = url.path_segments().and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("Unable to determine asset name from {url}"))?
+            .to_owned();
+
+        let time = self.get_modify_time(&url);
+
+        Ok(vec![Release::new(project.clone(), &version, vec![Asset {
+            name: asset_name,
+            time,
+            url,
+        }])])
+    }
+
+    // Plain HTTP sources have no repository
+    fn get_repository(&self, _project: &Project) -> GenericResult<Repository> {
+        Ok(Repository {description: String::new(), archived: false})
+    }
+}
+
+fn add_headers(mut request: RequestBuilder, headers: &BTreeMap<String, String>) -> RequestBuilder {
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    request
+}
+
+// Prefers the highest stable version when the contents has multiple matches, so it may be a directory listing
+fn discover_version(contents: &str, regex: Option<&str>) -> GenericResult<String> {
+    let Some(regex) = regex else {
+        let version = contents.trim();
+        if version.is_empty() || version.contains(char::is_whitespace) {
+            return Err!("Got an invalid version: {version:?}");
+        }
+        return Ok(version.to_owned());
+    };
+
+    let regex = Regex::new(regex)?;
+    let matches: Vec<&str> = regex.captures_iter(contents)
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(0)))
+        .map(|value| value.as_str())
+        .filter(|value| !value.is_empty())
+        .collect();
+
+    let highest = matches.iter().filter_map(|value| match ReleaseVersion::new(value) {
+        ReleaseVersion::Version(version) if version.pre.is_empty() => Some((version, value)),
+        _ => None,
+    }).max_by(|(a, _), (b, _)| a.cmp(b));
+
+    match highest {
+        Some((_, version)) => Ok(version.to_string()),
+        None => Ok(matches.first().ok_or("The version regex doesn't match the latest version URL contents")?.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(contents, regex, version,
+        case("v1.31.0\n", None, "v1.31.0"),
+        case(r#"{"version": "2.5.1"}"#, Some(r#""version": "([^"]+)""#), "2.5.1"),
+        case(indoc!(r#"
+            <a href="tool-1.9.0/">tool-1.9.0/</a>
+            <a href="tool-1.10.0/">tool-1.10.0/</a>
+            <a href="tool-1.11.0-rc1/">tool-1.11.0-rc1/</a>
+            <a href="tool-1.2.0/">tool-1.2.0/</a>
+        "#), Some(r"tool-([^/]+)/"), "1.10.0"),
+        case("build-b123", Some(r"b\d+"), "b123"),
+    )]
+    fn latest_version(contents: &str, regex: Option<&str>, version: &str) {
+        assert_eq!(discover_version(contents, regex).unwrap(), version);
+    }
+
+    #[rstest(contents, regex,
+        case("", None),
+        case("<html>\n</html>", None),
+        case("1.0.0", Some(r"v(\d+)")),
+    )]
+    fn invalid_latest_version(contents: &str, regex: Option<&str>) {
+        assert!(discover_version(contents, regex).is_err());
+    }
+}
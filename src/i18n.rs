@@ -0,0 +1,155 @@
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use tabled::Table;
+use tabled::settings::{Format, object::Rows};
+
+// Small localization layer for user-facing strings: prompts, table headers and status words. Messages are identified
+// by their English text, which is used as is when there is no translation for the user's language. Log messages and
+// machine-readable output formats are always in English.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Language {
+    English,
+    Russian,
+}
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+pub fn tr(message: &str) -> &str {
+    match language() {
+        Language::English => message,
+        Language::Russian => translate_russian(message).unwrap_or(message),
+    }
+}
+
+// Translates the table column names
+pub fn localize_header(table: &mut Table) {
+    table.modify(Rows::first(), Format::content(|header| tr(header).to_owned()));
+}
+
+// Translates the message template and substitutes its {} placeholders with the arguments in order
+pub fn trf(template: &str, args: &[&dyn Display]) -> String {
+    substitute(tr(template), args)
+}
+
+// Parses yes/no answer to a prompt in any of the supported languages
+pub fn parse_answer(answer: &str) -> Option<bool> {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" | "д" | "да" => Some(true),
+        "n" | "no" | "н" | "нет" => Some(false),
+        _ => None,
+    }
+}
+
+fn language() -> Language {
+    *LANGUAGE.get_or_init(|| detect_language(|name| env::var(name).ok()))
+}
+
+// The locale variables are checked in POSIX precedence order: LC_ALL overrides LC_MESSAGES, which overrides LANG
+fn detect_language(get_env: impl Fn(&str) -> Option<String>) -> Language {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].into_iter()
+        .filter_map(get_env)
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+
+    if locale.starts_with("ru") {
+        Language::Russian
+    } else {
+        Language::English
+    }
+}
+
+fn substitute(template: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+
+    let mut result = parts.next().unwrap_or_default().to_owned();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            result += &arg.to_string();
+        }
+        result += part;
+    }
+
+    result
+}
+
+fn translate_russian(message: &str) -> Option<&'static str> {
+    Some(match message {
+        // Prompts
+        "(y/n)" => "(д/н)",
+        "{} Assuming yes (non-interactive mode)." => "{} Предполагается «да» (неинтерактивный режим).",
+        "{} Assuming no (non-interactive mode)." => "{} Предполагается «нет» (неинтерактивный режим).",
+        "Are you sure want to uninstall the specified tools?" => "Вы уверены, что хотите удалить указанные инструменты?",
+        "Delete them to free {}?" => "Удалить их, чтобы освободить {}?",
+        "Restore {} from {}?" => "Восстановить {} из {}?",
+        "The tool is already registered with different configuration. Override it?" =>
+            "Инструмент уже зарегистрирован с другой конфигурацией. Перезаписать её?",
+        "Update the configuration file to use the new project names?" =>
+            "Обновить файл конфигурации, чтобы использовать новые имена проектов?",
+        "{} is not in $PATH. Add it to {}?" => "{} отсутствует в $PATH. Добавить его в {}?",
+        "Sign {}? Do it only if you trust its contents: binup relies on it to decide which tools are up-to-date." =>
+            "Подписать {}? Делайте это, только если доверяете его содержимому: binup опирается на него, чтобы решить, какие инструменты актуальны.",
+
+        // Table headers
+        "Name" => "Название",
+        "Installed" => "Установлена",
+        "Latest" => "Последняя",
+        "Last run" => "Последний запуск",
+        "Description" => "Описание",
+        "Changelog" => "Изменения",
+        "Asset" => "Файл релиза",
+        "Binary" => "Исполняемый файл",
+        "Project" => "Проект",
+        "Stars" => "Звёзды",
+        "Latest release" => "Последний релиз",
+        "Released" => "Выпущен",
+        "Tool" => "Инструмент",
+        "Download" => "Загрузка",
+        "Extract" => "Распаковка",
+        "Install" => "Установка",
+        "Hooks" => "Хуки",
+        "Total" => "Всего",
+
+        // Status words
+        "ok" => "ок",
+        "failed" => "ошибка",
+        "failed at {}: {}" => "ошибка в {}: {}",
+        "cached" => "из кэша",
+        "archived" => "в архиве",
+        "held: {}" => "заморожен: {}",
+        "unused since {}" => "не используется с {}",
+
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(lc_all, lang, expected,
+        case(None, None, Language::English),
+        case(None, Some("ru_RU.UTF-8"), Language::Russian),
+        case(Some(""), Some("ru_RU.UTF-8"), Language::Russian),
+        case(Some("C"), Some("ru_RU.UTF-8"), Language::English),
+        case(Some("ru_RU.UTF-8"), Some("en_US.UTF-8"), Language::Russian),
+    )]
+    fn language_detection(lc_all: Option<&str>, lang: Option<&str>, expected: Language) {
+        let language = detect_language(|name| match name {
+            "LC_ALL" => lc_all.map(ToOwned::to_owned),
+            "LANG" => lang.map(ToOwned::to_owned),
+            _ => None,
+        });
+        assert_eq!(language, expected);
+    }
+
+    #[test]
+    fn substitution() {
+        let template = translate_russian("Restore {} from {}?").unwrap();
+        assert_eq!(substitute(template, &[&"a", &"b"]), "Восстановить a из b?");
+        assert_eq!(substitute("held: {}", &[&"reason"]), "held: reason");
+    }
+}
@@ -0,0 +1,119 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use log::info;
+
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::download::{self, Cache, DownloadOptions, ExtractOptions, Installer};
+use crate::file_types::{self, FileType};
+use crate::install;
+use crate::netrc::Netrc;
+use crate::porcelain::{self, Porcelain};
+use crate::provider::Providers;
+use crate::state::State;
+use crate::util;
+
+// Lists contents of the tool's release asset without installing it, which helps to write binary matchers
+pub fn inspect(config: &Config, name: &str, porcelain: Option<Porcelain>) -> GenericResult<ExitCode> {
+    let spec = config.tools.get(name).ok_or_else(|| format!(
+        "{name} isn't specified in the configuration file"))?;
+
+    let providers = Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?;
+    let (release, asset) = providers.get_tool_release(name, spec)?;
+    let asset = asset?;
+
+    let state = State::load(config)?;
+    let cache_path = config.get_tool_cache_path(name, &asset.name);
+    let cached = state.tools.get(name).is_some_and(|info| install::get_cached_asset(&cache_path, &asset, info));
+
+    let options = ExtractOptions {
+        decompressors: &config.decompressors,
+        limits: &config.extraction_limits,
+    };
+
+    let mut lister = Lister::default();
+
+    if cached {
+        info!("Inspecting {} ({}) from the cache...", asset.name, release.version);
+        download::extract_cached(&cache_path, &asset.name, &options, &mut lister).map_err(|e| format!(
+            "Failed to extract {cache_path:?}: {e}"))?;
+    } else {
+        info!("Inspecting {} ({})...", asset.name, release.version);
+        let cache = Cache::new(&cache_path)?;
+        download::download(
+            &asset.url, &asset.name, &DownloadOptions {
+                headers: &providers.get_download_headers(spec),
+                forward_auth_on_redirect: spec.forward_auth_on_redirect,
+                netrc: config.netrc.then(Netrc::load).transpose()?.as_ref(),
+                tls: &config.tls,
+                network: &config.network,
+            }, &options, Some(&cache), &mut lister,
+        ).map_err(|e| format!("Failed to download {}: {e}", asset.url))?;
+        cache.commit()?;
+    }
+
+    if let Some(Porcelain::V1) = porcelain {
+        for file in &lister.files {
+            print!("{}", porcelain::format_record("file", &[
+                &format!("{:04o}", file.mode & 0o7777),
+                &file.size.to_string(),
+                &file.file_type,
+                &file.path.to_string_lossy(),
+            ]));
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let size_width = lister.files.iter().map(|file| util::format_size(file.size).len()).max().unwrap_or_default();
+
+    for file in &lister.files {
+        println!("{:04o} {:>size_width$} {} ({})",
+            file.mode & 0o7777, util::format_size(file.size), file.path.display(), file.file_type);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+struct ArchiveFile {
+    path: PathBuf,
+    mode: u32,
+    size: u64,
+    file_type: String,
+}
+
+#[derive(Default)]
+struct Lister {
+    files: Vec<ArchiveFile>,
+}
+
+impl Installer for Lister {
+    fn on_file(&mut self, path: &Path, mode: u32, data: &mut dyn Read) -> EmptyResult {
+        let mut header = Vec::with_capacity(file_types::HEADER_SIZE);
+        data.take(file_types::HEADER_SIZE as u64).read_to_end(&mut header)?;
+        let size = header.len() as u64 + io::copy(data, &mut io::sink())?;
+
+        self.files.push(ArchiveFile {
+            path: path.to_owned(),
+            mode,
+            size,
+            file_type: describe_file_type(&header),
+        });
+
+        Ok(())
+    }
+}
+
+fn describe_file_type(header: &[u8]) -> String {
+    let format_arch = |arch: &Option<platforms::Arch>| arch.map(|arch| arch.as_str()).unwrap_or("unknown arch");
+
+    match FileType::detect(header) {
+        Some(FileType::Elf(arch)) => format!("ELF, {}", format_arch(&arch)),
+        Some(FileType::MachO(arch)) => format!("Mach-O, {}", format_arch(&arch)),
+        Some(FileType::UniversalMachO(archs)) => format!(
+            "universal Mach-O, {}", archs.iter().map(format_arch).collect::<Vec<_>>().join(", ")),
+        None if header.starts_with(b"#!") => "script".to_owned(),
+        None => "data".to_owned(),
+    }
+}
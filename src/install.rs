@@ -1,37 +1,62 @@
-use std::fs::{self, OpenOptions};
-use std::io::{self, Read};
-use std::os::unix::fs::OpenOptionsExt;
-use std::path::{Path, PathBuf};
-use std::process::{Command, ExitCode};
-use std::time::SystemTime;
-
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File, OpenOptions, Permissions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::iter;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt, symlink};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
 use easy_logging::GlobalContext;
 use log::{Level, debug, info, warn, error};
 use semver::Version;
+use serde::Serialize;
 use url::Url;
 
+use crate::access::FileAccess;
+use crate::checksums;
 use crate::config::Config;
-use crate::core::{EmptyResult, GenericResult};
-use crate::download;
-use crate::github::{self, Github};
+use crate::core::{EmptyResult, GenericError, GenericResult};
+use crate::download::{self, Cache, DownloadOptions, ExtractOptions};
+use crate::file_types::{self, FileType};
+use crate::github;
+use crate::i18n;
 use crate::matcher::Matcher;
-use crate::release::{self, Release};
-use crate::tool::ToolSpec;
+use crate::metadata::MetadataCache;
+use crate::netrc::Netrc;
+use crate::provider::Providers;
+use crate::reflink;
+use crate::release::{self, Asset, Release};
+use crate::shell_path;
+use crate::shims::{self, Shim};
+use crate::state::{InstallInfo, State};
+use crate::timings::{self, Phase, Timings};
+use crate::tool::{ChangeKind, PostHook, ToolSpec};
 use crate::util;
 use crate::version::{self, ReleaseVersion};
+use crate::xattr;
 
 #[derive(Clone, Copy)]
 pub enum Mode {
     Install {
         force: bool,
         recheck_spec: bool,
+        // Reinstall from the cached archive if the latest release asset is the same as the installed one
+        reuse_cache: bool,
+    },
+    Upgrade {
+        // Skip the tools which have been successfully checked within the interval
+        min_check_interval: Option<Duration>,
     },
-    Upgrade,
 }
 
-pub fn install(config: &Config, mode: Mode, names: Vec<String>) -> GenericResult<ExitCode> {
+pub fn install(
+    config: &mut Config, mode: Mode, names: Vec<String>, transactional: bool, force_overwrite: bool, timings: bool,
+) -> GenericResult<ExitCode> {
     let tools: Vec<(&String, &ToolSpec)> = if names.is_empty() {
-        config.tools.iter().collect()
+        config.get_active_tools().collect()
     } else {
         let mut selected = Vec::new();
 
@@ -44,27 +69,263 @@ pub fn install(config: &Config, mode: Mode, names: Vec<String>) -> GenericResult
         selected
     };
 
-    let github = Github::new(&config.github)?;
+    let start_time = Instant::now();
+    let bulk = tools.len() > 1;
+    let mut checked = false;
+    let timings = Timings::new(timings);
+
+    let providers = Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?;
+    let mut state = State::load(config)?;
+    let mut installations = Vec::new();
+
+    // The cache is only used for warnings here, so it's not critical
+    let metadata = MetadataCache::load(&config.get_metadata_cache_path()).unwrap_or_else(|e| {
+        debug!("{e}.");
+        MetadataCache::default()
+    });
 
+    // Download and verify all the tools first to not leave them partially upgraded on network errors
     for (name, spec) in tools {
         let _logging_context = GlobalContext::new_conditional(Level::Debug, name);
 
+        if let Mode::Upgrade {min_check_interval: Some(interval)} = mode {
+            let binary_path = config.get_tool_path(name, spec);
+            if is_recently_checked(&state, name, &spec.get_hash()?, &binary_path, interval) {
+                info!("{name} has been checked recently. Skipping it.");
+                continue;
+            }
+        }
+        checked = true;
+
         if names.is_empty() {
             info!("Checking {name}...");
         }
 
-        let install_path = config.get_tool_path(name, spec);
-        install_tool(name, spec, &github, mode, &install_path).map_err(|e| format!(
-            "{name}: {e}"))?;
+        if matches!(mode, Mode::Upgrade {..}) && metadata.tools.get(name).is_some_and(|metadata| metadata.archived) {
+            warn!("{name}: The project repository is archived.");
+        }
+
+        let installation = match prepare_tool(
+            config, name, spec, &providers, mode, state.tools.get(name), force_overwrite, &timings,
+        ) {
+            Ok(installation) => installation,
+            Err(err) => {
+                record_failure(&mut state, name, &err);
+                return Err!("{name}: {err}");
+            },
+        };
+
+        if installation.is_none() {
+            state.record_run(name, None);
+        }
+        installations.extend(installation);
+    }
+    state.save()?;
+
+    if let (Some(script), false) = (config.pre_run.as_ref(), installations.is_empty()) {
+        timings.measure(timings::GLOBAL, Phase::Hooks, || run_pre_run_script(script, mode, &installations))?;
     }
 
+    let changed = !installations.is_empty();
+    let post_upgrade = config.post_upgrade.as_ref().filter(|_| changed);
+    let downloaded = installations.iter().map(|installation| installation.downloaded).sum();
+    let directories: Vec<PathBuf> = installations.iter()
+        .filter_map(|installation| installation.get_command_path().parent().map(Path::to_owned))
+        .collect();
+    let directories: Vec<&Path> = directories.iter().map(PathBuf::as_path).collect();
+
+    // The tools are installed sequentially, so their post-install hooks never run concurrently
+    if !transactional {
+        for installation in installations {
+            let name = installation.name.clone();
+            let _logging_context = GlobalContext::new_conditional(Level::Debug, &name);
+
+            let info = match installation.install(&timings, false) {
+                Ok(info) => info,
+                Err(err) => {
+                    record_failure(&mut state, &name, &err);
+                    return Err!("{name}: {err}");
+                },
+            };
+
+            state.record_run(&name, None);
+            state.tools.insert(name, info);
+            state.save()?;
+        }
+
+        if let Some(script) = post_upgrade {
+            timings.measure(timings::GLOBAL, Phase::Hooks, || run_script("post-upgrade script", script, &[]))?;
+        }
+
+        if bulk && checked {
+            print_summary(&providers, start_time, downloaded);
+        }
+        timings.print();
+        shell_path::check(&directories);
+        update_renamed_projects(config, &providers);
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut backups = Vec::new();
+    let mut infos = Vec::new();
+
+    for installation in installations {
+        let name = installation.name.clone();
+        let _logging_context = GlobalContext::new_conditional(Level::Debug, &name);
+
+        match Backup::new(&installation.path, installation.installer.get_tree_path()).and_then(|backup| {
+            backups.push(backup);
+            if let Some(shim) = installation.shim.as_ref() {
+                backups.push(Backup::new(&shim.path, None)?);
+            }
+            installation.install(&timings, true)
+        }) {
+            Ok(info) => infos.push((name, info)),
+            Err(err) => {
+                error!("{name}: {err}.");
+                record_failure(&mut state, &name, &err);
+                rollback(backups);
+                return Err!("The upgrade has been rolled back");
+            },
+        }
+    }
+
+    if let Some(script) = post_upgrade {
+        if let Err(err) = timings.measure(timings::GLOBAL, Phase::Hooks, || run_script("post-upgrade script", script, &[])) {
+            error!("{err}.");
+            rollback(backups);
+            return Err!("The upgrade has been rolled back");
+        }
+    }
+
+    for backup in backups {
+        backup.remove();
+    }
+
+    for (name, info) in infos {
+        state.record_run(&name, None);
+        state.tools.insert(name, info);
+    }
+    state.save()?;
+
+    if bulk && checked {
+        print_summary(&providers, start_time, downloaded);
+    }
+    timings.print();
+    shell_path::check(&directories);
+    update_renamed_projects(config, &providers);
+
     Ok(ExitCode::SUCCESS)
 }
 
-pub fn install_spec(config: &mut Config, name: Option<String>, spec: ToolSpec, force: bool) -> GenericResult<ExitCode> {
+// Offers to switch the configuration to the new names of renamed or transferred projects
+fn update_renamed_projects(config: &mut Config, providers: &Providers) {
+    let renames = providers.get_renamed_projects();
+    if renames.is_empty() {
+        return;
+    }
+
+    let updates: Vec<(String, ToolSpec)> = config.tools.iter().filter_map(|(name, spec)| {
+        let mut spec = spec.clone();
+        let mut changed = false;
+
+        for project in &mut spec.project {
+            if let Some(new_name) = renames.get(project.as_str()) {
+                project.clone_from(new_name);
+                changed = true;
+            }
+        }
+
+        changed.then(|| (name.clone(), spec))
+    }).collect();
+
+    if updates.is_empty() || !util::is_interactive() {
+        return;
+    }
+
+    match util::confirm(i18n::tr("Update the configuration file to use the new project names?")) {
+        Ok(true) => {},
+        Ok(false) => return,
+        Err(err) => {
+            error!("{err}.");
+            return;
+        },
+    }
+
+    let result = config.edit(|config, raw| {
+        for (name, spec) in &updates {
+            config.update_tool(raw, name, spec)?;
+        }
+        Ok(())
+    }, |_| Ok(()));
+
+    match result {
+        Ok(()) => info!("The configuration file has been updated."),
+        Err(err) => error!("Failed to update the configuration file: {err}"),
+    }
+}
+
+// Checks whether the last check of the tool has succeeded within the interval, so it may be skipped without network
+// access. The check isn't skipped if the binary is missing or the tool spec has been changed since the installation.
+fn is_recently_checked(state: &State, name: &str, spec_hash: &str, binary_path: &Path, interval: Duration) -> bool {
+    let Some(info) = state.tools.get(name) else {
+        return false;
+    };
+
+    if info.spec_hash.as_deref() != Some(spec_hash) || !binary_path.exists() {
+        return false;
+    }
+
+    state.runs.get(name).is_some_and(|run| {
+        run.error.is_none() && (Utc::now() - run.time).to_std().is_ok_and(|age| age < interval)
+    })
+}
+
+// Remembers the failure to show it in `binup list` output
+fn record_failure(state: &mut State, name: &str, err: &GenericError) {
+    state.record_run(name, Some(&err.to_string()));
+    if let Err(err) = state.save() {
+        error!("{err}.");
+    }
+}
+
+fn print_summary(providers: &Providers, start_time: Instant, downloaded: u64) {
+    let github = providers.github();
+
+    let rate_limit = match github.get_rate_limit() {
+        Ok((remaining, limit)) => format!("{remaining}/{limit}"),
+        Err(err) => {
+            debug!("Failed to get GitHub API rate limit: {err}.");
+            "unknown".to_owned()
+        },
+    };
+
+    info!("Downloaded {} in {:.1}s. GitHub API requests: {} (remaining rate limit: {rate_limit}).",
+        util::format_size(downloaded), start_time.elapsed().as_secs_f64(), github.get_request_count());
+}
+
+fn rollback(backups: Vec<Backup>) {
+    info!("Rolling back the changes...");
+
+    for backup in backups.into_iter().rev() {
+        if let Err(err) = backup.restore() {
+            error!("{err}.");
+        }
+    }
+}
+
+pub fn install_spec(
+    config: &mut Config, name: Option<String>, spec: ToolSpec, force: bool, force_overwrite: bool, timings: bool,
+) -> GenericResult<ExitCode> {
     let name = match name {
         Some(name) => name,
-        None => github::parse_project_name(&spec.project)?.name,
+        None => {
+            let project = github::parse_project_name(spec.project.first().ok_or("No project is specified")?)?;
+            // S3 project name is a bucket prefix which may consist of several directories
+            project.name.rsplit('/').next().filter(|name| !name.is_empty()).ok_or(
+                "Unable to derive the tool name from the project name")?.to_owned()
+        },
     };
 
     let mut update_config = true;
@@ -72,43 +333,140 @@ pub fn install_spec(config: &mut Config, name: Option<String>, spec: ToolSpec, f
     if let Some(registered) = config.tools.get(&name) {
         if *registered == spec {
             update_config = false
-        } else if !force && !util::confirm("The tool is already registered with different configuration. Override it?") {
+        } else if !force && !util::confirm(
+            i18n::tr("The tool is already registered with different configuration. Override it?"))? {
             return Ok(ExitCode::FAILURE);
         }
     }
 
-    let github = Github::new(&config.github)?;
-    let install_path = config.get_tool_path(&name, &spec);
-    let install_mode = Mode::Install {force, recheck_spec: update_config};
+    let providers = Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?;
+    let mut state = State::load(config)?;
+    let install_mode = Mode::Install {force, recheck_spec: update_config, reuse_cache: false};
+    let timings = Timings::new(timings);
 
-    if update_config {
+    let result = if update_config {
         config.edit(
             |config, raw| config.update_tool(raw, &name, &spec),
-            |_| install_tool(&name, &spec, &github, install_mode, &install_path),
-        )?;
+            |config| install_tool(
+                config, &name, &spec, &providers, install_mode, state.tools.get(&name), force_overwrite, &timings),
+        )
     } else {
-        install_tool(&name, &spec, &github, install_mode, &install_path)?;
+        install_tool(config, &name, &spec, &providers, install_mode, state.tools.get(&name), force_overwrite, &timings)
+    };
+
+    let info = match result {
+        Ok(info) => info,
+        Err(err) => {
+            if config.tools.contains_key(&name) {
+                record_failure(&mut state, &name, &err);
+            }
+            return Err(err);
+        },
+    };
+
+    state.record_run(&name, None);
+    state.save()?;
+
+    if let Some(info) = info {
+        state.tools.insert(name.clone(), info);
+        state.save()?;
+
+        if let Some(directory) = config.get_command_path(&name, &spec).parent() {
+            shell_path::check(&[directory]);
+        }
     }
 
+    timings.print();
     Ok(ExitCode::SUCCESS)
 }
 
-fn install_tool(name: &str, spec: &ToolSpec, github: &Github, mut mode: Mode, install_path: &Path) -> EmptyResult {
-    let tool = crate::tool::check(install_path)?;
+// Registers binup itself as a managed tool
+pub fn install_self(config: &mut Config, force: bool, mut force_overwrite: bool, timings: bool) -> GenericResult<ExitCode> {
+    let name = env!("CARGO_PKG_NAME");
+    let project = env!("CARGO_PKG_REPOSITORY").trim_start_matches("https://github.com/");
+
+    let spec: ToolSpec = serde_yaml::from_str(&format!("{{project: {project}, binary_matcher: {name}}}"))?;
+    let path = config.get_command_path(name, &spec);
+
+    // binup is usually installed manually or by the bootstrap script, so it's safe to replace the running binary
+    match (fs::canonicalize(&path), std::env::current_exe().and_then(fs::canonicalize)) {
+        (Ok(path), Ok(current)) if path == current => {
+            debug!("{path:?} is the running binary. Allowing to overwrite it.");
+            force_overwrite = true;
+        },
+        (_, Ok(current)) => {
+            warn!("The running binary is {current:?}, but {name} will be installed to {path:?}.");
+        },
+        (_, Err(err)) => {
+            debug!("Unable to determine the running binary path: {err}.");
+        },
+    }
+
+    install_spec(config, Some(name.to_owned()), spec, force, force_overwrite, timings)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn install_tool(
+    config: &Config, name: &str, spec: &ToolSpec, providers: &Providers, mode: Mode, install_info: Option<&InstallInfo>,
+    force_overwrite: bool, timings: &Timings,
+) -> GenericResult<Option<InstallInfo>> {
+    let installation = prepare_tool(config, name, spec, providers, mode, install_info, force_overwrite, timings)?;
+
+    if let (Some(script), Some(installation)) = (config.pre_run.as_ref(), installation.as_ref()) {
+        timings.measure(timings::GLOBAL, Phase::Hooks, || {
+            run_pre_run_script(script, mode, std::slice::from_ref(installation))
+        })?;
+    }
+
+    installation
+        .map(|installation| installation.install(timings, false))
+        .transpose()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prepare_tool(
+    config: &Config, name: &str, spec: &ToolSpec, providers: &Providers, mut mode: Mode, install_info: Option<&InstallInfo>,
+    force_overwrite: bool, timings: &Timings,
+) -> GenericResult<Option<Installation>> {
+    let install_path = &resolve_install_path(config, name, config.get_tool_path(name, spec))?;
+    let tree_path = config.get_tool_tree_path(name, spec);
+
+    let shim = if config.uses_shim(spec) {
+        let path = config.get_command_path(name, spec);
+
+        // Tools installed by binup before enabling shim mode are replaced by the shims
+        if install_info.is_none() && !force_overwrite && crate::tool::check(&path)?.is_some() && !shims::is_shim(&path)? {
+            return Err!(
+                "{path:?} already exists, but it hasn't been installed by binup. Use --force-overwrite to replace it");
+        }
+
+        Some(Shim {path, versions_path: config.get_tool_versions_path(name)})
+    } else {
+        None
+    };
+
+    // The tool is considered as not installed until its shim is in place
+    let tool = match shim.as_ref() {
+        Some(shim) if !shims::is_shim(&shim.path)? => None,
+        _ => crate::tool::check(install_path)?,
+    };
 
     match (mode, tool.is_some()) {
-        (Mode::Install{force: false, recheck_spec: false}, true) => {
+        (Mode::Install{force: false, recheck_spec: false, ..}, true) => {
             info!("{name} is already installed.");
-            return Ok(());
+            return Ok(None);
         },
-        (Mode::Upgrade, false) => {
-            mode = Mode::Install{force: false, recheck_spec: false};
+        (Mode::Upgrade {..}, false) => {
+            mode = Mode::Install{force: false, recheck_spec: false, reuse_cache: false};
         }
+        (Mode::Upgrade {..}, true) => if let Some(reason) = spec.hold.as_ref() {
+            info!("{name} is held: {reason}. Skipping it.");
+            return Ok(None);
+        },
         _ => {},
     }
 
-    let release = github.get_release(&spec.project).map_err(|e| format!(
-        "Failed to get latest release info for {}: {e}", spec.project))?;
+    let (release, asset) = timings.measure(name, Phase::Api, || providers.get_tool_release(name, spec))?;
 
     let release_version = &release.version;
     let changelog = spec.changelog.as_ref().unwrap_or(&release.project.changelog);
@@ -118,42 +476,47 @@ fn install_tool(name: &str, spec: &ToolSpec, github: &Github, mut mode: Mode, in
         debug!("* {}", asset.name)
     }
 
-    let asset = release.select_asset(name, spec.release_matcher.as_ref())?;
+    let asset = asset.map_err(|e| ErrorContext::new(&release, None).wrap(e))?;
+    let context = ErrorContext::new(&release, Some(&asset));
     let release_time: SystemTime = asset.time.into();
     let current_version = tool.as_ref().and_then(|_|
         version::get_binary_version(install_path));
 
-    match mode {
-        Mode::Install {force, recheck_spec: _} => if tool.is_none() {
+    let change = match mode {
+        Mode::Install {force, ..} => if tool.is_none() {
             info!("Installing {name}...");
+            ChangeKind::Install
         } else if force {
-            match current_version {
+            match current_version.as_ref() {
                 Some(current_version) => info!(
                     "Reinstalling {name}: {current_version} -> {release_version}{changelog}",
-                    changelog=format_changelog(changelog, Some(&current_version), release_version),
+                    changelog=format_changelog(changelog, Some(current_version), release_version),
                 ),
 
                 None => info!("Reinstalling {name}..."),
             }
+            ChangeKind::Reinstall
         } else {
             info!("{name} is already installed.");
-            return Ok(());
+            return Ok(None);
         },
 
-        Mode::Upgrade => {
+        Mode::Upgrade {..} => {
+            // Assets of a moving tag are re-uploaded without version change, so only their update time matters
             if match (tool.as_ref(), current_version.as_ref(), &release_version) {
+                (Some(tool), _, _) if spec.tag.is_some() => tool.modify_time == release_time,
                 (_, Some(current_version), ReleaseVersion::Version(latest_version)) => current_version >= latest_version,
                 (Some(tool), _, _) if tool.modify_time == release_time => true,
                 _ => false,
             } {
                 info!("{name} is already up-to-date.");
-                return Ok(());
+                return Ok(None);
             }
 
-            match current_version {
+            match current_version.as_ref() {
                 Some(current_version) => info!(
                     "Upgrading {name}: {current_version} -> {release_version}{changelog}",
-                    changelog=format_changelog(changelog, Some(&current_version), release_version),
+                    changelog=format_changelog(changelog, Some(current_version), release_version),
                 ),
 
                 None => info!(
@@ -161,63 +524,483 @@ fn install_tool(name: &str, spec: &ToolSpec, github: &Github, mut mode: Mode, in
                     changelog=format_changelog(changelog, None, release_version),
                 ),
             }
+
+            ChangeKind::Upgrade
+        },
+    };
+
+    // Protect from clobbering a binary of the same name installed by the system package manager or manually
+    if tool.is_some() && install_info.is_none() && !force_overwrite {
+        return Err!(
+            "{install_path:?} already exists, but it hasn't been installed by binup. Use --force-overwrite to replace it");
+    }
+
+    let access = FileAccess::new(spec)?;
+
+    if let Some(path) = install_path.parent() {
+        access.create_dir_all(path).map_err(|e| context.wrap(e))?;
+    }
+
+    let options = ExtractOptions {
+        decompressors: &config.decompressors,
+        limits: &config.extraction_limits,
+    };
+
+    let netrc = if config.netrc {
+        Some(Netrc::load()?)
+    } else {
+        None
+    };
+
+    let fetch = |asset: &Asset| -> GenericResult<(Installer, String, u64)> {
+        let mut installer = Installer::new(
+            name, &release, spec, install_path, tree_path.as_deref(), access, asset.time.into())?;
+
+        let cache_path = config.get_tool_cache_path(name, &asset.name);
+
+        let cached = match (mode, install_info) {
+            (Mode::Install {reuse_cache: true, ..}, Some(info)) => get_cached_asset(&cache_path, asset, info),
+            _ => false,
+        };
+
+        // The asset is cached only after successful verification and is identified by its checksum
+        let (checksum, downloaded, cache) = if cached {
+            info!("Reinstalling {name} from the cache...");
+            let checksum = timings.measure(name, Phase::Extract, || {
+                download::extract_cached(&cache_path, &asset.name, &options, &mut installer)
+            }).map_err(|e| format!("Failed to extract {cache_path:?}: {e}"))?;
+            (checksum, 0, None)
+        } else {
+            let download_options = DownloadOptions {
+                headers: &providers.get_download_headers(spec),
+                forward_auth_on_redirect: spec.forward_auth_on_redirect,
+                netrc: netrc.as_ref(),
+                tls: &config.tls,
+                network: &config.network,
+            };
+
+            let published = match spec.sha256 {
+                Some(_) => None,
+                None => timings.measure(name, Phase::Download, || checksums::get_expected_checksum(
+                    &release, asset, spec.checksum, &download_options))?,
+            };
+
+            let cache = Cache::new(&cache_path)?;
+            let (checksum, downloaded) = timings.measure(name, Phase::Download, || download::download(
+                &asset.url, &asset.name, &download_options, &options, Some(&cache), &mut installer,
+            )).map_err(|e| format!("Failed to download {}: {e}", asset.url))?;
+
+            if let Some(expected) = published {
+                if !checksum.eq_ignore_ascii_case(&expected.sha256) {
+                    return Err!(
+                        "{} checksum mismatch: {} lists {}, got {checksum}. Refusing to install it",
+                        asset.name, expected.source, expected.sha256);
+                }
+                debug!("{} checksum matches the one listed in {}.", asset.name, expected.source);
+            }
+
+            (checksum, downloaded, Some(cache))
+        };
+
+        debug!("{} SHA-256: {checksum}.", asset.name);
+        if let Some(expected) = spec.sha256.as_ref() {
+            if !checksum.eq_ignore_ascii_case(expected) {
+                return Err!(
+                    "{} checksum mismatch: expected {expected}, got {checksum}. Refusing to install it",
+                    asset.name);
+            }
+        }
+
+        installer.verify(&asset.url)?;
+        timings.measure(name, Phase::Extract, || installer.validate(spec.smoke_test.as_deref()))?;
+
+        if let Some(cache) = cache {
+            cache.commit()?;
+        }
+
+        Ok((installer, checksum, downloaded))
+    };
+
+    // Upstream may upload a corrupted asset, so try the other assets which match the current platform as well. The
+    // checksum identifies the specific asset, so there is no point in trying the others if it's specified.
+    let alternatives = if spec.sha256.is_none() {
+        release.get_alternative_assets(name, spec, &asset)
+    } else {
+        Vec::new()
+    };
+    let mut candidates = iter::once(&asset).chain(alternatives).peekable();
+
+    let (asset, installer, checksum, downloaded) = loop {
+        let candidate = candidates.next().unwrap();
+
+        match fetch(candidate) {
+            Ok((installer, checksum, downloaded)) => break (candidate, installer, checksum, downloaded),
+            Err(err) => match candidates.peek() {
+                Some(next) => warn!("{name}: Failed to install {}: {err}. Trying {} instead...", candidate.name, next.name),
+                None => return Err(ErrorContext::new(&release, Some(candidate)).wrap(err)),
+            },
+        }
+    };
+
+    let info = InstallInfo {
+        version: release_version.to_string(),
+        asset: asset.name.clone(),
+        binary: installer.get_binary().expect("Binary is not selected").to_owned(),
+        time: Some(asset.time),
+        sha256: Some(checksum),
+        url: Some(asset.url.to_string()),
+        spec_hash: Some(spec.get_hash()?),
+    };
+
+    Ok(Some(Installation {
+        name: name.to_owned(),
+        path: install_path.to_owned(),
+        url: asset.url.clone(),
+        info,
+        installer,
+        capabilities: spec.capabilities.clone(),
+        preserve_xattrs: spec.preserve_xattrs,
+        change,
+        current_version,
+        post: spec.post.clone(),
+        shim,
+        overwrite: install_info.is_some() || force_overwrite,
+        downloaded,
+        context,
+    }))
+}
+
+// The install path might be a symlink into a Nix store or another package manager's tree. Silently replacing such
+// symlink breaks the other package manager, so either refuse to install the tool or install it to the symlink target.
+fn resolve_install_path(config: &Config, name: &str, path: PathBuf) -> GenericResult<PathBuf> {
+    let target = match fs::read_link(&path) {
+        Ok(target) => path.parent().map(|parent| parent.join(&target)).unwrap_or(target),
+        Err(err) if matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::InvalidInput) => return Ok(path),
+        Err(err) => return Err!("Unable to read {path:?} symlink: {err}"),
+    };
+
+    // Tools with tree layout are installed as symlinks to their trees
+    if target.starts_with(config.tree_path.join(name)) {
+        return Ok(path);
+    }
+
+    if !config.follow_symlinks {
+        return Err!(concat!(
+            "{:?} is a symlink to {:?}, which might be managed by another package manager. Refusing to replace it ",
+            "(remove the symlink or enable follow_symlinks option to install the tool to the symlink target)",
+        ), path, target);
+    }
+
+    let target = fs::canonicalize(&path).map_err(|e| format!("Unable to resolve {path:?} symlink: {e}"))?;
+    debug!("{path:?} is a symlink to {target:?}. Installing the tool to the symlink target.");
+
+    Ok(target)
+}
+
+// Checks whether the cache contains the same asset as the latest release one
+pub fn get_cached_asset(path: &Path, asset: &Asset, info: &InstallInfo) -> bool {
+    let (Some(time), Some(expected)) = (info.time, info.sha256.as_ref()) else {
+        debug!("The state has no information about the cached asset.");
+        return false;
+    };
+
+    if info.asset != asset.name || time != asset.time {
+        debug!("The latest release asset differs from the installed one.");
+        return false;
+    }
+
+    match download::get_checksum(path) {
+        Ok(checksum) if checksum == *expected => true,
+        Ok(_) => {
+            warn!("{path:?} checksum mismatch. Ignoring the cache.");
+            false
         },
+        Err(err) => {
+            debug!("{err}.");
+            false
+        },
+    }
+}
+
+struct Installation {
+    name: String,
+    path: PathBuf,
+    url: Url,
+    info: InstallInfo,
+    installer: Installer,
+    capabilities: Option<String>,
+    preserve_xattrs: bool,
+    change: ChangeKind,
+    current_version: Option<Version>,
+    post: Option<PostHook>,
+    shim: Option<Shim>,
+    // Whether the install path may be occupied by a binary which hasn't been installed by binup
+    overwrite: bool,
+    downloaded: u64,
+    context: ErrorContext,
+}
+
+impl Installation {
+    fn get_command_path(&self) -> &Path {
+        self.shim.as_ref().map_or(&self.path, |shim| &shim.path)
     }
 
-    let mut installer = Installer::new(name, &release, spec.binary_matcher.clone(), install_path, release_time);
+    // Some other binary might have been put to the install path while the tools were being downloaded
+    fn check_ownership(&self) -> EmptyResult {
+        let path = self.get_command_path();
 
-    download::download(&asset.url, &asset.name, &mut installer).map_err(|e| format!(
-        "Failed to download {}: {e}", asset.url))?;
+        if !self.overwrite && crate::tool::check(path)?.is_some() && !shims::is_shim(path)? {
+            return Err!(
+                "{path:?} already exists, but it hasn't been installed by binup. Use --force-overwrite to replace it");
+        }
+
+        Ok(())
+    }
 
-    installer.finish(&asset.url)?;
+    // In transactional mode the replaced tool tree is kept until the backup is removed, so it can be restored on rollback
+    fn install(self, timings: &Timings, keep_old_tree: bool) -> GenericResult<InstallInfo> {
+        self.check_ownership().map_err(|e| self.context.wrap(e))?;
+
+        timings.measure(&self.name, Phase::Install, || -> EmptyResult {
+            let xattrs = if self.preserve_xattrs && crate::tool::check(&self.path)?.is_some() {
+                xattr::get_security_xattrs(&self.path)?
+            } else {
+                Vec::new()
+            };
+
+            self.installer.finish(keep_old_tree)?;
+
+            if !xattrs.is_empty() {
+                debug!("Restoring {:?} security extended attributes...", self.path);
+                xattr::set_xattrs(&self.path, &xattrs)?;
+            }
+
+            // Capabilities are dropped on binary replacement, so they must be set on each install
+            if let Some(capabilities) = self.capabilities.as_ref() {
+                set_capabilities(&self.path, capabilities)?;
+            }
 
-    if let Some(script) = spec.post.as_ref() {
-        run_post_script(script)?;
+            if let Some(shim) = self.shim.as_ref() {
+                shim.install(&self.name, &self.info.version, &self.path)?;
+            }
+
+            Ok(())
+        }).map_err(|e| self.context.wrap(e))?;
+
+        if let Some(hook) = self.post.as_ref().filter(|hook| {
+            let matches = hook.on.contains(&self.change);
+            if !matches {
+                debug!("Skipping the post-install script: it's not configured to run on {}.", self.change);
+            }
+            matches
+        }) {
+            let path = self.path.to_str().ok_or_else(|| format!("Invalid path: {:?}", self.path))?;
+            let binary = self.info.binary.to_str().ok_or_else(|| format!("Invalid path: {:?}", self.info.binary))?;
+
+            timings.measure(&self.name, Phase::Hooks, || run_script("post-install script", &hook.command, &[
+                ("BINUP_TOOL", &self.name),
+                ("BINUP_CHANGE", &self.change.to_string()),
+                ("BINUP_VERSION", &self.info.version),
+                ("BINUP_ASSET", &self.info.asset),
+                ("BINUP_ASSET_URL", self.url.as_str()),
+                ("BINUP_BINARY", binary),
+                ("BINUP_PATH", path),
+            ])).map_err(|e| self.context.wrap(e))?;
+        }
+
+        Ok(self.info)
+    }
+}
+
+// Identifies the release asset which is being processed. It's attached to the errors, so they can be told apart when
+// dozens of tools are installed at once.
+#[derive(Clone)]
+struct ErrorContext {
+    project: String,
+    version: String,
+    asset: Option<String>,
+}
+
+impl ErrorContext {
+    fn new(release: &Release, asset: Option<&Asset>) -> ErrorContext {
+        ErrorContext {
+            project: release.project.full_name(),
+            version: release.version.to_string(),
+            asset: asset.map(|asset| asset.name.clone()),
+        }
     }
 
-    Ok(())
+    fn wrap(&self, err: GenericError) -> GenericError {
+        format!("{err} ({self})").into()
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{} {}", self.project, self.version)?;
+        if let Some(asset) = self.asset.as_ref() {
+            write!(formatter, ", {asset}")?;
+        }
+        Ok(())
+    }
+}
+
+struct Backup {
+    path: PathBuf,
+    backup_path: Option<PathBuf>,
+    tree: Option<TreeBackup>,
+}
+
+// The old tree is moved aside by Tree::install, so the backup only remembers where to find it
+struct TreeBackup {
+    path: PathBuf,
+    old_path: PathBuf,
+    existed: bool,
+}
+
+impl Backup {
+    fn new(path: &Path, tree_path: Option<&Path>) -> GenericResult<Backup> {
+        let tree = tree_path.map(|tree_path| -> GenericResult<TreeBackup> {
+            let existed = match fs::symlink_metadata(tree_path) {
+                Ok(_) => true,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+                Err(err) => return Err!("Unable to stat {tree_path:?}: {err}"),
+            };
+
+            Ok(TreeBackup {
+                path: tree_path.to_owned(),
+                old_path: get_temp_path(tree_path, "old")?,
+                existed,
+            })
+        }).transpose()?;
+
+        let mut backup = Backup {
+            path: path.to_owned(),
+            backup_path: None,
+            tree,
+        };
+
+        if crate::tool::check(path)?.is_none() {
+            return Ok(backup);
+        }
+
+        let backup_path = get_temp_path(path, "backup")?;
+        if let Err(err) = fs::remove_file(&backup_path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                return Err!("Unable to delete {backup_path:?}: {err}");
+            }
+        }
+
+        debug!("Backing up {path:?} to {backup_path:?}...");
+        fs::hard_link(path, &backup_path).or_else(|_| reflink::copy(path, &backup_path)).map_err(|e| format!(
+            "Unable to backup {path:?} to {backup_path:?}: {e}"))?;
+
+        backup.backup_path.replace(backup_path);
+        Ok(backup)
+    }
+
+    fn restore(self) -> EmptyResult {
+        if let Some(ref tree) = self.tree {
+            remove_tree(&tree.path)?;
+
+            if tree.existed {
+                fs::rename(&tree.old_path, &tree.path).map_err(|e| format!(
+                    "Unable to restore {:?} from {:?}: {e}", tree.path, tree.old_path))?;
+            }
+        }
+
+        match self.backup_path {
+            Some(ref backup_path) => {
+                fs::rename(backup_path, &self.path).map_err(|e| format!(
+                    "Unable to restore {:?} from {backup_path:?}: {e}", self.path))?;
+                info!("{} is restored.", self.path.display());
+            },
+            None => {
+                if let Err(err) = fs::remove_file(&self.path) {
+                    if err.kind() != io::ErrorKind::NotFound {
+                        return Err!("Unable to delete {:?}: {err}", self.path);
+                    }
+                }
+                info!("{} is removed.", self.path.display());
+            },
+        }
+        Ok(())
+    }
+
+    fn remove(self) {
+        if let Some(backup_path) = self.backup_path {
+            if let Err(err) = fs::remove_file(&backup_path) {
+                error!("Unable to delete {backup_path:?}: {err}.");
+            }
+        }
+
+        if let Some(tree) = self.tree {
+            if let Err(err) = remove_tree(&tree.old_path) {
+                error!("{err}.");
+            }
+        }
+    }
 }
 
 struct Installer {
+    name: String,
     matcher: Matcher,
     automatic_matcher: bool,
+    excludes: Vec<Matcher>,
+    // Archive directory to consider as archive root
+    subdir: Option<Matcher>,
 
     binaries: Vec<PathBuf>,
     matches: Vec<PathBuf>,
-    temp_path: Option<PathBuf>,
+
+    outputs: Outputs,
+    tree: Option<Tree>,
+    // The local file which contains the data of the currently processed raw binary
+    local_source: Option<PathBuf>,
 
     path: PathBuf,
     time: SystemTime,
 }
 
 impl Installer {
-    fn new(name: &str, release: &Release, matcher: Option<Matcher>, path: &Path, time: SystemTime) -> Installer {
+    fn new(
+        name: &str, release: &Release, spec: &ToolSpec, path: &Path, tree_path: Option<&Path>, access: FileAccess,
+        time: SystemTime,
+    ) -> GenericResult<Installer> {
         let mut automatic_matcher = false;
 
-        let matcher = matcher.unwrap_or_else(|| {
+        let matcher = spec.binary_matcher.clone().unwrap_or_else(|| {
             automatic_matcher = true;
             release::generate_binary_matcher(name, release)
         });
 
-        Installer {
+        Ok(Installer {
+            name: name.to_owned(),
             matcher,
             automatic_matcher,
+            excludes: spec.binary_excludes.clone().unwrap_or_else(release::default_binary_excludes),
+            subdir: spec.subdir.clone(),
 
             binaries: Vec::new(),
             matches: Vec::new(),
 
-            temp_path: None,
+            outputs: Outputs::new(access),
+            tree: tree_path.map(|path| Tree::new(path, access)).transpose()?,
+            local_source: None,
+
             path: path.to_owned(),
             time,
-        }
+        })
     }
 
-    fn finish(mut self, url: &Url) -> EmptyResult {
+    fn verify(&self, url: &Url) -> EmptyResult {
         if self.automatic_matcher && self.matches.is_empty() && self.binaries.len() == 1 {
             debug!(concat!(
                 "Automatic binary matcher found zero binaries, ",
                 "but the release archive has only one executable, so using it."
             ));
+        } else if let Some(path) = self.select_match().filter(|_| self.matches.len() > 1) {
+            debug!("Automatic binary matcher found multiple binaries. Preferring {path:?} as the most relevant.");
         } else if self.matches.len() != 1 {
             if self.automatic_matcher {
                 let message = format!("Unable to automatically choose the proper executable from release ({url}) binaries");
@@ -248,48 +1031,174 @@ impl Installer {
             }
         }
 
-        let temp_path = self.temp_path.take().expect(
-            "An attempt to finish non-successful installation");
+        Ok(())
+    }
+
+    // Validates the extracted binary before it replaces the installed one, so a broken download never leaves the tool
+    // missing or half-written
+    fn validate(&self, smoke_test: Option<&str>) -> EmptyResult {
+        let path = self.get_staged_binary().expect("An attempt to validate non-successful installation");
+
+        let mut header = Vec::with_capacity(file_types::HEADER_SIZE);
+        File::open(&path)
+            .and_then(|file| file.take(file_types::HEADER_SIZE as u64).read_to_end(&mut header))
+            .map_err(|e| format!("Unable to read {path:?}: {e}"))?;
+
+        check_executable("The extracted binary", &header)?;
+
+        if let Some(command) = smoke_test {
+            let path = path.to_str().ok_or_else(|| format!("Invalid path: {path:?}"))?;
+            run_script("smoke test", command, &[
+                ("BINUP_TOOL", &self.name),
+                ("BINUP_PATH", path),
+            ])?;
+        }
+
+        Ok(())
+    }
 
-        fs::rename(&temp_path, &self.path).map_err(|e| format!(
-            "Unable to rename {temp_path:?} to {:?}: {e}", self.path))?;
+    // Returns the path the binary has been extracted to
+    fn get_staged_binary(&self) -> Option<PathBuf> {
+        let binary = self.get_binary()?;
 
+        match self.tree.as_ref() {
+            Some(tree) => self.get_relative_path(binary).map(|path| tree.temp_path.join(path)),
+            None => self.outputs.get(&self.path).map(|output| output.temp_path.clone()),
+        }
+    }
+
+    fn finish(mut self, keep_old_tree: bool) -> EmptyResult {
+        if self.tree.is_some() {
+            let binary = self.get_binary().expect("An attempt to finish non-successful installation");
+            let binary = self.get_relative_path(binary).expect("Got a binary outside of the subdirectory");
+            return self.tree.take().unwrap().install(&binary, &self.path, keep_old_tree);
+        }
+
+        if self.outputs.get(&self.path).is_none() {
+            panic!("An attempt to finish non-successful installation");
+        }
+
+        self.outputs.commit()?;
         debug!("The tool is installed as {:?}.", self.path);
 
         Ok(())
     }
-}
 
-impl Drop for Installer {
-    fn drop(&mut self) {
-        if let Some(temp_path) = self.temp_path.take() {
-            if let Err(err) = fs::remove_file(&temp_path) {
-                error!("Unable to delete {temp_path:?}: {err}.");
-            }
+    fn get_tree_path(&self) -> Option<&Path> {
+        self.tree.as_ref().map(|tree| tree.path.as_path())
+    }
+
+    fn get_binary(&self) -> Option<&PathBuf> {
+        if self.tree.is_some() {
+            self.select_match().or(self.binaries.first())
+        } else {
+            self.outputs.get(&self.path).map(|output| &output.source)
+        }
+    }
+
+    fn select_match(&self) -> Option<&PathBuf> {
+        if self.matches.len() == 1 {
+            return self.matches.first();
+        } else if !self.automatic_matcher {
+            return None;
+        }
+
+        let mut candidates: Vec<_> = self.matches.iter()
+            .map(|path| (self.get_priority(path), path))
+            .collect();
+        candidates.sort_by_key(|(priority, _)| *priority);
+
+        match candidates.as_slice() {
+            [(best, path), (next, _), ..] if best != next => Some(path),
+            _ => None,
+        }
+    }
+
+    // Prefer binaries whose name exactly equals the tool name, then the ones located in bin directory, then the ones
+    // with the shortest path
+    fn get_priority(&self, path: &Path) -> (bool, bool, usize) {
+        let exact_name = path.file_name() == Some(self.name.as_ref());
+        let in_bin = path.parent().and_then(Path::file_name) == Some("bin".as_ref());
+        (!exact_name, !in_bin, path.as_os_str().len())
+    }
+
+    // Returns the path relative to the subdirectory or None if the file is located outside of it
+    fn get_relative_path(&self, path: &Path) -> Option<PathBuf> {
+        match self.subdir.as_ref() {
+            Some(subdir) => strip_subdir(subdir, path),
+            None => Some(path.to_owned()),
         }
     }
 }
 
+// Archive entries and the staged binary are checked by the same rule, so the extracted binary is never rejected after
+// it has been accepted from the archive
+fn check_executable(name: &str, header: &[u8]) -> EmptyResult {
+    if file_types::is_executable(header) {
+        Ok(())
+    } else if FileType::detect(header).is_some() {
+        Err!("{name} is built for another platform")
+    } else {
+        Err!("{name} is not executable")
+    }
+}
+
 impl download::Installer for Installer {
     fn on_file(&mut self, path: &Path, mode: u32, data: &mut dyn Read) -> EmptyResult {
-        let is_executable = mode & 0o100 != 0;
+        let mut header = Vec::with_capacity(file_types::HEADER_SIZE);
+        data.take(file_types::HEADER_SIZE as u64).read_to_end(&mut header)?;
+
+        // Release archives not always have proper file modes, so rely on file contents instead
+        let file_type = FileType::detect(&header);
+        let is_executable = file_types::is_executable(&header);
+
+        if let Some(file_type) = file_type.as_ref() {
+            debug!("{path:?} is {file_type:?}.");
+        }
+        if is_executable != (mode & 0o100 != 0) {
+            debug!("{path:?} mode ({mode:o}) doesn't match its contents. Considering it as {}.",
+                if is_executable { "executable" } else { "non-executable" });
+        }
+
+        let Some(relative_path) = self.get_relative_path(path) else {
+            debug!("{path:?} is outside of the subdirectory. Skipping it.");
+            return Ok(());
+        };
+
+        let matches = self.matcher.matches(&relative_path);
+        let is_excluded = !matches && self.excludes.iter().any(|exclude| exclude.matches(&relative_path));
 
         if is_executable {
-            self.binaries.push(path.to_owned());
+            if is_excluded {
+                debug!("{path:?} is excluded from binary candidates.");
+            } else {
+                self.binaries.push(path.to_owned());
+            }
         }
 
-        if self.matcher.matches(path) {
+        if matches {
             debug!("{path:?} matches binary matcher.");
 
             self.matches.push(path.to_owned());
-            if self.matches.len() > 1 {
-                return Ok(()); // We'll return error later when collect all matches
+
+            if self.matches.len() > 1 && self.tree.is_none() {
+                let downloaded = self.get_binary().filter(|downloaded| self.matches.contains(downloaded));
+
+                match downloaded {
+                    Some(downloaded) if self.automatic_matcher && self.get_priority(path) < self.get_priority(downloaded) => {
+                        debug!("{path:?} is more relevant than {downloaded:?}.");
+                    },
+                    // We'll choose the most relevant one or return error later when collect all matches
+                    _ => return Ok(()),
+                }
             }
 
             if !is_executable {
-                return Err!("{path:?} in the archive is not executable");
+                check_executable(&format!("{path:?} in the archive"), &header)?;
             }
-        } else if self.automatic_matcher && is_executable && self.temp_path.is_none() {
+        } else if self.tree.is_some() {
+            // All files are extracted in tree layout
+        } else if self.automatic_matcher && is_executable && !is_excluded && self.get_binary().is_none() {
             debug!(concat!(
                 "Got first executable in archive: {:?}. ",
                 "Download it for the case if it's the only one executable in archive.",
@@ -298,54 +1207,385 @@ impl download::Installer for Installer {
             return Ok(());
         }
 
-        let temp_path = match self.temp_path.as_ref() {
-            Some(path) => path.to_owned(),
-            None => {
-                let file_name = self.path.file_name()
-                    .and_then(|name| name.to_str())
-                    .ok_or_else(|| format!("Got an unexpected install path: {:?}", self.path))?;
+        let mut data = io::Cursor::new(header).chain(data);
 
-                self.path.with_file_name(format!(".{file_name}.{ext}", ext=env!("CARGO_PKG_NAME")))
-            },
+        if let Some(tree) = self.tree.as_mut() {
+            return tree.extract(&relative_path, mode, &mut data, self.time);
+        }
+
+        self.outputs.stage(path, &self.path, 0o755, &mut data, self.local_source.as_deref(), self.time)
+    }
+
+    fn on_local_file(&mut self, path: &Path, mode: u32, data: &mut dyn Read, source: &Path) -> EmptyResult {
+        self.local_source = Some(source.to_owned());
+        let result = self.on_file(path, mode, data);
+        self.local_source = None;
+        result
+    }
+}
+
+// Strips the shortest path prefix which matches the subdirectory pattern
+fn strip_subdir(subdir: &Matcher, path: &Path) -> Option<PathBuf> {
+    let mut prefixes: Vec<&Path> = path.ancestors().skip(1)
+        .filter(|prefix| !prefix.as_os_str().is_empty())
+        .collect();
+    prefixes.reverse();
+
+    prefixes.into_iter()
+        .find(|prefix| subdir.matches(prefix))
+        .and_then(|prefix| path.strip_prefix(prefix).ok())
+        .map(ToOwned::to_owned)
+}
+
+// Release archive files which are extracted to temporary files next to their destinations and moved to the
+// destinations only when the whole installation is prepared. Each output has its own destination and mode, so an
+// installation may consist of several files.
+struct Outputs {
+    files: Vec<Output>,
+    access: FileAccess,
+}
+
+struct Output {
+    // Path inside the release archive
+    source: PathBuf,
+    destination: PathBuf,
+    temp_path: PathBuf,
+}
+
+impl Outputs {
+    fn new(access: FileAccess) -> Outputs {
+        Outputs {files: Vec::new(), access}
+    }
+
+    fn get(&self, destination: &Path) -> Option<&Output> {
+        self.files.iter().find(|output| output.destination == destination)
+    }
+
+    // Extracts the file to a temporary file for the destination. The file which has been previously staged for the
+    // same destination is replaced.
+    //
+    // If the file data is available locally, it's cloned on the filesystems which support copy-on-write (Btrfs, XFS,
+    // APFS), so repeated installations from the cache don't write the data again and share the disk space.
+    fn stage(
+        &mut self, source: &Path, destination: &Path, mode: u32, data: &mut dyn Read, local_source: Option<&Path>,
+        time: SystemTime,
+    ) -> EmptyResult {
+        let index = self.files.iter().position(|output| output.destination == destination);
+        let temp_path = match index {
+            Some(index) => self.files[index].temp_path.clone(),
+            None => get_temp_path(destination, "")?,
         };
 
-        debug!("Downloading {path:?} to {temp_path:?}...");
+        let cloned = local_source.is_some_and(|local_source| {
+            if let Err(err) = fs::remove_file(&temp_path) {
+                if err.kind() != io::ErrorKind::NotFound {
+                    return false;
+                }
+            }
+
+            match reflink::clone(local_source, &temp_path, mode) {
+                Ok(()) => {
+                    debug!("Cloned {local_source:?} to {temp_path:?}.");
+                    true
+                },
+                Err(err) => {
+                    debug!("Unable to clone {local_source:?} to {temp_path:?}: {err}.");
+                    false
+                },
+            }
+        });
+
+        if !cloned {
+            debug!("Extracting {source:?} to {temp_path:?}...");
+        }
 
         let mut file = OpenOptions::new()
             .create(true)
-            .mode(0o755)
+            .mode(mode)
             .write(true)
-            .truncate(true)
+            .truncate(!cloned)
             .custom_flags(libc::O_NOFOLLOW)
             .open(&temp_path)
             .map_err(|e| format!("Unable to create {temp_path:?}: {e}"))?;
-        self.temp_path.replace(temp_path);
 
-        io::copy(data, &mut file)?;
-        file.set_modified(self.time)?;
+        match index {
+            Some(index) => self.files[index].source = source.to_owned(),
+            None => self.files.push(Output {
+                source: source.to_owned(),
+                destination: destination.to_owned(),
+                temp_path: temp_path.clone(),
+            }),
+        }
+
+        if !cloned {
+            io::copy(data, &mut file)?;
+        }
+        self.access.apply_to_file(&file, &temp_path, mode)?;
+        file.set_modified(time)?;
         file.sync_all()?;
 
         Ok(())
     }
+
+    fn commit(&mut self) -> EmptyResult {
+        while let Some(output) = self.files.first() {
+            fs::rename(&output.temp_path, &output.destination).map_err(|e| format!(
+                "Unable to rename {:?} to {:?}: {e}", output.temp_path, output.destination))?;
+            self.files.remove(0);
+        }
+        Ok(())
+    }
 }
 
-fn run_post_script(script: &str) -> EmptyResult {
-    debug!("Executing post-install script:{}", util::format_multiline(script));
+impl Drop for Outputs {
+    fn drop(&mut self) {
+        for output in self.files.drain(..) {
+            if let Err(err) = fs::remove_file(&output.temp_path) {
+                error!("Unable to delete {:?}: {err}.", output.temp_path);
+            }
+        }
+    }
+}
 
-    let result = Command::new("bash").args(["-c", script]).output().map_err(|e| format!(
-        "Failed to execute post-install script: unable to spawn bash process: {e}"))?;
+struct Tree {
+    path: PathBuf,
+    temp_path: PathBuf,
+    temp_created: bool,
+    access: FileAccess,
+}
+
+impl Tree {
+    fn new(path: &Path, access: FileAccess) -> GenericResult<Tree> {
+        Ok(Tree {
+            path: path.to_owned(),
+            temp_path: get_temp_path(path, "")?,
+            temp_created: false,
+            access,
+        })
+    }
+
+    fn extract(&mut self, path: &Path, mode: u32, data: &mut dyn Read, time: SystemTime) -> EmptyResult {
+        if !path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+            return Err!("Got an invalid path in the archive: {path:?}");
+        }
+
+        if !self.temp_created {
+            remove_tree(&self.temp_path)?;
+            self.temp_created = true;
+        }
+
+        let file_path = self.temp_path.join(path);
+        if let Some(parent) = file_path.parent() {
+            self.access.create_dir_all(parent)?;
+        }
+
+        debug!("Extracting {path:?} to {file_path:?}...");
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .mode(mode & 0o777)
+            .write(true)
+            .truncate(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(&file_path)
+            .map_err(|e| format!("Unable to create {file_path:?}: {e}"))?;
+
+        io::copy(data, &mut file)?;
+        self.access.apply_to_file(&file, &file_path, mode & 0o777)?;
+        file.set_modified(time)?;
+
+        Ok(())
+    }
+
+    fn install(mut self, binary: &Path, link_path: &Path, keep_old: bool) -> EmptyResult {
+        if !self.temp_created {
+            return Err!("The release archive is empty");
+        }
+
+        let old_path = get_temp_path(&self.path, "old")?;
+        remove_tree(&old_path)?;
+
+        let exists = match fs::symlink_metadata(&self.path) {
+            Ok(_) => true,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+            Err(err) => return Err!("Unable to stat {:?}: {err}", self.path),
+        };
+
+        if exists {
+            fs::rename(&self.path, &old_path).map_err(|e| format!(
+                "Unable to rename {:?} to {old_path:?}: {e}", self.path))?;
+        }
+
+        fs::rename(&self.temp_path, &self.path).map_err(|e| format!(
+            "Unable to rename {:?} to {:?}: {e}", self.temp_path, self.path))?;
+        self.temp_created = false;
+
+        debug!("The tool tree is installed to {:?}.", self.path);
+
+        if exists && !keep_old {
+            if let Err(err) = remove_tree(&old_path) {
+                error!("{err}.");
+            }
+        }
+
+        let target = self.path.join(binary);
+
+        let permissions = fs::metadata(&target).map_err(|e| format!(
+            "Unable to stat {target:?}: {e}"))?.permissions();
+
+        if permissions.mode() & 0o111 == 0 {
+            fs::set_permissions(&target, Permissions::from_mode(permissions.mode() | 0o111)).map_err(|e| format!(
+                "Unable to change {target:?} permissions: {e}"))?;
+        }
+
+        let temp_link_path = get_temp_path(link_path, "")?;
+
+        if let Err(err) = fs::remove_file(&temp_link_path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                return Err!("Unable to delete {temp_link_path:?}: {err}");
+            }
+        }
+
+        symlink(&target, &temp_link_path).map_err(|e| format!(
+            "Unable to create {temp_link_path:?} symlink: {e}"))?;
+
+        fs::rename(&temp_link_path, link_path).map_err(|e| format!(
+            "Unable to rename {temp_link_path:?} to {link_path:?}: {e}"))?;
+
+        debug!("The tool is installed as {link_path:?} -> {target:?}.");
+
+        Ok(())
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        if self.temp_created {
+            if let Err(err) = remove_tree(&self.temp_path) {
+                error!("{err}.");
+            }
+        }
+    }
+}
+
+pub fn remove_tree(path: &Path) -> EmptyResult {
+    match fs::remove_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err!("Unable to delete {path:?}: {err}"),
+    }
+}
+
+fn get_temp_path(path: &Path, suffix: &str) -> GenericResult<PathBuf> {
+    let file_name = path.file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Got an unexpected install path: {path:?}"))?;
+
+    let suffix = if suffix.is_empty() {
+        String::new()
+    } else {
+        format!("-{suffix}")
+    };
+
+    Ok(path.with_file_name(format!(".{file_name}.{ext}{suffix}", ext=env!("CARGO_PKG_NAME"))))
+}
+
+fn set_capabilities(path: &Path, capabilities: &str) -> EmptyResult {
+    let mut command = Command::new("setcap");
+    command.arg(capabilities).arg(path);
+
+    debug!("Setting {path:?} capabilities: `{command:?}`...");
+
+    let output = command.output().map_err(|e| format!("Failed to run `{command:?}`: {e}"))?;
+    if !output.status.success() {
+        return Err!(
+            "Failed to set {path:?} capabilities: `{command:?}` returned an error ({}):{}", output.status,
+            util::format_multiline(&String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+// Passes the planned changes to the pre-run script which may veto the run by exiting with non-zero code
+fn run_pre_run_script(script: &str, mode: Mode, installations: &[Installation]) -> EmptyResult {
+    #[derive(Serialize)]
+    struct Plan<'a> {
+        mode: &'static str,
+        tools: Vec<PlannedChange<'a>>,
+    }
+
+    #[derive(Serialize)]
+    struct PlannedChange<'a> {
+        name: &'a str,
+        change: String,
+        current_version: Option<String>,
+        version: &'a str,
+        asset: &'a str,
+        path: &'a Path,
+    }
+
+    let plan = Plan {
+        mode: match mode {
+            Mode::Install {..} => "install",
+            Mode::Upgrade {..} => "upgrade",
+        },
+        tools: installations.iter().map(|installation| PlannedChange {
+            name: &installation.name,
+            change: installation.change.to_string(),
+            current_version: installation.current_version.as_ref().map(ToString::to_string),
+            version: &installation.info.version,
+            asset: &installation.info.asset,
+            path: &installation.path,
+        }).collect(),
+    };
+
+    let plan = serde_json::to_vec_pretty(&plan)?;
+    run_script_with_input("pre-run script", script, &[], Some(&plan)).map_err(|e| format!(
+        "The run has been vetoed: {e}").into())
+}
+
+fn run_script(description: &str, script: &str, envs: &[(&str, &str)]) -> EmptyResult {
+    run_script_with_input(description, script, envs, None)
+}
+
+fn run_script_with_input(description: &str, script: &str, envs: &[(&str, &str)], input: Option<&[u8]>) -> EmptyResult {
+    let title = description[..1].to_uppercase() + &description[1..];
+    debug!("Executing {description}:{}", util::format_multiline(script));
+
+    let mut process = Command::new("bash").args(["-c", script]).envs(envs.iter().copied())
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().map_err(|e| format!("Failed to execute {description}: unable to spawn bash process: {e}"))?;
+
+    // The script may not read its input, so write it concurrently to not block on a full pipe
+    let writer = input.map(|input| {
+        let input = input.to_vec();
+        let mut stdin = process.stdin.take().unwrap();
+        thread::spawn(move || stdin.write_all(&input))
+    });
+
+    let result = process.wait_with_output().map_err(|e| format!(
+        "Failed to execute {description}: {e}"))?;
+
+    if let Some(writer) = writer {
+        match writer.join().expect("Script input writer has panicked") {
+            Err(err) if err.kind() != ErrorKind::BrokenPipe && result.status.success() => return Err!(
+                "Failed to pass the input to {description}: {err}"),
+            _ => {},
+        }
+    }
 
     let stderr = String::from_utf8_lossy(&result.stderr);
     if !result.status.success() {
         return Err!(
-            "Post-install script returned an error ({}):{}",
+            "{title} returned an error ({}):{}",
             result.status, util::format_multiline(&stderr));
     }
 
     if stderr.trim().is_empty() {
-        debug!("Post-install script has finished.");
+        debug!("{title} has finished.");
     } else {
-        warn!("Post-install script:{}", util::format_multiline(&stderr));
+        warn!("{title}:{}", util::format_multiline(&stderr));
     }
 
     Ok(())
@@ -355,6 +1595,132 @@ fn format_changelog(changelog: &Url, from: Option<&Version>, to: &ReleaseVersion
     match (from, to) {
         // We don't place ellipsis after changelog, because at least iTerm2 parses URL improperly in this case
         (Some(from), ReleaseVersion::Version(to)) if from == to => "...".to_owned(),
-        _ => format!(" (see {changelog})")
+        _ => format!(" (see {})", expand_changelog(changelog, to)),
+    }
+}
+
+// Changelog URL may contain {version} placeholder which is percent-encoded by URL parser when it's in the path
+pub fn expand_changelog(changelog: &Url, version: &ReleaseVersion) -> String {
+    let version = version.to_string();
+    changelog.as_str().replace("{version}", &version).replace("%7Bversion%7D", &version)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(changelog, version, expected,
+        case("https://github.com/owner/name/releases", "v1.2.3", "https://github.com/owner/name/releases"),
+        case("https://example.com/CHANGELOG.md#v{version}", "v1.2.3", "https://example.com/CHANGELOG.md#v1.2.3"),
+        case("https://example.com/releases/{version}/notes", "v1.2.3", "https://example.com/releases/1.2.3/notes"),
+        case("https://example.com/CHANGELOG.md#{version}", "nightly", "https://example.com/CHANGELOG.md#nightly"),
+    )]
+    fn changelog_expansion(changelog: &str, version: &str, expected: &str) {
+        let changelog = Url::parse(changelog).unwrap();
+        assert_eq!(expand_changelog(&changelog, &ReleaseVersion::new(version)), expected);
+    }
+
+    #[test]
+    fn recently_checked() {
+        let binary_path = env::current_exe().unwrap();
+        let interval = Duration::from_secs(60 * 60);
+
+        let mut state = State::default();
+        state.record_run("tool", None);
+        assert!(!is_recently_checked(&state, "tool", "hash", &binary_path, interval));
+
+        state.tools.insert("tool".to_owned(), InstallInfo {
+            version: "1.0.0".to_owned(),
+            asset: "tool-linux-amd64".to_owned(),
+            binary: PathBuf::from("tool-linux-amd64"),
+            time: None,
+            sha256: None,
+            url: None,
+            spec_hash: Some("hash".to_owned()),
+        });
+        assert!(is_recently_checked(&state, "tool", "hash", &binary_path, interval));
+        assert!(!is_recently_checked(&state, "tool", "hash", &binary_path, Duration::ZERO));
+        assert!(!is_recently_checked(&state, "tool", "other", &binary_path, interval));
+        assert!(!is_recently_checked(&state, "tool", "hash", &binary_path.with_extension("missing"), interval));
+
+        state.record_run("tool", Some("Network error"));
+        assert!(!is_recently_checked(&state, "tool", "hash", &binary_path, interval));
+
+        state.runs.get_mut("tool").unwrap().error = None;
+        state.runs.get_mut("tool").unwrap().time -= chrono::Duration::hours(2);
+        assert!(!is_recently_checked(&state, "tool", "hash", &binary_path, interval));
+    }
+
+    #[test]
+    fn error_context() {
+        let project = github::parse_project_name("owner/tool").unwrap();
+        let asset = Asset {
+            name: "tool-linux-amd64.tar.gz".to_owned(),
+            time: Utc::now(),
+            url: Url::parse("https://example.com/tool-linux-amd64.tar.gz").unwrap(),
+        };
+        let release = Release::new(project, "v1.2.3", vec![asset.clone()]);
+
+        let err = ErrorContext::new(&release, None).wrap("The release has no assets".into());
+        assert_eq!(err.to_string(), "The release has no assets (owner/tool 1.2.3)");
+
+        let err = ErrorContext::new(&release, Some(&asset)).wrap("Permission denied".into());
+        assert_eq!(err.to_string(), "Permission denied (owner/tool 1.2.3, tool-linux-amd64.tar.gz)");
     }
-}
\ No newline at end of file
+
+    #[rstest(subdir, path, expected,
+        case("tool-*", "tool-1.2.3-linux-amd64/bin/tool", Some("bin/tool")),
+        case("tool-*", "tool-1.2.3-linux-amd64/README.md", Some("README.md")),
+        case("tool-*", "tool-1.2.3-linux-amd64", None),
+        case("tool-*", "other/tool", None),
+        case("*/bin", "tool-1.2.3/bin/tool", Some("tool")),
+        case("*/bin", "tool-1.2.3/lib/tool.so", None),
+    )]
+    fn subdir_stripping(subdir: &str, path: &str, expected: Option<&str>) {
+        let subdir = Matcher::new(subdir).unwrap();
+        assert_eq!(strip_subdir(&subdir, Path::new(path)), expected.map(PathBuf::from));
+    }
+
+    #[rstest(script, input, ok,
+        case("grep -q '\"mode\": \"upgrade\"'", "{\"mode\": \"upgrade\"}", true),
+        case("grep -q '\"mode\": \"upgrade\"'", "{\"mode\": \"install\"}", false),
+        case("exit 0", &"x".repeat(1024 * 1024), true),
+        case("exit 1", "", false),
+    )]
+    fn script_input(script: &str, input: &str, ok: bool) {
+        assert_eq!(run_script_with_input("test script", script, &[], Some(input.as_bytes())).is_ok(), ok);
+    }
+
+    #[test]
+    fn tree_rollback() {
+        let root = std::env::temp_dir().join(format!("binup-tree-rollback-{}", std::process::id()));
+        let tree_path = root.join("opt/tool");
+        let link_path = root.join("bin/tool");
+
+        let install = |contents: &str, keep_old: bool| -> EmptyResult {
+            let mut tree = Tree::new(&tree_path, FileAccess::default())?;
+            tree.extract(Path::new("bin/tool"), 0o755, &mut contents.as_bytes(), SystemTime::now())?;
+            tree.install(Path::new("bin/tool"), &link_path, keep_old)
+        };
+
+        remove_tree(&root).unwrap();
+        fs::create_dir_all(link_path.parent().unwrap()).unwrap();
+        fs::create_dir_all(tree_path.parent().unwrap()).unwrap();
+
+        install("old", false).unwrap();
+        assert_eq!(fs::read_to_string(&link_path).unwrap(), "old");
+
+        let backup = Backup::new(&link_path, Some(&tree_path)).unwrap();
+        install("new", true).unwrap();
+        assert_eq!(fs::read_to_string(&link_path).unwrap(), "new");
+
+        backup.restore().unwrap();
+        assert_eq!(fs::read_to_string(&link_path).unwrap(), "old");
+        assert!(!get_temp_path(&tree_path, "old").unwrap().exists());
+
+        remove_tree(&root).unwrap();
+    }
+}
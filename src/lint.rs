@@ -0,0 +1,411 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use itertools::Itertools;
+use log::{info, warn};
+use nondestructive::yaml::{Document, MappingMut};
+use serde_yaml::{Mapping, Value};
+
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::github;
+
+const PATH_OPTIONS: [&[&str]; 10] = [
+    &["path"], &["tree_path"], &["cache_path"], &["versions_path"], &["state_path"], &["state_key"],
+    &["tls", "ca_file"], &["tls", "client_cert"], &["tls", "client_key"],
+    &["github", "app", "private_key"],
+];
+
+const MATCHER_OPTIONS: [&str; 4] = ["release_matcher", "binary_matcher", "binary_excludes", "subdir"];
+
+struct Issue {
+    location: String,
+    message: String,
+    fix: Option<Fix>,
+}
+
+// Safe rewrites which don't change the effective configuration
+enum Fix {
+    RemoveOption(Vec<String>),
+    RemoveTemplate(String),
+    SetString(Vec<String>, String),
+}
+
+// Detects the common configuration mistakes which don't make the configuration invalid, but most likely aren't
+// intended, and optionally fixes the ones which can be fixed without changing the effective configuration.
+pub fn lint(config: &mut Config, fix: bool) -> GenericResult<ExitCode> {
+    let Some(data) = config.get_source_data() else {
+        info!("The configuration file doesn't exist.");
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let issues = check(data)?;
+    if issues.is_empty() {
+        info!("No issues found.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for issue in &issues {
+        warn!("{}: {}", issue.location, issue.message);
+    }
+
+    let (fixable, unfixable): (Vec<_>, Vec<_>) = issues.into_iter().partition(|issue| issue.fix.is_some());
+
+    if !fixable.is_empty() {
+        if fix {
+            config.edit(|config, raw| {
+                for issue in &fixable {
+                    apply_fix(config, raw, issue.fix.as_ref().unwrap()).map_err(|e| format!(
+                        "Unable to fix {}: {e}", issue.location))?;
+                }
+                Ok(())
+            }, |_| Ok(()))?;
+            info!("Fixed {} of the issues.", fixable.len());
+        } else {
+            info!("{} of the issues can be fixed automatically with --fix option.", fixable.len());
+        }
+    }
+
+    Ok(if unfixable.is_empty() && fix {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn check(data: &[u8]) -> GenericResult<Vec<Issue>> {
+    let config = Config::read(data)?;
+
+    let root = match serde_yaml::from_slice(data)? {
+        Value::Mapping(root) => root,
+        _ => Mapping::new(),
+    };
+
+    let mut issues = Vec::new();
+    check_redundant_options(&root, &config, &mut issues);
+    check_unused_templates(&root, &mut issues);
+    check_paths(&root, &mut issues);
+    check_matchers(&root, &mut issues);
+    check_duplicate_projects(&config, &mut issues);
+
+    Ok(issues)
+}
+
+// Finds the options which may be removed without changing the effective configuration: the ones which are set to their
+// default values or are overridden anyway
+fn check_redundant_options(root: &Mapping, config: &Config, issues: &mut Vec<Issue>) {
+    let is_redundant = |path: &[&str]| -> bool {
+        let mut root = root.clone();
+        if !remove_value(&mut root, path) {
+            return false;
+        }
+
+        serde_yaml::to_string(&root).ok()
+            .and_then(|data| Config::read(data.as_bytes()).ok())
+            .is_some_and(|modified| modified == *config)
+    };
+
+    for key in root.keys().filter_map(Value::as_str) {
+        if !matches!(key, "tools" | "templates") && !key.starts_with("x-") && is_redundant(&[key]) {
+            issues.push(Issue {
+                location: key.to_owned(),
+                message: "The option has no effect: it's set to its default value.".to_owned(),
+                fix: Some(Fix::RemoveOption(vec![key.to_owned()])),
+            });
+        }
+    }
+
+    for (name, spec) in get_entries(root, "tools") {
+        for key in spec.keys().filter_map(Value::as_str).filter(|&key| key != "<<") {
+            if is_redundant(&["tools", name, key]) {
+                issues.push(Issue {
+                    location: format!("tools.{name}.{key}"),
+                    message: "The option has no effect: it's set to its default or inherited value.".to_owned(),
+                    fix: Some(Fix::RemoveOption(vec!["tools".to_owned(), name.to_owned(), key.to_owned()])),
+                });
+            }
+        }
+    }
+}
+
+fn check_unused_templates(root: &Mapping, issues: &mut Vec<Issue>) {
+    let used: Vec<String> = get_entries(root, "tools")
+        .filter_map(|(_, spec)| spec.get("extends"))
+        .flat_map(|extends| match extends {
+            Value::String(template) => vec![template.clone()],
+            Value::Sequence(templates) => templates.iter().filter_map(Value::as_str).map(ToOwned::to_owned).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+
+    for (name, _) in get_entries(root, "templates") {
+        if !used.iter().any(|template| template == name) {
+            issues.push(Issue {
+                location: format!("templates.{name}"),
+                message: "The template isn't used by any tool.".to_owned(),
+                fix: Some(Fix::RemoveTemplate(name.to_owned())),
+            });
+        }
+    }
+}
+
+// Only leading tilde is expanded in paths, so absolute paths inside of home directory are suggested to be replaced with
+// tilde ones to make the configuration portable between machines and users.
+fn check_paths(root: &Mapping, issues: &mut Vec<Issue>) {
+    let home = shellexpand::tilde("~/").to_string();
+
+    let tool_paths = get_entries(root, "tools").map(|(name, _)| vec!["tools", name, "path"]);
+    let paths = PATH_OPTIONS.iter().map(|path| path.to_vec()).chain(tool_paths);
+
+    for path in paths {
+        let Some(value) = get_value(root, &path).and_then(Value::as_str) else {
+            continue;
+        };
+
+        let location = path.join(".");
+        let message = if value.contains('$') {
+            "Environment variables aren't expanded in paths: only leading tilde is supported."
+        } else if value.get(1..).is_some_and(|rest| rest.contains('~')) {
+            "Tilde is expanded only at the beginning of the path."
+        } else if let Some(relative) = value.strip_prefix(&home) {
+            let fixed = format!("~/{relative}");
+            issues.push(Issue {
+                location,
+                message: format!("The path is inside of the home directory. Consider to use {fixed:?} instead."),
+                fix: Some(Fix::SetString(path.iter().map(|&key| key.to_owned()).collect(), fixed)),
+            });
+            continue;
+        } else {
+            continue;
+        };
+
+        issues.push(Issue {location, message: message.to_owned(), fix: None});
+    }
+}
+
+fn check_matchers(root: &Mapping, issues: &mut Vec<Issue>) {
+    for section in ["tools", "templates"] {
+        for (name, spec) in get_entries(root, section) {
+            for key in MATCHER_OPTIONS {
+                let patterns: Vec<(String, &str)> = match spec.get(key) {
+                    Some(Value::String(pattern)) => vec![(format!("{section}.{name}.{key}"), pattern)],
+                    Some(Value::Sequence(patterns)) => patterns.iter().enumerate()
+                        .filter_map(|(index, pattern)| Some((format!("{section}.{name}.{key}[{index}]"), pattern.as_str()?)))
+                        .collect(),
+                    _ => continue,
+                };
+
+                for (location, pattern) in patterns {
+                    if let Some(message) = check_matcher(key, pattern) {
+                        issues.push(Issue {location, message: message.to_owned(), fix: None});
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Returns the reason why the matcher can never match
+fn check_matcher(option: &str, pattern: &str) -> Option<&'static str> {
+    let archive_path = option != "release_matcher";
+
+    if let Some(regex) = pattern.strip_prefix('~') {
+        if has_misplaced_anchors(regex) {
+            return Some("The regex can never match: `^` and `$` anchors must be at the beginning and at the end of it.");
+        } else if archive_path && regex.starts_with("^/") {
+            return Some("The regex can never match: archive paths are relative, so remove the leading slash.");
+        }
+    } else if archive_path && pattern.starts_with('/') {
+        return Some("The pattern can never match: archive paths are relative, so remove the leading slash.");
+    } else if !archive_path && pattern.contains('/') {
+        return Some("The pattern can never match: it's matched against asset name which can't contain slashes.");
+    }
+
+    None
+}
+
+// Detects `^` and `$` anchors in the middle of the regex, which make it never match single-line asset names and
+// archive paths. The check is heuristic: only anchors which are adjacent to literal characters are considered.
+fn has_misplaced_anchors(regex: &str) -> bool {
+    if regex.contains("(?m") {
+        return false;
+    }
+
+    let is_literal = |char: Option<&char>| char.is_some_and(|&char| char.is_alphanumeric() || "-_./".contains(char));
+    let chars: Vec<char> = regex.chars().collect();
+
+    let mut index = 0;
+    let mut in_class = false;
+
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '^' if !in_class && index > 0 && (is_literal(chars.get(index - 1)) || chars[index - 1] == ']') => return true,
+            '$' if !in_class && (is_literal(chars.get(index + 1)) || chars.get(index + 1) == Some(&'[')) => return true,
+            _ => {},
+        }
+        index += 1;
+    }
+
+    false
+}
+
+fn check_duplicate_projects(config: &Config, issues: &mut Vec<Issue>) {
+    let mut projects: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+
+    for (name, spec) in &config.tools {
+        for project in spec.project.iter().map(|project| normalize_project(project)).unique() {
+            projects.entry(project).or_default().push(name);
+        }
+    }
+
+    for (project, names) in projects {
+        for (first, second) in names.iter().tuple_combinations() {
+            let (first_spec, second_spec) = (&config.tools[*first], &config.tools[*second]);
+
+            // Installing several binaries from the same project or installing the project on different machines is fine
+            if first_spec.binary_matcher != second_spec.binary_matcher || first_spec.only_on != second_spec.only_on {
+                continue;
+            }
+
+            issues.push(Issue {
+                location: format!("tools.{second}"),
+                message: format!(concat!(
+                    "{} project is also installed as {} tool. If it's not intended, remove one of the tools or specify ",
+                    "binary_matcher to install different binaries."), project, first),
+                fix: None,
+            });
+        }
+    }
+}
+
+// Projects may be specified in several forms (like owner/name or its URL), so reduce them to a comparable one
+fn normalize_project(project: &str) -> String {
+    let project = match github::parse_project_name(project) {
+        Ok(project) if project.host.as_deref().is_some_and(|host| host.eq_ignore_ascii_case(github::PUBLIC_HOST)) => {
+            format!("{}/{}", project.owner, project.name)
+        },
+        Ok(project) => project.full_name(),
+        Err(_) => project.to_owned(),
+    };
+    project.to_lowercase()
+}
+
+fn apply_fix(config: &mut Config, raw: &mut Document, fix: &Fix) -> EmptyResult {
+    match fix {
+        Fix::RemoveOption(path) => {
+            let (key, parent) = path.split_last().unwrap();
+            if !get_mapping_mut(raw, parent).is_some_and(|mut mapping| mapping.remove(key)) {
+                return Err!("the option is not found");
+            }
+        },
+        Fix::RemoveTemplate(name) => config.remove_template(raw, name)?,
+        Fix::SetString(path, value) => {
+            let (key, parent) = path.split_last().unwrap();
+            let Some(mut mapping) = get_mapping_mut(raw, parent) else {
+                return Err!("the option is not found");
+            };
+            mapping.insert_str(key, value);
+        },
+    }
+
+    Ok(())
+}
+
+fn get_entries<'a>(root: &'a Mapping, section: &str) -> impl Iterator<Item = (&'a str, &'a Mapping)> {
+    root.get(section).and_then(Value::as_mapping).into_iter().flatten().filter_map(|(name, value)| {
+        Some((name.as_str()?, value.as_mapping()?))
+    })
+}
+
+fn get_value<'a>(root: &'a Mapping, path: &[&str]) -> Option<&'a Value> {
+    let (key, parent) = path.split_last()?;
+    let mut mapping = root;
+    for key in parent {
+        mapping = mapping.get(key)?.as_mapping()?;
+    }
+    mapping.get(key)
+}
+
+fn remove_value(root: &mut Mapping, path: &[&str]) -> bool {
+    let Some((key, parent)) = path.split_last() else {
+        return false;
+    };
+
+    let mut mapping = root;
+    for key in parent {
+        let Some(child) = mapping.get_mut(key).and_then(Value::as_mapping_mut) else {
+            return false;
+        };
+        mapping = child;
+    }
+
+    mapping.remove(key).is_some()
+}
+
+fn get_mapping_mut<'a>(raw: &'a mut Document, path: &[String]) -> Option<MappingMut<'a>> {
+    let mut mapping = raw.as_mut().into_mapping_mut()?;
+    for key in path {
+        mapping = mapping.get_into_mut(key)?.into_mapping_mut()?;
+    }
+    Some(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(regex, misplaced,
+        case("^tool-.*-linux$", false),
+        case("(?:^|/)tool$", false),
+        case("tool-[^/]+$", false),
+        case(r"tool\$x", false),
+        case("tool$.tar.gz", true),
+        case("bin/^tool", true),
+        case("[a-z]^tool", true),
+    )]
+    fn anchors(regex: &str, misplaced: bool) {
+        assert_eq!(has_misplaced_anchors(regex), misplaced);
+    }
+
+    #[test]
+    fn issues() {
+        let data = indoc!(r#"
+            path: ~/bin
+            follow_symlinks: false
+
+            templates:
+              used:
+                layout: tree
+              unused:
+                layout: tree
+
+            tools:
+              rg:
+                project: BurntSushi/ripgrep
+                layout: binary
+                release_matcher: ~ripgrep-.*$.tar.gz
+              ripgrep:
+                project: https://github.com/burntsushi/ripgrep.git
+                extends: used
+                path: /opt/$USER/bin
+        "#);
+
+        let issues: Vec<_> = check(data.as_bytes()).unwrap().into_iter()
+            .map(|issue| (issue.location, issue.fix.is_some()))
+            .collect();
+
+        assert_eq!(issues, [
+            ("follow_symlinks".to_owned(), true),
+            ("tools.rg.layout".to_owned(), true),
+            ("templates.unused".to_owned(), true),
+            ("tools.ripgrep.path".to_owned(), false),
+            ("tools.rg.release_matcher".to_owned(), false),
+            ("tools.ripgrep".to_owned(), false),
+        ]);
+    }
+}
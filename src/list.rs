@@ -1,119 +1,525 @@
+use std::fmt::{self, Display, Formatter};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::time::SystemTime;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use ansi_term::Color;
+use chrono::{DateTime, Local, TimeDelta, Utc};
 use is_terminal::IsTerminal;
-use log::{debug, error};
+use log::{debug, error, warn};
+use semver::Version;
 use tabled::{Table, Tabled};
 use tabled::settings::{Alignment, Height, Remove, object::{Rows, Columns}, style::Style};
 
 use crate::config::Config;
-use crate::core::GenericResult;
-use crate::github::Github;
+use crate::core::{GenericError, GenericResult};
+use crate::i18n;
+use crate::install;
+use crate::metadata::{MetadataCache, ToolMetadata};
+use crate::porcelain::{self, Porcelain};
+use crate::provider::Providers;
+use crate::state::{InstallInfo, RunResult, State};
 use crate::tool::ToolSpec;
+use crate::util;
 use crate::version::{self, ReleaseVersion};
 
-pub fn list(config: &Config, full: bool) -> GenericResult<ExitCode> {
-    if config.tools.is_empty() {
+const MAX_PROBE_WORKERS: usize = 8;
+
+pub fn list(
+    config: &Config, format: ListFormat, full: bool, probe: bool, local: bool, stale_after: Option<Duration>,
+    unused_after: Option<Duration>,
+) -> GenericResult<ExitCode> {
+    if config.get_active_tools().next().is_none() {
         return Ok(ExitCode::SUCCESS);
     }
 
+    let rows = collect(config, probe, local, stale_after, unused_after)?;
+
+    let output = match format {
+        ListFormat::Table => format_table(rows, full, std::io::stdout().is_terminal()),
+        ListFormat::Env => format_env(&rows),
+        ListFormat::Tsv => format_tsv(&rows),
+        ListFormat::Porcelain(version) => format_porcelain(&rows, version),
+    };
+
+    let _ = write!(std::io::stdout(), "{}", output);
+    Ok(ExitCode::SUCCESS)
+}
+
+// Gathers information about all configured tools. In local mode GitHub isn't accessed and the last known release
+// information is shown instead.
+//
+// If stale threshold is specified, tools installed from releases older than the threshold are marked as stale even if
+// there is no newer release (it might indicate an abandoned project).
+//
+// If unused threshold is specified, installed tools which haven't been run for longer than the threshold are marked as
+// unused, so they can be considered for uninstall. The last run is determined by binary access time.
+pub fn collect(
+    config: &Config, probe: bool, local: bool, stale_after: Option<Duration>, unused_after: Option<Duration>,
+) -> GenericResult<Vec<ToolInfo>> {
     let mut rows = Vec::new();
-    let github = Github::new(&config.github)?;
-    let colored = std::io::stdout().is_terminal();
+    let providers = if local {
+        None
+    } else {
+        Some(Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?)
+    };
+    let state = State::load(config)?;
+
+    // The cache is just a fallback, so don't fail on its errors
+    let mut cache = MetadataCache::load(&config.get_metadata_cache_path()).map_err(|e| {
+        warn!("Failed to load project metadata cache: {e}.");
+    }).ok();
+
+    let tools: Vec<_> = config.get_active_tools().collect();
+    let install_paths: Vec<PathBuf> = tools.iter().map(|(name, spec)| {
+        config.get_tool_path(name, spec)
+    }).collect();
+
+    let mut probed_versions = probe.then(|| probe_versions(&install_paths).into_iter());
 
-    for (name, spec) in &config.tools {
+    for ((name, spec), install_path) in tools.into_iter().zip(install_paths) {
         debug!("Checking {name}...");
-        let install_path = config.get_tool_path(name, spec);
-        rows.push(list_tool(name, spec, &github, &install_path, colored));
+        let probed_version = probed_versions.as_mut().map(|versions| versions.next().unwrap());
+        let cached = cache.as_ref().and_then(|cache| cache.tools.get(name));
+
+        let (mut info, metadata) = list_tool(
+            name, spec, providers.as_ref(), &install_path, state.tools.get(name), probed_version, cached);
+
+        if let (Some(cache), Some(metadata)) = (cache.as_mut(), metadata) {
+            cache.tools.insert(name.clone(), metadata);
+        }
+
+        info.run = state.runs.get(name.as_str()).cloned();
+
+        if let (Some(stale_after), Some(install_time)) = (stale_after, info.install_time) {
+            let stale = SystemTime::now().duration_since(install_time).is_ok_and(|age| age > stale_after);
+            if stale && matches!(info.status, Status::UpToDate | Status::Unknown) {
+                info.status = Status::Stale;
+            }
+        }
+
+        if let (Some(unused_after), Some(last_use)) = (unused_after, info.last_use) {
+            info.unused = SystemTime::now().duration_since(last_use).is_ok_and(|idle| idle > unused_after);
+        }
+
+        rows.push(info);
     }
 
-    let mut table = Table::new(&rows);
-    table.with(Style::blank());
-    table.modify(Rows::first(), Height::increase(2));
-    if colored {
-        table.modify(Rows::first(), tabled::settings::Color::BOLD);
+    if let (Some(mut cache), false) = (cache, local) {
+        cache.tools.retain(|name, _| config.tools.contains_key(name));
+        if let Err(err) = cache.save() {
+            warn!("Failed to save project metadata cache: {err}.");
+        }
     }
-    table.modify(Columns::new(1..=2), Alignment::center());
-    if !full {
-        table.with(Remove::column(Columns::single(3)));
+
+    Ok(rows)
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum ListFormat {
+    #[default]
+    Table,
+    Env,
+    Tsv,
+    // Set by --porcelain option
+    Porcelain(Porcelain),
+}
+
+impl ListFormat {
+    pub const VALUES: [&'static str; 3] = ["table", "env", "tsv"];
+}
+
+impl FromStr for ListFormat {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<ListFormat> {
+        Ok(match value {
+            "table" => ListFormat::Table,
+            "env" => ListFormat::Env,
+            "tsv" => ListFormat::Tsv,
+            _ => return Err!("Invalid list format: {value:?}"),
+        })
     }
+}
 
-    let _ = writeln!(std::io::stdout(), "{}", table);
-    Ok(ExitCode::SUCCESS)
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    NotInstalled,
+    UpToDate,
+    Outdated,
+    Stale,
+    Unknown,
+}
+
+impl Display for Status {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            Status::NotInstalled => "not-installed",
+            Status::UpToDate => "up-to-date",
+            Status::Outdated => "outdated",
+            Status::Stale => "stale",
+            Status::Unknown => "unknown",
+        })
+    }
 }
 
 #[derive(Tabled)]
-struct ToolInfo {
+pub struct ToolInfo {
     #[tabled(rename = "Name")]
-    name: String,
+    pub name: String,
 
     #[tabled(rename = "Installed")]
-    installed: String,
+    pub installed: String,
 
     #[tabled(rename = "Latest")]
-    latest: String,
+    pub latest: String,
+
+    #[tabled(rename = "Last run")]
+    pub last_run: String,
+
+    #[tabled(rename = "Description")]
+    pub description: String,
 
     #[tabled(rename = "Changelog")]
-    changelog: String,
+    pub changelog: String,
+
+    #[tabled(rename = "Asset")]
+    pub asset: String,
+
+    #[tabled(rename = "Binary")]
+    pub binary: String,
+
+    #[tabled(skip)]
+    pub status: Status,
+
+    // Whether the latest release has an asset suitable for the tool
+    #[tabled(skip)]
+    pub installable: bool,
+
+    // Release archive update time which is set as binary modification time on install
+    #[tabled(skip)]
+    pub install_time: Option<SystemTime>,
+
+    // Binary access time which approximates the time when the tool has been run last time
+    #[tabled(skip)]
+    pub last_use: Option<SystemTime>,
+
+    // Whether the tool hasn't been run for longer than the unused threshold
+    #[tabled(skip)]
+    pub unused: bool,
+
+    // Whether the latest release information is taken from the cache
+    #[tabled(skip)]
+    pub cached: bool,
+
+    // The reason why the tool is held at the installed version
+    #[tabled(skip)]
+    pub hold: Option<String>,
+
+    // Result of the last install/upgrade attempt
+    #[tabled(skip)]
+    pub run: Option<RunResult>,
+
+    #[tabled(skip)]
+    pub archived: bool,
 }
 
-fn list_tool(name: &str, spec: &ToolSpec, github: &Github, install_path: &Path, colored: bool) -> ToolInfo {
+// Probed version is specified only when versions are determined by running the binaries. Returns the tool information
+// and the actual project metadata if it has been successfully obtained from GitHub.
+fn list_tool(
+    name: &str, spec: &ToolSpec, providers: Option<&Providers>, install_path: &Path, install_info: Option<&InstallInfo>,
+    probed_version: Option<Option<Version>>, cached: Option<&ToolMetadata>,
+) -> (ToolInfo, Option<ToolMetadata>) {
     let tool = crate::tool::check(install_path).unwrap_or_else(|e| {
         error!("{name}: {e}.");
         None
     });
 
-    let installed_version = tool.as_ref().and_then(|_|
-        version::get_binary_version(install_path));
+    // Running the binaries is slow and not always safe, so rely on the version recorded at install time by default
+    let (installed_version, installed) = match (tool.as_ref(), probed_version, install_info) {
+        (None, _, _) => (None, String::new()),
+        (Some(_), Some(probed_version), _) => {
+            let installed = probed_version.as_ref().map(ToString::to_string).unwrap_or_default();
+            (probed_version, installed)
+        },
+        (Some(_), None, Some(install_info)) => match ReleaseVersion::new(&install_info.version) {
+            ReleaseVersion::Version(version) => {
+                let installed = version.to_string();
+                (Some(version), installed)
+            },
+            ReleaseVersion::Tag(tag) => (None, tag),
+        },
+        (Some(_), None, None) => (None, String::new()),
+    };
 
     let mut info = ToolInfo {
         name: name.to_owned(),
-        installed: installed_version.as_ref().map(|version| version.to_string()).unwrap_or_default(),
+        installed,
         latest: String::new(),
+        last_run: String::new(),
+        description: String::new(),
         changelog: spec.changelog.as_ref().map(ToString::to_string).unwrap_or_default(),
+        asset: String::new(),
+        binary: String::new(),
+        status: if tool.is_some() {
+            Status::Unknown
+        } else {
+            Status::NotInstalled
+        },
+        installable: false,
+        install_time: tool.as_ref().map(|tool| tool.modify_time),
+        last_use: tool.as_ref().map(|tool| tool.access_time),
+        unused: false,
+        cached: false,
+        hold: spec.hold.clone(),
+        run: None,
+        archived: false,
     };
 
-    let release = match github.get_release(&spec.project) {
-        Ok(release) => release,
-        Err(err) => {
-            error!("{name}: Failed to get latest release info for {}: {err}.", spec.project);
-            return info;
+    if let (Some(_), Some(install_info)) = (tool.as_ref(), install_info) {
+        info.asset = install_info.asset.clone();
+        info.binary = install_info.binary.display().to_string();
+    }
+
+    if spec.tag.is_some() {
+        if let Some(tool) = tool.as_ref() {
+            info.installed = format_time(tool.modify_time);
         }
+    }
+
+    let actual = providers.map(|providers| providers.get_tool_release(name, spec).map(|(release, asset)| {
+        // The repository may be archived at any time, so its status is rechecked daily
+        let fresh = cached.filter(|metadata| metadata.description.is_some() && metadata.repository_time.is_some_and(
+            |time| Utc::now().signed_duration_since(time) < TimeDelta::days(1)));
+
+        let (description, archived, repository_time) = match fresh {
+            Some(metadata) => (metadata.description.clone(), metadata.archived, metadata.repository_time),
+            // Plain HTTP sources have no repository
+            None if spec.url.is_some() => (None, false, None),
+            None => match providers.get_repository(&release.project) {
+                Ok(repository) => (Some(repository.description), repository.archived, Some(Utc::now())),
+                Err(err) => {
+                    error!("{name}: Failed to get {} repository info: {err}.", release.project.full_name());
+                    (
+                        cached.and_then(|metadata| metadata.description.clone()),
+                        cached.is_some_and(|metadata| metadata.archived),
+                        cached.and_then(|metadata| metadata.repository_time),
+                    )
+                },
+            },
+        };
+
+        ToolMetadata {
+            changelog: install::expand_changelog(
+                spec.changelog.as_ref().unwrap_or(&release.project.changelog), &release.version),
+            latest_version: release.version.to_string(),
+            latest_time: asset.ok().map(|asset| asset.time),
+            description,
+            archived,
+            repository_time,
+        }
+    }));
+
+    let (metadata, actual) = match actual {
+        Some(Ok(metadata)) => (metadata.clone(), Some(metadata)),
+        result => {
+            if let Some(Err(err)) = result {
+                error!("{name}: {err}.");
+            }
+
+            match cached {
+                Some(metadata) => {
+                    info.cached = true;
+                    (metadata.clone(), None)
+                },
+                None => return (info, None),
+            }
+        },
     };
 
-    info.latest = release.version.to_string();
-    if info.changelog.is_empty() {
-        info.changelog = release.project.changelog.to_string();
+    if metadata.archived {
+        warn!("{name}: The project repository is archived.");
+        info.archived = true;
     }
 
-    if colored {
-        let release_time: Option<SystemTime> = match release.select_asset(name, spec.release_matcher.as_ref()) {
-            Ok(asset) => Some(asset.time.into()),
-            Err(_) => {
-                info.latest = Color::Yellow.paint(info.latest).to_string();
-                None
-            },
+    let release_version = ReleaseVersion::new(&metadata.latest_version);
+    info.latest = metadata.latest_version;
+    info.description = metadata.description.unwrap_or_default();
+    info.changelog = metadata.changelog;
+
+    let release_time: Option<SystemTime> = metadata.latest_time.map(Into::into);
+    info.installable = release_time.is_some();
+
+    // Moving tag always has the same name, so show its assets update time instead
+    if let (Some(_), Some(release_time)) = (spec.tag.as_ref(), release_time) {
+        info.latest = format_time(release_time);
+    }
+
+    if let (Some(current), ReleaseVersion::Version(latest), None) = (installed_version, release_version, spec.tag.as_ref()) {
+        info.status = if current >= latest {
+            Status::UpToDate
+        } else {
+            Status::Outdated
         };
+    } else if let (Some(tool), Some(release_time)) = (tool, release_time) {
+        info.status = if tool.modify_time >= release_time {
+            Status::UpToDate
+        } else {
+            Status::Outdated
+        };
+    }
 
-        if let (Some(current), ReleaseVersion::Version(latest)) = (installed_version, release.version) {
-            let color = if current >= latest {
-                Color::Green
-            } else {
-                Color::Yellow
+    (info, actual)
+}
+
+fn format_table(mut rows: Vec<ToolInfo>, full: bool, colored: bool) -> String {
+    for info in &mut rows {
+        if info.cached {
+            info.latest += &format!(" ({})", i18n::tr("cached"));
+        }
+        if info.archived {
+            info.latest += &format!(" ({})", i18n::tr("archived"));
+        }
+
+        if let Some(run) = info.run.as_ref() {
+            info.last_run = match (run.error.as_ref(), full) {
+                (None, _) => i18n::tr("ok").to_owned(),
+                (Some(_), false) => i18n::tr("failed").to_owned(),
+                (Some(error), true) => i18n::trf(
+                    "failed at {}: {}", &[&format_time(run.time.into()), &error.lines().next().unwrap_or_default()]),
             };
-            info.installed = color.paint(info.installed).to_string();
-        } else if let (Some(tool), Some(release_time)) = (tool, release_time) {
-            let color = if tool.modify_time >= release_time {
-                Color::Green
+        }
+    }
+
+    if colored {
+        for info in &mut rows {
+            if !info.installable && !info.latest.is_empty() {
+                info.latest = Color::Yellow.paint(&info.latest).to_string();
+            }
+
+            match info.status {
+                Status::UpToDate => info.installed = Color::Green.paint(&info.installed).to_string(),
+                Status::Outdated => info.installed = Color::Yellow.paint(&info.installed).to_string(),
+                Status::Stale => info.installed = Color::Red.paint(&info.installed).to_string(),
+                Status::NotInstalled | Status::Unknown => {},
+            }
+
+            if info.run.as_ref().is_some_and(|run| run.error.is_some()) {
+                info.last_run = Color::Red.paint(&info.last_run).to_string();
+            }
+        }
+    }
+
+    for info in &mut rows {
+        if let Some(reason) = info.hold.as_ref() {
+            info.installed += &format!(" ({})", i18n::trf("held: {}", &[reason]));
+        }
+
+        if let (true, Some(last_use)) = (info.unused, info.last_use) {
+            info.installed += &format!(" ({})", i18n::trf("unused since {}", &[&format_date(last_use)]));
+        }
+    }
+
+    let mut table = Table::new(&rows);
+    table.with(Style::blank());
+    i18n::localize_header(&mut table);
+    table.modify(Rows::first(), Height::increase(2));
+    if colored {
+        table.modify(Rows::first(), tabled::settings::Color::BOLD);
+    }
+    table.modify(Columns::new(1..=3), Alignment::center());
+    if !full {
+        table.with(Remove::column(Columns::new(4..)));
+    }
+
+    format!("{table}\n")
+}
+
+// Produces TOOL_$NAME_VERSION=... lines which may be evaluated by shell
+fn format_env(rows: &[ToolInfo]) -> String {
+    let mut output = String::new();
+
+    for info in rows {
+        let name: String = info.name.chars().map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
             } else {
-                Color::Yellow
-            };
-            info.installed = color.paint(info.installed).to_string();
+                '_'
+            }
+        }).collect();
+
+        for (key, value) in [
+            ("VERSION", info.installed.as_str()),
+            ("LATEST", info.latest.as_str()),
+            ("STATUS", &info.status.to_string()),
+        ] {
+            output.push_str(&format!("TOOL_{name}_{key}={}\n", util::shell_quote(value)));
         }
     }
 
-    info
+    output
 }
+
+fn format_tsv(rows: &[ToolInfo]) -> String {
+    rows.iter().map(|info| format!("{}\t{}\t{}\n", info.name, info.installed, info.status)).collect()
+}
+
+pub fn format_porcelain(rows: &[ToolInfo], version: Porcelain) -> String {
+    match version {
+        Porcelain::V1 => rows.iter().map(|info| {
+            let run = info.run.as_ref();
+
+            porcelain::format_record("tool", &[
+                &info.name,
+                &info.installed,
+                &info.latest,
+                &info.status.to_string(),
+                porcelain::format_bool(info.installable),
+                porcelain::format_bool(info.cached),
+                porcelain::format_bool(info.archived),
+                info.hold.as_deref().unwrap_or_default(),
+                run.map(|run| if run.error.is_some() { "failed" } else { "ok" }).unwrap_or_default(),
+                &run.map(|run| porcelain::format_time(run.time)).unwrap_or_default(),
+                run.and_then(|run| run.error.as_deref()).unwrap_or_default(),
+            ])
+        }).collect(),
+    }
+}
+
+// Runs the binaries concurrently using a bounded pool of workers
+fn probe_versions(paths: &[PathBuf]) -> Vec<Option<Version>> {
+    let workers = thread::available_parallelism().map(|count| count.get()).unwrap_or(1).min(MAX_PROBE_WORKERS);
+    let versions = Mutex::new(vec![None; paths.len()]);
+    let tasks = Mutex::new(paths.iter().enumerate());
+
+    thread::scope(|scope| {
+        for _ in 0..workers.min(paths.len()) {
+            scope.spawn(|| {
+                loop {
+                    let Some((index, path)) = tasks.lock().unwrap().next() else {
+                        break;
+                    };
+
+                    if path.exists() {
+                        let version = version::get_binary_version(path);
+                        versions.lock().unwrap()[index] = version;
+                    }
+                }
+            });
+        }
+    });
+
+    versions.into_inner().unwrap()
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn format_date(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d").to_string()
+}
\ No newline at end of file
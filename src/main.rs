@@ -1,31 +1,66 @@
 #[macro_use] mod core;
 
+mod access;
+mod bitbucket;
+mod bootstrap;
+mod checksums;
 mod cli;
+mod completion;
 mod config;
+mod distro;
 mod download;
+mod file_types;
+mod forks;
+mod gc;
+mod gitea;
 mod github;
+mod http_source;
+mod i18n;
+mod inspect;
 mod install;
+mod lint;
 mod list;
 mod matcher;
+mod metadata;
+mod netrc;
+mod network;
+mod notes;
+mod porcelain;
 mod project;
+mod provider;
+mod rate_limit;
+mod reflink;
 mod release;
+mod report;
+mod s3;
+mod shell_path;
+mod shims;
+mod state;
+mod timings;
+mod tls;
 mod tool;
 mod uninstall;
 mod util;
+mod verify;
 mod version;
+mod xattr;
 
 use core::GenericResult;
 use std::io::{self, Write};
 use std::path::Path;
 use std::process::{self, ExitCode};
 
+use clap_complete::CompleteEnv;
 use easy_logging::LoggingConfig;
 use log::error;
 
 use crate::cli::Action;
-use crate::config::Config;
+use crate::config::{Config, Layer};
+use crate::network::IpVersion;
 
 fn main() -> ExitCode {
+    CompleteEnv::with_factory(cli::build_cli).complete();
+
     let args = cli::parse_args().unwrap_or_else(|e| {
         let _ = writeln!(io::stderr(), "{}.", e);
         process::exit(1);
@@ -36,7 +71,9 @@ fn main() -> ExitCode {
         process::exit(1);
     }
 
-    match run(&args.config_path, args.custom_config, args.action) {
+    util::set_prompt_default(args.prompt_default);
+
+    match run(&args.config_path, args.custom_config, args.system, args.ip_version, args.action) {
         Ok(code) => code,
         Err(err) => {
             let message = err.to_string();
@@ -52,14 +89,55 @@ fn main() -> ExitCode {
     }
 }
 
-fn run(config_path: &Path, custom_config: bool, action: Action) -> GenericResult<ExitCode> {
-    let mut config = Config::load(config_path, custom_config).map_err(|e| format!(
+fn run(
+    config_path: &Path, custom_config: bool, system: bool, ip_version: Option<IpVersion>, action: Action,
+) -> GenericResult<ExitCode> {
+    if let Action::RestoreConfig = action {
+        return config::restore_backup(config_path);
+    }
+
+    // Custom configuration file is self-contained, so the system one isn't merged into it
+    let layer = if system {
+        Layer::System
+    } else if custom_config {
+        Layer::User(None)
+    } else {
+        Layer::user(Path::new(cli::SYSTEM_CONFIG_PATH))?
+    };
+
+    let mut config = Config::load(config_path, custom_config, layer).map_err(|e| format!(
         "Error while reading {:?} configuration file: {}", config_path, e))?;
 
+    let mut overrides = Vec::new();
+    if let Some(ip_version) = ip_version {
+        config.network.ip_version = ip_version;
+        overrides.push("network.ip_version");
+    }
+
+    release::set_static_preference(distro::prefers_static_assets(config.static_assets));
+
     match action {
-        Action::List {full} => list::list(&config, full),
-        Action::Install {mode, names} => install::install(&config, mode, names),
-        Action::InstallFromSpec {name, spec, force} => install::install_spec(&mut config, name, spec, force),
+        Action::List {format, full, probe, local, stale_after, unused} => list::list(
+            &config, format, full, probe, local, stale_after, unused),
+        Action::Install {mode, names, transactional, force_overwrite, timings} => install::install(
+            &mut config, mode, names, transactional, force_overwrite, timings),
+        Action::InstallFromSpec {name, spec, force, force_overwrite, timings} => install::install_spec(
+            &mut config, name, spec, force, force_overwrite, timings),
+        Action::InstallSelf {force, force_overwrite, timings} => install::install_self(
+            &mut config, force, force_overwrite, timings),
+        Action::Inspect {name, porcelain} => inspect::inspect(&config, &name, porcelain),
+        Action::SuggestForks {name, inactive_for, porcelain} => forks::suggest_forks(
+            &config, &name, inactive_for, porcelain),
+        Action::Notes {name, output} => notes::notes(&config, &name, output.as_deref()),
         Action::Uninstall {names} => uninstall::uninstall(&mut config, names),
+        Action::CollectGarbage {keep} => gc::gc(&config, keep),
+        Action::Report {format, probe, new_only} => report::report(&config, format, probe, new_only),
+        Action::Verify {names, paranoid} => verify::verify(&config, names, paranoid),
+        Action::Bootstrap {output} => bootstrap::bootstrap(&config, output.as_deref()),
+        Action::ShowConfig {effective} => config::show(&config, effective, &overrides),
+        Action::LintConfig {fix} => lint::lint(&mut config, fix),
+        Action::SignState => state::sign(&config),
+        Action::InstallShims {name, version} => shims::install(&config, name, version),
+        Action::RestoreConfig => unreachable!(),
     }
 }
\ No newline at end of file
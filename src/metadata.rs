@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::util;
+
+// Last known information about the tool projects which allows to list the tools without GitHub access
+#[derive(Default, Deserialize, Serialize)]
+pub struct MetadataCache {
+    #[serde(skip)]
+    path: PathBuf,
+
+    #[serde(default)]
+    pub tools: BTreeMap<String, ToolMetadata>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ToolMetadata {
+    pub changelog: String,
+    pub latest_version: String,
+
+    // Update time of the latest release asset suitable for the tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_time: Option<DateTime<Utc>>,
+
+    // Repository description is unlikely to change, so it's requested only once (empty means no description)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    // Archive status and the time when it has been checked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository_time: Option<DateTime<Utc>>,
+}
+
+impl MetadataCache {
+    pub fn load(path: &Path) -> GenericResult<MetadataCache> {
+        let mut cache: MetadataCache = match fs::read(path) {
+            Ok(data) => serde_yaml::from_slice(&data).map_err(|e| format!(
+                "Error while reading {path:?}: {e}"))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => MetadataCache::default(),
+            Err(err) => return Err!("Unable to read {path:?}: {err}"),
+        };
+
+        cache.path = path.to_owned();
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> EmptyResult {
+        if let Some(path) = self.path.parent() {
+            fs::create_dir_all(path).map_err(|e| format!(
+                "Unable to create {path:?}: {e}"))?;
+        }
+
+        let data = serde_yaml::to_string(self)?;
+        util::write_file(&self.path, data.as_bytes())
+    }
+}
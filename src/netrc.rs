@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use log::debug;
+
+use crate::core::GenericResult;
+
+// Credentials from ~/.netrc (or $NETRC) which are used for basic authentication on download hosts
+#[derive(Default)]
+pub struct Netrc {
+    machines: BTreeMap<String, Credentials>,
+    default: Option<Credentials>,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Credentials {
+    pub login: String,
+    pub password: Option<String>,
+}
+
+impl Netrc {
+    pub fn load() -> GenericResult<Netrc> {
+        let path = match env::var_os("NETRC") {
+            Some(path) => PathBuf::from(path),
+            None => match env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".netrc"),
+                None => return Ok(Netrc::default()),
+            },
+        };
+
+        let data = match fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                debug!("{path:?} doesn't exist.");
+                return Ok(Netrc::default());
+            },
+            Err(err) => return Err!("Unable to read {path:?}: {err}"),
+        };
+
+        Ok(Netrc::parse(&data))
+    }
+
+    pub fn get(&self, host: &str) -> Option<&Credentials> {
+        self.machines.get(host).or(self.default.as_ref())
+    }
+
+    fn parse(data: &str) -> Netrc {
+        let mut netrc = Netrc::default();
+        let mut current: Option<(Option<String>, Credentials)> = None;
+        let mut lines = data.lines();
+
+        let finish = |netrc: &mut Netrc, current: Option<(Option<String>, Credentials)>| {
+            match current {
+                Some((Some(machine), credentials)) => {
+                    netrc.machines.entry(machine).or_insert(credentials);
+                },
+                Some((None, credentials)) => {
+                    netrc.default.get_or_insert(credentials);
+                },
+                None => {},
+            }
+        };
+
+        while let Some(line) = lines.next() {
+            let mut tokens = line.split_whitespace();
+
+            while let Some(token) = tokens.next() {
+                match token {
+                    "machine" => {
+                        finish(&mut netrc, current.take());
+                        current = tokens.next().map(|machine| (Some(machine.to_owned()), Credentials::default()));
+                    },
+                    "default" => {
+                        finish(&mut netrc, current.take());
+                        current = Some((None, Credentials::default()));
+                    },
+                    "login" | "password" => {
+                        let (Some((_, credentials)), Some(value)) = (current.as_mut(), tokens.next()) else {
+                            continue;
+                        };
+                        if token == "login" {
+                            credentials.login = value.to_owned();
+                        } else {
+                            credentials.password = Some(value.to_owned());
+                        }
+                    },
+                    "account" => {
+                        tokens.next();
+                    },
+                    // Macro definition lasts until an empty line
+                    "macdef" => {
+                        finish(&mut netrc, current.take());
+                        for line in lines.by_ref() {
+                            if line.trim().is_empty() {
+                                break;
+                            }
+                        }
+                        break;
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        finish(&mut netrc, current);
+        netrc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let netrc = Netrc::parse(indoc!("
+            machine artifacts.example.com
+                login user
+                password secret
+
+            machine macro.example.com login macro password value
+            macdef init
+            machine ignored.example.com login ignored
+
+            machine other.example.com login other account ignored
+            default login anonymous password guest
+        "));
+
+        let credentials = |login: &str, password: Option<&str>| Credentials {
+            login: login.to_owned(),
+            password: password.map(ToOwned::to_owned),
+        };
+
+        assert_eq!(netrc.get("artifacts.example.com"), Some(&credentials("user", Some("secret"))));
+        assert_eq!(netrc.get("macro.example.com"), Some(&credentials("macro", Some("value"))));
+        assert_eq!(netrc.get("other.example.com"), Some(&credentials("other", None)));
+        assert_eq!(netrc.get("ignored.example.com"), Some(&credentials("anonymous", Some("guest"))));
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::vec;
+
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::connect::dns::{GaiResolver, Name};
+use reqwest::blocking::ClientBuilder;
+use serde::Deserialize;
+use tower_service::Service;
+
+// Connection options for environments with broken IPv6 or split-horizon DNS which are used by both GitHub API and
+// download clients
+#[derive(Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    #[serde(default)]
+    pub ip_version: IpVersion,
+    // Host to address mapping which is used instead of DNS
+    #[serde(default)]
+    pub resolve: BTreeMap<String, IpAddr>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpVersion {
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl Display for IpVersion {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            IpVersion::Any => "any",
+            IpVersion::Ipv4 => "ipv4",
+            IpVersion::Ipv6 => "ipv6",
+        })
+    }
+}
+
+impl NetworkConfig {
+    pub fn configure_client(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        if let Some(address) = self.get_local_address() {
+            builder = builder.local_address(address);
+        }
+
+        for (host, address) in &self.resolve {
+            // The port is ignored by reqwest
+            builder = builder.resolve(host, SocketAddr::new(*address, 0));
+        }
+
+        builder
+    }
+
+    pub fn get_connector(&self) -> HttpConnector<Resolver> {
+        let mut connector = HttpConnector::new_with_resolver(Resolver {
+            overrides: Arc::new(self.resolve.clone()),
+            system: GaiResolver::new(),
+        });
+        connector.enforce_http(false);
+        connector.set_local_address(self.get_local_address());
+        connector
+    }
+
+    // Binding to unspecified address of the specific family makes the connectors skip addresses of the other one
+    fn get_local_address(&self) -> Option<IpAddr> {
+        match self.ip_version {
+            IpVersion::Any => None,
+            IpVersion::Ipv4 => Some(Ipv4Addr::UNSPECIFIED.into()),
+            IpVersion::Ipv6 => Some(Ipv6Addr::UNSPECIFIED.into()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Resolver {
+    overrides: Arc<BTreeMap<String, IpAddr>>,
+    system: GaiResolver,
+}
+
+impl Service<Name> for Resolver {
+    type Response = vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.system.poll_ready(context)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        if let Some(address) = self.overrides.get(name.as_str()) {
+            let addresses = vec![SocketAddr::new(*address, 0)];
+            return Box::pin(async move { Ok(addresses.into_iter()) });
+        }
+
+        let future = self.system.call(name);
+        Box::pin(async move {
+            Ok(future.await?.collect::<Vec<_>>().into_iter())
+        })
+    }
+}
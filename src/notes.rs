@@ -0,0 +1,128 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use log::info;
+use semver::Version;
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::provider::Providers;
+use crate::release::Release;
+use crate::state::State;
+use crate::tool::ReleaseStrategy;
+use crate::util;
+use crate::version::ReleaseVersion;
+
+// Aggregates release notes of all releases between the installed and the latest versions into a Markdown document
+pub fn notes(config: &Config, name: &str, output: Option<&Path>) -> GenericResult<ExitCode> {
+    let spec = config.tools.get(name).ok_or_else(|| format!(
+        "{name} isn't specified in the configuration file"))?;
+
+    if spec.artifact.is_some() || spec.url.is_some() {
+        return Err!("{name} is not installed from project releases, so it has no release notes");
+    }
+
+    let state = State::load(config)?;
+    let info = state.tools.get(name).ok_or_else(|| format!("{name} isn't installed by binup"))?;
+    let ReleaseVersion::Version(installed) = ReleaseVersion::new(&info.version) else {
+        return Err!("{name} installed version ({}) is not a semantic version", info.version);
+    };
+
+    let providers = Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?;
+    let (latest, _) = providers.get_tool_release(name, spec)?;
+    let ReleaseVersion::Version(latest_version) = &latest.version else {
+        return Err!("{name} latest release version ({}) is not a semantic version", latest.version);
+    };
+
+    if *latest_version <= installed {
+        info!("{name} is already up-to-date.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let project = latest.project.full_name();
+    let releases = providers.get_releases(&project, None, ReleaseStrategy::HighestVersion, spec.max_release_pages)
+        .map_err(|e| format!("Failed to get {project} releases: {e}"))?;
+
+    let document = format_notes(name, &installed, latest_version, &releases);
+
+    match output {
+        Some(path) => {
+            util::write_file(path, document.as_bytes())?;
+            info!("{name} release notes are written to {path:?}.");
+        },
+        None => {
+            io::stdout().write_all(document.as_bytes()).map_err(|e| format!(
+                "Failed to write the release notes: {e}"))?;
+        },
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// Releases are listed from the newest to the oldest one like in changelogs
+fn format_notes(name: &str, installed: &Version, latest: &Version, releases: &[Release]) -> String {
+    let mut document = format!("# {name} {installed} -> {latest}\n");
+
+    for release in releases {
+        let ReleaseVersion::Version(version) = &release.version else {
+            continue;
+        };
+
+        if version <= installed || version > latest {
+            continue;
+        }
+
+        document.push_str(&format!("\n## {version}\n\n"));
+        match release.notes.as_ref() {
+            Some(notes) => {
+                document.push_str(&notes.trim().replace("\r\n", "\n"));
+                document.push('\n');
+            },
+            None => document.push_str("_The release has no notes._\n"),
+        }
+    }
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use crate::github;
+    use super::*;
+
+    #[test]
+    fn notes() {
+        let project = github::parse_project_name("owner/tool").unwrap();
+        let releases: Vec<Release> = [
+            ("v1.3.0", None),
+            ("v1.2.1", Some("* Fixed crash\r\n* Improved performance\r\n")),
+            ("v1.2.0", Some("  \n")),
+            ("v1.1.0", Some("* Added feature")),
+            ("v1.0.0", Some("* Initial release")),
+        ].into_iter().map(|(tag, notes)| {
+            Release::new(project.clone(), tag, Vec::new()).with_notes(notes.map(ToOwned::to_owned))
+        }).collect();
+
+        let installed = Version::new(1, 0, 0);
+        let latest = Version::new(1, 2, 1);
+
+        assert_eq!(format_notes("tool", &installed, &latest, &releases), indoc!("
+            # tool 1.0.0 -> 1.2.1
+
+            ## 1.2.1
+
+            * Fixed crash
+            * Improved performance
+
+            ## 1.2.0
+
+            _The release has no notes._
+
+            ## 1.1.0
+
+            * Added feature
+        "));
+    }
+}
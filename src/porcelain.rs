@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::core::{GenericError, GenericResult};
+
+// Stable machine-readable output for wrapper scripts. Each line is a record of tab-separated fields where the first
+// field is the record type. Within a porcelain version the existing fields are never removed or reordered, so human
+// output may be changed freely.
+#[derive(Clone, Copy)]
+pub enum Porcelain {
+    V1,
+}
+
+impl Porcelain {
+    pub const VALUES: [&'static str; 1] = ["v1"];
+}
+
+impl FromStr for Porcelain {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<Porcelain> {
+        Ok(match value {
+            "v1" => Porcelain::V1,
+            _ => return Err!("Invalid porcelain version: {value:?}"),
+        })
+    }
+}
+
+// Fields are escaped, so tabs and newlines inside of them don't break the record
+pub fn format_record(kind: &str, fields: &[&str]) -> String {
+    let mut record = kind.to_owned();
+
+    for field in fields {
+        record.push('\t');
+
+        for c in field.chars() {
+            match c {
+                '\\' => record.push_str("\\\\"),
+                '\t' => record.push_str("\\t"),
+                '\n' => record.push_str("\\n"),
+                '\r' => record.push_str("\\r"),
+                _ => record.push(c),
+            }
+        }
+    }
+
+    record.push('\n');
+    record
+}
+
+pub fn format_bool(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+pub fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(fields, expected,
+        case(&[], "tool\n"),
+        case(&["name", "", "1.0.0"], "tool\tname\t\t1.0.0\n"),
+        case(&["a\tb", "c\nd\\e"], "tool\ta\\tb\tc\\nd\\\\e\n"),
+    )]
+    fn record(fields: &[&str], expected: &str) {
+        assert_eq!(format_record("tool", fields), expected);
+    }
+}
@@ -1,6 +1,11 @@
 use url::Url;
 
+use crate::s3;
+
+#[derive(Clone)]
 pub struct Project {
+    // Gitea-compatible service host, S3 pseudo host or None for GitHub
+    pub host: Option<String>,
     pub name: String,
     pub owner: String,
     pub changelog: Url,
@@ -8,6 +13,10 @@ pub struct Project {
 
 impl Project {
     pub fn full_name(&self) -> String {
-        format!("{}/{}", self.owner, self.name)
+        match self.host.as_deref() {
+            Some(s3::HOST) => format!("s3://{}/{}", self.owner, self.name),
+            Some(host) => format!("{host}/{}/{}", self.owner, self.name),
+            None => format!("{}/{}", self.owner, self.name),
+        }
     }
 }
\ No newline at end of file
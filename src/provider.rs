@@ -0,0 +1,330 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use url::Url;
+
+use crate::bitbucket::{self, Bitbucket, BitbucketConfig};
+use crate::core::GenericResult;
+use crate::gitea::Gitea;
+use crate::github::{self, Github, GithubConfig};
+use crate::http_source::HttpSource;
+use crate::network::NetworkConfig;
+use crate::project::Project;
+use crate::release::{Asset, Release};
+use crate::s3::{self, S3, S3Config};
+use crate::tls::TlsConfig;
+use crate::tool::{ArtifactSpec, ReleaseStrategy, ToolSpec};
+
+// Source of project releases (GitHub, Gitea-compatible services, Bitbucket, etc.). Providers are registered by the hosts
+// they serve, so the code which installs and lists the tools doesn't depend on the specific forge.
+pub trait ReleaseProvider {
+    // Returns the latest release or the release with the specified tag. For highest-version strategy returns all
+    // release candidates in order of preference.
+    fn get_releases(
+        &self, project: &Project, tag: Option<&str>, strategy: ReleaseStrategy, max_pages: usize,
+    ) -> GenericResult<Vec<Release>>;
+
+    // Returns repository description (empty if it's not set) and archive status
+    fn get_repository(&self, project: &Project) -> GenericResult<Repository>;
+
+    // Returns the most starred forks of the project
+    fn get_forks(&self, project: &Project, _limit: u8) -> GenericResult<Vec<Fork>> {
+        Err!("Forks aren't supported for {} projects", project.host.as_deref().unwrap_or_default())
+    }
+
+    // Represents the latest workflow artifact as a release with a single asset
+    fn get_artifact(&self, _project: &Project, _artifact: &ArtifactSpec) -> GenericResult<Release> {
+        Err!("Workflow artifacts are supported only for GitHub projects")
+    }
+
+    // Returns URL of the project releases page if it differs from the one derived from the project name
+    fn get_changelog(&self, _project: &Project) -> Option<Url> {
+        None
+    }
+
+    // Returns the headers which are required to download the project's release assets or workflow artifacts
+    fn get_download_headers(&self, _project: &Project, _artifact: bool) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+
+    // Whether the release asset URLs expire (like presigned ones), so they can't be recorded to be used later
+    fn has_expiring_urls(&self) -> bool {
+        false
+    }
+
+    // Returns the projects which have been detected to be renamed or transferred: configured name -> actual name
+    fn get_renamed_projects(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+}
+
+pub struct Repository {
+    pub description: String,
+    pub archived: bool,
+}
+
+pub struct Fork {
+    pub name: String,
+    pub stars: u32,
+    pub pushed_at: Option<DateTime<Utc>>,
+}
+
+// Renamed or transferred projects: configured name -> actual name
+#[derive(Default)]
+pub struct Renames(RefCell<BTreeMap<String, String>>);
+
+impl Renames {
+    // Forges redirect requests for renamed and transferred repositories, so the actual name (owner/name) is visible in
+    // responses
+    pub fn check(&self, project: &Project, actual: &str) {
+        let configured = project.full_name();
+        let actual = format_project_name(project, actual);
+        if actual.eq_ignore_ascii_case(&configured) {
+            return;
+        }
+
+        if self.0.borrow_mut().insert(configured.clone(), actual.to_owned()).is_none() {
+            warn!("{configured} project has been renamed or transferred to {actual}.");
+        }
+    }
+
+    pub fn get(&self) -> BTreeMap<String, String> {
+        self.0.borrow().clone()
+    }
+}
+
+// Tool project and the provider of its releases
+type Source = (Project, Rc<dyn ReleaseProvider>);
+
+// Release providers by the hosts they serve
+pub struct Providers {
+    github: Rc<Github>,
+    hosts: BTreeMap<String, Rc<dyn ReleaseProvider>>,
+    // Projects hosted on unknown hosts are considered to be hosted on Gitea-compatible services
+    fallback: Rc<dyn ReleaseProvider>,
+    // Host of projects which are specified without host
+    default_host: String,
+    http: HttpSource,
+}
+
+impl Providers {
+    pub fn new(
+        github: &GithubConfig, bitbucket: &BitbucketConfig, s3: &S3Config, tls: &TlsConfig, network: &NetworkConfig,
+    ) -> GenericResult<Providers> {
+        let github = Rc::new(Github::new(github, tls, network)?);
+
+        let mut providers = Providers {
+            github: github.clone(),
+            hosts: BTreeMap::new(),
+            fallback: Rc::new(Gitea::new(tls, network)?),
+            default_host: github.get_default_host().to_owned(),
+            http: HttpSource::new(tls, network)?,
+        };
+
+        for host in github.get_hosts() {
+            providers.register(host, github.clone());
+        }
+        providers.register(bitbucket::HOST, Rc::new(Bitbucket::new(bitbucket, tls, network)?));
+        providers.register(s3::HOST, Rc::new(S3::new(s3, tls, network)?));
+
+        Ok(providers)
+    }
+
+    // Registers the provider for the projects hosted on the specified host. The provider which has been registered for
+    // the host before is replaced.
+    pub fn register(&mut self, host: &str, provider: Rc<dyn ReleaseProvider>) {
+        self.hosts.insert(host.to_ascii_lowercase(), provider);
+    }
+
+    pub fn github(&self) -> &Github {
+        &self.github
+    }
+
+    // Tries the tool sources and their release candidates in order and returns the first release which has an asset
+    // suitable for the tool. If there is no such release, returns the last source's preferred release with asset
+    // selection error.
+    pub fn get_tool_release(&self, name: &str, spec: &ToolSpec) -> GenericResult<(Release, GenericResult<Asset>)> {
+        let mut sources = self.get_sources(name, spec).into_iter().peekable();
+
+        while let Some((source, resolved)) = sources.next() {
+            let last = sources.peek().is_none();
+
+            let releases = resolved.and_then(|(project, provider)| match spec.artifact.as_ref() {
+                Some(artifact) => provider.get_artifact(&project, artifact).map(|release| vec![release]),
+                None => provider.get_releases(&project, spec.tag.as_deref(), spec.strategy, spec.max_release_pages),
+            }).map_err(|e| format!("Failed to get latest release info for {source}: {e}"));
+
+            let releases = match releases {
+                Ok(releases) => releases,
+                Err(err) if !last => {
+                    debug!("{err}. Trying the next project...");
+                    continue;
+                },
+                Err(err) => return Err(err.into()),
+            };
+
+            let (release, asset) = select_release(name, spec, releases);
+            if asset.is_ok() || last {
+                return Ok((release, asset));
+            }
+
+            debug!("{source}: {}. Trying the next project...", asset.err().unwrap());
+        }
+
+        Err!("The tool has no projects specified")
+    }
+
+    // Returns the latest release or the release with the specified tag. For highest-version strategy returns all
+    // release candidates in order of preference.
+    pub fn get_releases(
+        &self, project: &str, tag: Option<&str>, strategy: ReleaseStrategy, max_pages: usize,
+    ) -> GenericResult<Vec<Release>> {
+        let project = self.parse_project(project)?;
+        self.get_provider(&project).get_releases(&project, tag, strategy, max_pages)
+    }
+
+    pub fn get_repository(&self, project: &Project) -> GenericResult<Repository> {
+        self.get_provider(project).get_repository(project)
+    }
+
+    pub fn get_forks(&self, project: &Project, limit: u8) -> GenericResult<Vec<Fork>> {
+        self.get_provider(project).get_forks(project, limit)
+    }
+
+    pub fn get_download_headers(&self, spec: &ToolSpec) -> BTreeMap<String, String> {
+        let mut headers = spec.download_headers.clone();
+
+        if let Some(project) = spec.project.first().and_then(|project| self.parse_project(project).ok()) {
+            headers.extend(self.get_provider(&project).get_download_headers(&project, spec.artifact.is_some()));
+        }
+
+        headers
+    }
+
+    pub fn has_expiring_urls(&self, spec: &ToolSpec) -> bool {
+        spec.project.iter().filter_map(|project| self.parse_project(project).ok()).any(|project| {
+            self.get_provider(&project).has_expiring_urls()
+        })
+    }
+
+    pub fn get_renamed_projects(&self) -> BTreeMap<String, String> {
+        let mut renames = self.fallback.get_renamed_projects();
+        for provider in self.hosts.values() {
+            renames.extend(provider.get_renamed_projects());
+        }
+        renames
+    }
+
+    // Returns the tool release sources in order of preference: its projects or plain HTTP URL
+    fn get_sources(&self, name: &str, spec: &ToolSpec) -> Vec<(String, GenericResult<Source>)> {
+        if let Some(url) = spec.url.as_ref() {
+            let provider = self.http.get_provider(url, &spec.download_headers);
+            let project = provider.get_project(name);
+            return vec![(url.latest_version_url.to_string(), Ok((project, Rc::new(provider))))];
+        }
+
+        spec.project.iter().map(|source| {
+            let resolved = self.parse_project(source).map(|project| {
+                let provider = self.get_provider(&project).clone();
+                (project, provider)
+            });
+            (source.clone(), resolved)
+        }).collect()
+    }
+
+    fn get_provider(&self, project: &Project) -> &Rc<dyn ReleaseProvider> {
+        let host = project.host.as_deref().unwrap_or(&self.default_host).to_ascii_lowercase();
+        self.hosts.get(&host).unwrap_or(&self.fallback)
+    }
+
+    fn parse_project(&self, name: &str) -> GenericResult<Project> {
+        let mut project = github::parse_project_name(name)?;
+        if let Some(changelog) = self.get_provider(&project).get_changelog(&project) {
+            project.changelog = changelog;
+        }
+        Ok(project)
+    }
+}
+
+// Returns the first release candidate which has an asset suitable for the tool or the preferred release with asset
+// selection error
+fn select_release(name: &str, spec: &ToolSpec, releases: Vec<Release>) -> (Release, GenericResult<Asset>) {
+    let mut releases = releases.into_iter();
+
+    let preferred = releases.next().expect("Got an empty list of releases");
+    let preferred_asset = preferred.select_tool_asset(name, spec).cloned();
+    if preferred_asset.is_ok() {
+        return (preferred, preferred_asset);
+    }
+
+    // Skip the releases which can't be installed: for example, when the newest release has no assets yet
+    for release in releases {
+        if let Ok(asset) = release.select_tool_asset(name, spec).cloned() {
+            debug!("{} {} release has no suitable asset. Using {} release instead.",
+                release.project.full_name(), preferred.version, release.version);
+            return (release, Ok(asset));
+        }
+    }
+
+    (preferred, preferred_asset)
+}
+
+// Returns project name which is configured for the project's host
+pub fn format_project_name(project: &Project, name: &str) -> String {
+    match project.host.as_ref() {
+        Some(host) => format!("{host}/{name}"),
+        None => name.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProvider;
+
+    impl ReleaseProvider for StaticProvider {
+        fn get_releases(
+            &self, project: &Project, _tag: Option<&str>, _strategy: ReleaseStrategy, _max_pages: usize,
+        ) -> GenericResult<Vec<Release>> {
+            Ok(vec![Release::new(project.clone(), "v1.2.3", Vec::new())])
+        }
+
+        fn get_repository(&self, _project: &Project) -> GenericResult<Repository> {
+            Ok(Repository {description: "Static project".to_owned(), archived: true})
+        }
+
+        fn get_changelog(&self, project: &Project) -> Option<Url> {
+            Url::parse(&format!("https://example.com/{}/changes", project.name)).ok()
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut providers = Providers::new(
+            &GithubConfig::default(), &BitbucketConfig::default(), &S3Config::default(), &TlsConfig::default(),
+            &NetworkConfig::default(),
+        ).unwrap();
+        providers.register("Example.com", Rc::new(StaticProvider));
+
+        let releases = providers.get_releases(
+            "https://example.com/owner/app", None, ReleaseStrategy::default(), 1).unwrap();
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].project.changelog.as_str(), "https://example.com/app/changes");
+
+        let project = providers.parse_project("bitbucket.org/owner/app").unwrap();
+        assert_eq!(project.changelog.as_str(), "https://bitbucket.org/owner/app/downloads/");
+        assert!(providers.get_forks(&project, 1).is_err());
+
+        let project = providers.parse_project("EXAMPLE.COM/owner/app").unwrap();
+        assert!(providers.get_repository(&project).unwrap().archived);
+
+        let project = providers.parse_project("s3://bucket/tools/app/").unwrap();
+        assert_eq!(project.full_name(), "s3://bucket/tools/app");
+        assert!(!providers.get_repository(&project).unwrap().archived);
+        assert!(providers.get_forks(&project, 1).is_err());
+    }
+}
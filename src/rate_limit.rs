@@ -0,0 +1,223 @@
+use std::future::{self, Future};
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+
+use http::{Request, Response, StatusCode};
+use log::{debug, warn};
+use tower_layer::Layer;
+use tower_service::Service;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const MAX_RETRIES: usize = 3;
+
+// Primary rate limit is reset hourly, so there is no sense to wait for it
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+// GitHub recommends to not make requests faster than this after hitting secondary rate limits
+const THROTTLED_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+// Honors Retry-After and rate limit reset headers of throttled GitHub API responses by retrying the requests after the
+// specified delay. All requests made through the layer share the pacing state, so once a rate limit is hit, the
+// following requests are spread out instead of failing one by one. When the primary rate limit is exhausted, the
+// following requests fail immediately until it's reset.
+#[derive(Clone, Default)]
+pub struct RateLimitLayer {
+    state: Arc<Mutex<PacingState>>,
+}
+
+#[derive(Default)]
+struct PacingState {
+    interval: Duration,
+    next_request: Option<Instant>,
+    exhausted_until: Option<SystemTime>,
+}
+
+impl PacingState {
+    // Schedules the next request after the specified delay or, if it's too long to wait, marks the rate limit as
+    // exhausted. Returns false in the latter case.
+    fn throttle(&mut self, delay: Duration, now: Instant, system_now: SystemTime) -> bool {
+        if delay > MAX_WAIT {
+            let until = system_now + delay;
+            self.exhausted_until = Some(self.exhausted_until.map_or(until, |time| time.max(until)));
+            return false;
+        }
+
+        let next_request = now + delay;
+        self.interval = THROTTLED_REQUEST_INTERVAL;
+        self.next_request = Some(self.next_request.map_or(next_request, |time| time.max(next_request)));
+        true
+    }
+
+    fn check_exhausted(&self, now: SystemTime) -> Result<(), String> {
+        match self.exhausted_until.and_then(|until| until.duration_since(now).ok()).filter(|delay| !delay.is_zero()) {
+            Some(delay) => Err(format!(
+                "GitHub API rate limit is exceeded. It will be reset in {} minutes",
+                delay.as_secs().div_ceil(60))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> RateLimitService<S> {
+        RateLimitService {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    state: Arc<Mutex<PacingState>>,
+}
+
+impl<S, R, B> Service<Request<R>> for RateLimitService<S>
+    where
+        S: Service<Request<R>, Response = Response<B>> + Clone + Send + 'static,
+        S::Future: Send,
+        S::Error: Into<BoxError>,
+        R: Clone + Send + 'static,
+        B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The inner service readiness is checked on each (re)try
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut request: Request<R>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+
+        Box::pin(async move {
+            let mut retries = 0;
+
+            loop {
+                // There is no sense to send requests which are known to be rejected
+                state.lock().unwrap().check_exhausted(SystemTime::now())?;
+                wait_for_turn(&state).await;
+
+                let retry_request = clone_request(&request);
+                future::poll_fn(|cx| inner.poll_ready(cx)).await.map_err(Into::into)?;
+                let response = inner.call(request).await.map_err(Into::into)?;
+
+                let Some(delay) = get_retry_delay(&response, SystemTime::now()) else {
+                    return Ok(response);
+                };
+
+                if !state.lock().unwrap().throttle(delay, Instant::now(), SystemTime::now()) {
+                    warn!("GitHub API rate limit is exceeded. It will be reset in {} minutes.",
+                        delay.as_secs().div_ceil(60));
+                    return Ok(response);
+                } else if retries >= MAX_RETRIES {
+                    return Ok(response);
+                }
+
+                warn!("GitHub API rate limit is hit. Retrying the request in {} seconds...", delay.as_secs());
+                request = retry_request;
+                retries += 1;
+            }
+        })
+    }
+}
+
+async fn wait_for_turn(state: &Mutex<PacingState>) {
+    let delay = {
+        let mut state = state.lock().unwrap();
+        let now = Instant::now();
+        let start = state.next_request.map_or(now, |time| time.max(now));
+        state.next_request = Some(start + state.interval);
+        start - now
+    };
+
+    if !delay.is_zero() {
+        debug!("Waiting {:.1}s before the next GitHub API request...", delay.as_secs_f64());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// Request can't be cloned, but its body can
+fn clone_request<R: Clone>(request: &Request<R>) -> Request<R> {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+// See https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api#exceeding-the-rate-limit
+fn get_retry_delay<B>(response: &Response<B>, now: SystemTime) -> Option<Duration> {
+    if !matches!(response.status(), StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) {
+        return None;
+    }
+
+    let get_header = |name: &str| -> Option<u64> {
+        response.headers().get(name)?.to_str().ok()?.parse().ok()
+    };
+
+    if let Some(seconds) = get_header("retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    if get_header("x-ratelimit-remaining") == Some(0) {
+        let reset = SystemTime::UNIX_EPOCH + Duration::from_secs(get_header("x-ratelimit-reset")?);
+        return Some(reset.duration_since(now).unwrap_or_default() + Duration::from_secs(1));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(status, headers, expected,
+        case(200, &[("retry-after", "10")], None),
+        case(403, &[], None),
+        case(403, &[("x-ratelimit-remaining", "10"), ("x-ratelimit-reset", "1030")], None),
+        case(403, &[("retry-after", "10")], Some(10)),
+        case(429, &[("retry-after", "5"), ("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1030")], Some(5)),
+        case(403, &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1030")], Some(31)),
+        case(403, &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "900")], Some(1)),
+    )]
+    fn retry_delay(status: u16, headers: &[(&str, &str)], expected: Option<u64>) {
+        let mut response = Response::builder().status(status);
+        for (name, value) in headers {
+            response = response.header(*name, *value);
+        }
+        let response = response.body(()).unwrap();
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        assert_eq!(get_retry_delay(&response, now), expected.map(Duration::from_secs));
+    }
+
+    #[test]
+    fn exhausted_rate_limit() {
+        let mut state = PacingState::default();
+        let (now, system_now) = (Instant::now(), SystemTime::UNIX_EPOCH + Duration::from_secs(1000));
+
+        assert!(state.throttle(Duration::from_secs(10), now, system_now));
+        assert_eq!(state.next_request, Some(now + Duration::from_secs(10)));
+        assert_eq!(state.check_exhausted(system_now), Ok(()));
+
+        assert!(!state.throttle(Duration::from_secs(3000), now, system_now));
+        assert_eq!(state.next_request, Some(now + Duration::from_secs(10)));
+        assert_eq!(state.check_exhausted(system_now).unwrap_err(),
+            "GitHub API rate limit is exceeded. It will be reset in 50 minutes");
+        assert_eq!(state.check_exhausted(system_now + Duration::from_secs(3000)), Ok(()));
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+#[cfg(target_os = "linux")] use std::fs::OpenOptions;
+use std::io;
+#[cfg(target_os = "macos")] use std::ffi::CString;
+#[cfg(target_os = "macos")] use std::os::unix::ffi::OsStrExt;
+#[cfg(target_os = "linux")] use std::os::unix::fs::OpenOptionsExt;
+#[cfg(target_os = "linux")] use std::os::fd::AsRawFd;
+use std::path::Path;
+
+use log::debug;
+
+// Clones the file sharing its data extents with the source (Btrfs and XFS on Linux, APFS on macOS), so the copy takes
+// neither time nor disk space. The destination must not exist.
+#[cfg(target_os = "linux")]
+pub fn clone(source: &Path, destination: &Path, mode: u32) -> io::Result<()> {
+    let source = fs::File::open(source)?;
+    let file = OpenOptions::new()
+        .create_new(true)
+        .mode(mode)
+        .write(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(destination)?;
+
+    if unsafe { libc::ioctl(file.as_raw_fd(), libc::FICLONE as _, source.as_raw_fd()) } != 0 {
+        let err = io::Error::last_os_error();
+        drop(file);
+        let _ = fs::remove_file(destination);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn clone(source: &Path, destination: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let c_path = |path: &Path| CString::new(path.as_os_str().as_bytes()).map_err(|_| io::Error::new(
+        io::ErrorKind::InvalidInput, format!("Invalid path: {path:?}")));
+
+    if unsafe { libc::clonefile(c_path(source)?.as_ptr(), c_path(destination)?.as_ptr(), libc::CLONE_NOFOLLOW) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    fs::set_permissions(destination, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn clone(_source: &Path, _destination: &Path, _mode: u32) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+// Clones the file if the filesystem supports it and copies it otherwise. The destination must not exist.
+pub fn copy(source: &Path, destination: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(source)?.permissions().mode() & 0o7777;
+
+    match clone(source, destination, mode) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Err(err),
+        Err(err) => {
+            debug!("Unable to clone {source:?} to {destination:?}: {err}. Copying it.");
+            fs::copy(source, destination).map(|_| ())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util;
+    use super::*;
+
+    #[test]
+    fn reflink_copy() {
+        let temp_dir = util::TempDir::new().unwrap();
+        let (source, destination) = (temp_dir.path.join("source"), temp_dir.path.join("destination"));
+        fs::write(&source, "contents").unwrap();
+
+        // Falls back to copying on the filesystems without reflink support
+        copy(&source, &destination).unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "contents");
+        assert_eq!(copy(&source, &destination).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    }
+}
@@ -1,8 +1,11 @@
 use std::env::consts;
 #[cfg(test)] use std::ops::Deref;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
 use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use log::warn;
 use platforms::{Arch, OS};
 use regex::{self, Regex};
 use url::Url;
@@ -10,13 +13,24 @@ use url::Url;
 use crate::core::GenericResult;
 use crate::matcher::Matcher;
 use crate::project::Project;
+use crate::tool::ToolSpec;
 use crate::util;
 use crate::version::ReleaseVersion;
 
+static PREFER_STATIC: OnceLock<bool> = OnceLock::new();
+
+// Makes automatic asset selection prefer statically linked (static or musl) assets when a release has several builds for
+// the current platform
+pub fn set_static_preference(prefer: bool) {
+    let _ = PREFER_STATIC.set(prefer);
+}
+
 pub struct Release {
     pub project: Project,
     pub version: ReleaseVersion,
     pub assets: Vec<Asset>,
+    // Release description (usually Markdown) if the source provides it
+    pub notes: Option<String>,
 }
 
 impl Release {
@@ -25,10 +39,28 @@ impl Release {
             project,
             version: ReleaseVersion::new(tag),
             assets,
+            notes: None,
         }
     }
 
-    pub fn select_asset(&self, binary_name: &str, matcher: Option<&Matcher>) -> GenericResult<&Asset> {
+    pub fn with_notes(mut self, notes: Option<String>) -> Release {
+        self.notes = notes.filter(|notes| !notes.trim().is_empty());
+        self
+    }
+
+    pub fn select_tool_asset(&self, name: &str, spec: &ToolSpec) -> GenericResult<&Asset> {
+        // Workflow artifact and URL source are represented as a release with a single asset
+        if spec.artifact.is_some() || spec.url.is_some() {
+            if let [asset] = self.assets.as_slice() {
+                return Ok(asset);
+            }
+        }
+        self.select_asset(name, spec.release_matcher.as_ref(), spec.variant.as_deref(), spec.rosetta_fallback)
+    }
+
+    pub fn select_asset(
+        &self, binary_name: &str, matcher: Option<&Matcher>, variant: Option<&str>, rosetta_fallback: bool,
+    ) -> GenericResult<&Asset> {
         if self.assets.is_empty() {
             return Err!("The latest release of {project} ({version}) has no assets",
                 project=self.project.full_name(), version=self.version);
@@ -54,38 +86,115 @@ impl Release {
             });
         }
 
+        let prefer_static = PREFER_STATIC.get().copied().unwrap_or_default();
+
+        if let Some(asset) = self.select_platform_asset(
+            binary_name, consts::OS, consts::ARCH, variant, prefer_static, rosetta_fallback,
+        ) {
+            return Ok(asset);
+        }
+
+        Err!(concat!(
+            "Unable to automatically choose the proper release from the following assets:{}\n\n",
+            "Release matcher should be specifed.",
+        ), util::format_list(self.assets.iter().map(|asset| &asset.name)))
+    }
+
+    // Returns the other assets which match the current platform in order of preference. They are used as a fallback
+    // when the selected asset turns out to be broken.
+    pub fn get_alternative_assets(&self, binary_name: &str, spec: &ToolSpec, selected: &Asset) -> Vec<&Asset> {
+        let mut assets: Vec<&Asset> = Vec::new();
+
+        if spec.artifact.is_some() || spec.url.is_some() || spec.release_matcher.is_some() {
+            return assets;
+        }
+
         let matchers = generate_release_matchers(binary_name, &self.project.name, consts::OS, consts::ARCH)
             .unwrap_or_default();
+        let variant_matcher = spec.variant.as_deref().map(get_variant_matcher);
 
         for matcher in matchers {
-            let assets: Vec<_> = self.assets.iter()
+            for asset in &self.assets {
+                if asset.name != selected.name && matcher.matches(&asset.name)
+                    && variant_matcher.as_ref().is_none_or(|variant| variant.is_match(&asset.name))
+                    && !assets.iter().any(|other| other.name == asset.name) {
+                    assets.push(asset);
+                }
+            }
+        }
+
+        assets
+    }
+
+    fn select_platform_asset(
+        &self, binary_name: &str, os: &str, arch: &str, variant: Option<&str>, prefer_static: bool,
+        rosetta_fallback: bool,
+    ) -> Option<&Asset> {
+        if let Some(asset) = self.select_asset_for(binary_name, os, arch, variant, prefer_static) {
+            return Some(asset);
+        }
+
+        if rosetta_fallback && is_apple_silicon(os, arch) {
+            if let Some(asset) = self.select_asset_for(
+                binary_name, OS::MacOS.as_str(), Arch::X86_64.as_str(), variant, prefer_static,
+            ) {
+                warn!(concat!(
+                    "There is no arm64 asset in the latest release of {}. ",
+                    "Falling back to x86_64 one which will be run under Rosetta: {}."
+                ), self.project.full_name(), asset.name);
+                return Some(asset);
+            }
+        }
+
+        None
+    }
+
+    fn select_asset_for(
+        &self, binary_name: &str, os: &str, arch: &str, variant: Option<&str>, prefer_static: bool,
+    ) -> Option<&Asset> {
+        let matchers = generate_release_matchers(binary_name, &self.project.name, os, arch)
+            .unwrap_or_default();
+
+        let static_matcher = Regex::new(r"[-._](?:static|musl(?:eabi(?:hf)?)?)(?:[-._]|$)").unwrap();
+        let variant_matcher = variant.map(get_variant_matcher);
+
+        for matcher in matchers {
+            let mut assets: Vec<_> = self.assets.iter()
                 .filter(|asset| matcher.matches(&asset.name))
+                .filter(|asset| variant_matcher.as_ref().is_none_or(|variant| variant.is_match(&asset.name)))
                 .collect();
 
+            if assets.len() > 1 && prefer_static {
+                assets.retain(|asset| static_matcher.is_match(&asset.name));
+            }
+
             if assets.len() == 1 {
-                return Ok(assets[0]);
+                return Some(assets[0]);
             }
         }
 
-        Err!(concat!(
-            "Unable to automatically choose the proper release from the following assets:{}\n\n",
-            "Release matcher should be specifed.",
-        ), util::format_list(self.assets.iter().map(|asset| &asset.name)))
+        None
     }
 }
 
+#[derive(Clone)]
 pub struct Asset {
     pub name: String,
     pub time: DateTime<Utc>,
     pub url: Url,
 }
 
+fn is_apple_silicon(os: &str, arch: &str) -> bool {
+    matches!((OS::from_str(os), Arch::from_str(arch)), (Ok(OS::MacOS), Ok(Arch::AArch64)))
+}
+
 fn generate_release_matchers(binary_name: &str, project_name: &str, os: &str, arch: &str) -> Option<Vec<Matcher>> {
     let os = OS::from_str(os).ok()?;
     let arch = Arch::from_str(arch).ok()?;
 
     let os_regex = match os {
-        OS::Linux => "linux",
+        // Rust target triples are like x86_64-unknown-linux-musl
+        OS::Linux => "(?:unknown-linux|linux)",
         OS::MacOS => "(?:apple-darwin|darwin|macos)",
         _ => return None,
     };
@@ -96,21 +205,58 @@ fn generate_release_matchers(binary_name: &str, project_name: &str, os: &str, ar
         _ => return None,
     };
 
+    let mut arch_regexes = vec![arch_regex];
+    if os == OS::MacOS {
+        // Universal (fat) binaries contain code for all architectures, so use them as a fallback
+        arch_regexes.push("(?:universal2?|all)");
+    }
+
     let separator_regex = "[-._]";
     let any_fields_regex = format!("(?:{separator_regex}[^/]+)?");
 
-    let platform_regex = format!("(?:{os_regex}[-_]{arch_regex}|{arch_regex}[-_]{os_regex})");
-    let basic_regex = format!(
-        r"{separator_regex}{platform_regex}{any_fields_regex}\.tar\.[^/.]+$",
-    );
+    // Raw (unarchived) binaries have no extension, so their fields can't contain dots to not match checksums,
+    // signatures and archives of unsupported types
+    let raw_fields_regex = "(?:[-_][^/.]+)?";
+
+    let mut basic_regexes = Vec::new();
+
+    // Archives are preferred over Linux packages which are preferred over raw binaries
+    for arch_regex in &arch_regexes {
+        let platform_regex = format!("(?:{os_regex}[-_]{arch_regex}|{arch_regex}[-_]{os_regex})");
+        basic_regexes.push(format!(r"{separator_regex}{platform_regex}{any_fields_regex}\.tar\.[^/.]+$"));
+    }
+
+    // Linux packages are usually named as name_version_arch.deb and name-version-release.arch.rpm without OS
+    if os == OS::Linux {
+        for (extension, arch_regex) in ["deb", "rpm"].into_iter().cartesian_product(&arch_regexes) {
+            basic_regexes.push(format!(
+                r"{separator_regex}(?:{os_regex}[-_])?{arch_regex}{any_fields_regex}\.{extension}$"));
+        }
+    }
+
+    // The same applies to macOS disk images and installer packages (like Tool-1.0.0-arm64.dmg)
+    if os == OS::MacOS {
+        for (extension, arch_regex) in ["dmg", "pkg"].into_iter().cartesian_product(&arch_regexes) {
+            basic_regexes.push(format!(
+                r"{separator_regex}(?:{os_regex}[-_])?{arch_regex}{any_fields_regex}\.{extension}$"));
+        }
+    }
+
+    // Compressed raw binaries (like tool-linux-amd64.gz) are preferred over uncompressed ones
+    for (suffix, arch_regex) in [r"\.(?:gz|xz|bz2)", ""].into_iter().cartesian_product(&arch_regexes) {
+        let platform_regex = format!("(?:{os_regex}[-_]{arch_regex}|{arch_regex}[-_]{os_regex})");
+        basic_regexes.push(format!("{separator_regex}{platform_regex}{raw_fields_regex}{suffix}$"));
+    }
 
     let mut matchers = Vec::new();
 
-    for name in [binary_name, project_name] {
-        let name_regex = get_name_matcher(name);
-        matchers.push(Regex::new(&format!("^{name_regex}{any_fields_regex}{basic_regex}")).unwrap());
+    for basic_regex in basic_regexes {
+        for name in [binary_name, project_name] {
+            let name_regex = get_name_matcher(name);
+            matchers.push(Regex::new(&format!("^{name_regex}{any_fields_regex}{basic_regex}")).unwrap());
+        }
+        matchers.push(Regex::new(&basic_regex).unwrap());
     }
-    matchers.push(Regex::new(&basic_regex).unwrap());
 
     Some(matchers.into_iter().map(Matcher::Regex).collect())
 }
@@ -132,6 +278,20 @@ fn generate_binary_matcher_inner(binary_name: &str, project_name: &str) -> Match
     Matcher::Regex(Regex::new(&format!("(?:^|/){matcher}$")).unwrap())
 }
 
+// Executables in these paths are most likely not the tool binary, so they are ignored by automatic binary matcher
+pub fn default_binary_excludes() -> Vec<Matcher> {
+    [
+        "**/{completion,completions,contrib,doc,docs,example,examples,test,tests,testdata}/**",
+        "**/*.sh",
+    ].into_iter().map(|pattern| Matcher::new(pattern).unwrap()).collect()
+}
+
+// Variant builds (like tool-full-linux-amd64.tar.gz or tool-x86_64-unknown-linux-gnu.tar.xz) have the variant as a
+// separate field of the asset name
+fn get_variant_matcher(variant: &str) -> Regex {
+    Regex::new(&format!(r"[-._]{}(?:[-._]|$)", regex::escape(variant))).unwrap()
+}
+
 fn get_name_matcher(name: &str) -> String {
     let hyphen_name = name.replace('_', "-");
     let underscore_name = hyphen_name.replace('-', "_");
@@ -274,6 +434,79 @@ mod tests {
             (OS::MacOS, Arch::AArch64, "node_exporter-1.8.2.darwin-arm64.tar.gz"),
         ], 1),
 
+        case("tool", "tool", &[
+            "tool-darwin-universal.tar.gz",
+            "tool-linux-amd64.tar.gz",
+            "tool-linux-arm64.tar.gz",
+        ], &[
+            (OS::MacOS, Arch::X86_64, "tool-darwin-universal.tar.gz"),
+            (OS::MacOS, Arch::AArch64, "tool-darwin-universal.tar.gz"),
+        ], 3),
+
+        case("tool", "tool", &[
+            "checksums.txt",
+            "tool-darwin-amd64",
+            "tool-darwin-arm64",
+            "tool-linux-amd64",
+            "tool-linux-amd64.sha256",
+            "tool-linux-arm64",
+            "tool-linux-arm64.sig",
+        ], &[
+            (OS::Linux, Arch::X86_64, "tool-linux-amd64"),
+            (OS::Linux, Arch::AArch64, "tool-linux-arm64"),
+        ], 12),
+
+        case("tool", "tool", &[
+            "checksums.txt",
+            "tool-linux-amd64",
+            "tool-linux-amd64.gz",
+            "tool-linux-amd64.gz.sha256",
+            "tool-linux-arm64.xz",
+        ], &[
+            (OS::Linux, Arch::X86_64, "tool-linux-amd64.gz"),
+            (OS::Linux, Arch::AArch64, "tool-linux-arm64.xz"),
+        ], 9),
+
+        case("tool", "tool", &[
+            "checksums.txt",
+            "tool_1.0.0_amd64.deb",
+            "tool_1.0.0_amd64.deb.sig",
+            "tool_1.0.0_arm64.deb",
+            "tool-1.0.0-1.x86_64.rpm",
+        ], &[
+            (OS::Linux, Arch::X86_64, "tool_1.0.0_amd64.deb"),
+            (OS::Linux, Arch::AArch64, "tool_1.0.0_arm64.deb"),
+        ], 3),
+
+        case("tool", "tool", &[
+            "checksums.txt",
+            "tool-1.0.0-1.aarch64.rpm",
+            "tool-1.0.0-1.src.rpm",
+            "tool-1.0.0-1.x86_64.rpm",
+        ], &[
+            (OS::Linux, Arch::X86_64, "tool-1.0.0-1.x86_64.rpm"),
+            (OS::Linux, Arch::AArch64, "tool-1.0.0-1.aarch64.rpm"),
+        ], 6),
+
+        case("tool", "tool", &[
+            "checksums.txt",
+            "Tool-1.0.0-arm64.dmg",
+            "Tool-1.0.0-x86_64.dmg",
+            "Tool-1.0.0-x86_64.dmg.sha256",
+            "tool_1.0.0_amd64.deb",
+        ], &[
+            (OS::MacOS, Arch::X86_64, "Tool-1.0.0-x86_64.dmg"),
+            (OS::MacOS, Arch::AArch64, "Tool-1.0.0-arm64.dmg"),
+        ], 8),
+
+        case("tool", "tool", &[
+            "checksums.txt",
+            "tool-1.0.0-universal.pkg",
+        ], &[
+            (OS::MacOS, Arch::X86_64, "tool-1.0.0-universal.pkg"),
+            (OS::MacOS, Arch::AArch64, "tool-1.0.0-universal.pkg"),
+        ], 15),
+
         case("ssservice", "shadowsocks-rust", &[
             "shadowsocks-v1.20.3.aarch64-apple-darwin.tar.xz",
             "shadowsocks-v1.20.3.aarch64-apple-darwin.tar.xz.sha256",
@@ -337,6 +570,100 @@ mod tests {
         }
     }
 
+    #[rstest(variant, expected,
+        case(None, None),
+        case(Some("full"), Some("tool-full-linux-amd64.tar.gz")),
+        case(Some("lite"), Some("tool-linux-amd64-lite.tar.gz")),
+        case(Some("gnu"), Some("tool-x86_64-unknown-linux-gnu.tar.gz")),
+        case(Some("pgo"), None),
+    )]
+    fn variant_assets(variant: Option<&str>, expected: Option<&str>) {
+        let release = Release::new(Project {
+            host: None,
+            owner: "owner".to_owned(),
+            name: "tool".to_owned(),
+            changelog: Url::parse("https://github.com/owner/tool/releases").unwrap(),
+        }, "v1.0.0", [
+            "tool-full-linux-amd64.tar.gz",
+            "tool-linux-amd64-lite.tar.gz",
+            "tool-x86_64-unknown-linux-gnu.tar.gz",
+            "tool-x86_64-unknown-linux-musl.tar.gz",
+            "tool-fullstack-linux-arm64.tar.gz",
+        ].into_iter().map(|name| Asset {
+            name: name.to_owned(),
+            time: Utc::now(),
+            url: Url::parse(&format!("https://github.com/owner/tool/releases/download/v1.0.0/{name}")).unwrap(),
+        }).collect());
+
+        let asset = release.select_asset_for("tool", OS::Linux.as_str(), Arch::X86_64.as_str(), variant, false);
+        assert_eq!(asset.map(|asset| asset.name.as_str()), expected);
+    }
+
+    #[rstest(assets, dynamic, static_,
+        case(&[
+            "shadowsocks-v1.20.3.x86_64-unknown-linux-gnu.tar.xz",
+            "shadowsocks-v1.20.3.x86_64-unknown-linux-gnu.tar.xz.sha256",
+            "shadowsocks-v1.20.3.x86_64-unknown-linux-musl.tar.xz",
+            "shadowsocks-v1.20.3.x86_64-unknown-linux-musl.tar.xz.sha256",
+        ], None, Some("shadowsocks-v1.20.3.x86_64-unknown-linux-musl.tar.xz")),
+        case(&[
+            "tool-linux-amd64.tar.gz",
+            "tool-linux-amd64-static.tar.gz",
+        ], None, Some("tool-linux-amd64-static.tar.gz")),
+        case(&[
+            "tool-linux-amd64.tar.gz",
+            "tool-linux-arm64.tar.gz",
+        ], Some("tool-linux-amd64.tar.gz"), Some("tool-linux-amd64.tar.gz")),
+    )]
+    fn static_assets(assets: &[&str], dynamic: Option<&str>, static_: Option<&str>) {
+        let release = Release::new(Project {
+            host: None,
+            owner: "owner".to_owned(),
+            name: "tool".to_owned(),
+            changelog: Url::parse("https://github.com/owner/tool/releases").unwrap(),
+        }, "v1.0.0", assets.iter().map(|name| Asset {
+            name: name.to_string(),
+            time: Utc::now(),
+            url: Url::parse(&format!("https://github.com/owner/tool/releases/download/v1.0.0/{name}")).unwrap(),
+        }).collect());
+
+        for (prefer_static, expected) in [(false, dynamic), (true, static_)] {
+            let asset = release.select_asset_for("tool", OS::Linux.as_str(), Arch::X86_64.as_str(), None, prefer_static);
+            assert_eq!(asset.map(|asset| asset.name.as_str()), expected);
+        }
+    }
+
+    #[rstest(assets, rosetta_fallback, expected,
+        case(&[
+            "tool-darwin-amd64.tar.gz",
+            "tool-linux-arm64.tar.gz",
+        ], true, Some("tool-darwin-amd64.tar.gz")),
+        case(&[
+            "tool-darwin-amd64.tar.gz",
+            "tool-linux-arm64.tar.gz",
+        ], false, None),
+        case(&[
+            "tool-darwin-amd64.tar.gz",
+            "tool-darwin-arm64.tar.gz",
+        ], true, Some("tool-darwin-arm64.tar.gz")),
+    )]
+    fn rosetta_assets(assets: &[&str], rosetta_fallback: bool, expected: Option<&str>) {
+        let release = Release::new(Project {
+            host: None,
+            owner: "owner".to_owned(),
+            name: "tool".to_owned(),
+            changelog: Url::parse("https://github.com/owner/tool/releases").unwrap(),
+        }, "v1.0.0", assets.iter().map(|name| Asset {
+            name: name.to_string(),
+            time: Utc::now(),
+            url: Url::parse(&format!("https://github.com/owner/tool/releases/download/v1.0.0/{name}")).unwrap(),
+        }).collect());
+
+        let asset = release.select_platform_asset(
+            "tool", OS::MacOS.as_str(), Arch::AArch64.as_str(), None, false, rosetta_fallback);
+        assert_eq!(asset.map(|asset| asset.name.as_str()), expected);
+    }
+
     #[rstest(binary_name, project_name, file,
         case("tool", "tool", "tool"),
 
@@ -360,4 +687,19 @@ mod tests {
         let matcher = generate_binary_matcher_inner(binary_name, project_name);
         assert!(matcher.matches(file), "{matcher} vs {file}");
     }
+
+    #[rstest(file, excluded,
+        case("tool", false),
+        case("bin/tool", false),
+        case("tool-1.0/tool", false),
+        case("install.sh", true),
+        case("tool-1.0/scripts/install.sh", true),
+        case("docs/generate", true),
+        case("tool-1.0/examples/run", true),
+        case("tool-1.0/completions/tool.bash", true),
+    )]
+    fn binary_excludes(file: &str, excluded: bool) {
+        let excludes = default_binary_excludes();
+        assert_eq!(excludes.iter().any(|matcher| matcher.matches(file)), excluded);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,164 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::mem;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use chrono::Local;
+use log::debug;
+
+use crate::config::Config;
+use crate::core::{GenericError, GenericResult};
+use crate::list::{self, Status, ToolInfo};
+use crate::porcelain::Porcelain;
+use crate::state::State;
+
+// Generates a human-readable report about the configured tools which may be pasted into a ticket or sent by email.
+// In new-only mode the report is generated only if there are upgrades which haven't been reported yet, so when it's
+// run from cron, there is one email per new release instead of one per each run.
+pub fn report(config: &Config, format: ReportFormat, probe: bool, new_only: bool) -> GenericResult<ExitCode> {
+    let tools = list::collect(config, probe, false, None, None)?;
+
+    let mut state = None;
+    if new_only {
+        let mut current = State::load(config)?;
+
+        let pending = tools.iter()
+            .filter(|tool| tool.status == Status::Outdated)
+            .map(|tool| (tool.name.clone(), tool.latest.clone()))
+            .collect();
+
+        let reported = mem::replace(&mut current.reported, pending);
+        if current.reported.iter().all(|(name, latest)| reported.get(name) == Some(latest)) {
+            debug!("There are no new upgrades to report.");
+            if current.reported != reported {
+                current.save()?;
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        state.replace(current);
+    }
+    let time = Local::now().format("%Y-%m-%d %H:%M").to_string();
+
+    let report = match format {
+        ReportFormat::Markdown => format_markdown(&tools, &time),
+        ReportFormat::Html => format_html(&tools, &time),
+        ReportFormat::Porcelain(version) => list::format_porcelain(&tools, version),
+    };
+
+    io::stdout().write_all(report.as_bytes()).map_err(|e| format!(
+        "Failed to write the report: {e}"))?;
+
+    if let Some(state) = state {
+        state.save()?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+    // Set by --porcelain option
+    Porcelain(Porcelain),
+}
+
+impl ReportFormat {
+    pub const VALUES: [&'static str; 2] = ["markdown", "html"];
+}
+
+impl FromStr for ReportFormat {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<ReportFormat> {
+        Ok(match value {
+            "markdown" => ReportFormat::Markdown,
+            "html" => ReportFormat::Html,
+            _ => return Err!("Invalid report format: {value:?}"),
+        })
+    }
+}
+
+fn format_markdown(tools: &[ToolInfo], time: &str) -> String {
+    let mut report = format!("# binup report\n\nGenerated at {time}.\n\n## Pending upgrades\n\n");
+
+    let outdated: Vec<&ToolInfo> = tools.iter().filter(|tool| tool.status == Status::Outdated).collect();
+    if outdated.is_empty() {
+        report.push_str("There are no pending upgrades.\n");
+    }
+    for tool in outdated {
+        let _ = writeln!(report, "* **{}**: {} → {} ([changelog]({}))",
+            escape_markdown(&tool.name), escape_markdown(&tool.installed), escape_markdown(&tool.latest), tool.changelog);
+    }
+
+    report.push_str("\n## Tools\n\n| Tool | Installed | Latest | Status | Changelog |\n|---|---|---|---|---|\n");
+    for tool in tools {
+        let changelog = if tool.changelog.is_empty() {
+            String::new()
+        } else {
+            format!("[link]({})", tool.changelog)
+        };
+
+        let _ = writeln!(report, "| {} | {} | {} | {} | {} |",
+            escape_markdown(&tool.name), escape_markdown(&tool.installed), escape_markdown(&tool.latest),
+            tool.status, changelog);
+    }
+
+    report
+}
+
+fn format_html(tools: &[ToolInfo], time: &str) -> String {
+    let mut report = format!(
+        "<html>\n<body>\n<h1>binup report</h1>\n<p>Generated at {time}.</p>\n<h2>Pending upgrades</h2>\n");
+
+    let outdated: Vec<&ToolInfo> = tools.iter().filter(|tool| tool.status == Status::Outdated).collect();
+    if outdated.is_empty() {
+        report.push_str("<p>There are no pending upgrades.</p>\n");
+    } else {
+        report.push_str("<ul>\n");
+        for tool in outdated {
+            let _ = writeln!(report, r#"<li><b>{}</b>: {} → {} (<a href="{}">changelog</a>)</li>"#,
+                escape_html(&tool.name), escape_html(&tool.installed), escape_html(&tool.latest),
+                escape_html(&tool.changelog));
+        }
+        report.push_str("</ul>\n");
+    }
+
+    report.push_str(concat!(
+        "<h2>Tools</h2>\n<table>\n",
+        "<tr><th>Tool</th><th>Installed</th><th>Latest</th><th>Status</th><th>Changelog</th></tr>\n"));
+
+    for tool in tools {
+        let changelog = if tool.changelog.is_empty() {
+            String::new()
+        } else {
+            format!(r#"<a href="{}">link</a>"#, escape_html(&tool.changelog))
+        };
+
+        let _ = writeln!(report, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&tool.name), escape_html(&tool.installed), escape_html(&tool.latest), tool.status, changelog);
+    }
+
+    report.push_str("</table>\n</body>\n</html>\n");
+    report
+}
+
+fn escape_markdown(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use log::{error, info, warn};
+
+use crate::core::EmptyResult;
+use crate::i18n;
+use crate::util;
+
+// Installing tools to a directory which is missing in $PATH is a frequent stumbling block for new users, so offer to
+// add it to the shell's rc file or at least tell how to do it
+pub fn check(directories: &[&Path]) {
+    let mut checked: Vec<&Path> = Vec::new();
+
+    for &directory in directories {
+        if checked.contains(&directory) || in_path(directory) {
+            continue;
+        }
+        checked.push(directory);
+
+        if let Err(err) = setup(directory) {
+            error!("Failed to add {directory:?} to $PATH: {err}.");
+        }
+    }
+}
+
+fn in_path(directory: &Path) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+
+    let canonical = fs::canonicalize(directory).ok();
+
+    env::split_paths(&path).any(|path| {
+        path == directory || canonical.is_some() && fs::canonicalize(&path).ok() == canonical
+    })
+}
+
+fn setup(directory: &Path) -> EmptyResult {
+    let directory_str = directory.to_str().ok_or("Unsupported path")?;
+
+    // Keep the line portable if the directory is in the home directory
+    let home = env::var("HOME").ok().filter(|home| !home.is_empty());
+    let shell_directory = match home.as_ref().and_then(|home| directory_str.strip_prefix(home.as_str())) {
+        Some(relative) if relative.starts_with('/') => format!("$HOME{}", relative),
+        _ => directory_str.to_owned(),
+    };
+
+    let Some((rc_path, line)) = get_shell_config(home.as_deref(), &shell_directory) else {
+        warn!("{directory:?} is not in $PATH. Add it to $PATH to be able to run the installed tools.");
+        return Ok(());
+    };
+
+    let contents = match fs::read_to_string(&rc_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+        Err(err) => return Err!("Unable to read {rc_path:?}: {err}"),
+    };
+
+    if contents.lines().any(|existing| existing.trim() == line) {
+        info!("{directory:?} is already added to $PATH in {rc_path:?}. Restart the shell to apply it.");
+        return Ok(());
+    }
+
+    let message = format!("{directory:?} is not in $PATH.");
+    let command = format!("echo {} >> {}", util::shell_quote(&line), util::shell_quote(&rc_path.to_string_lossy()));
+
+    if !util::is_interactive() || !util::confirm(i18n::trf(
+        "{} is not in $PATH. Add it to {}?", &[&format_args!("{directory:?}"), &format_args!("{rc_path:?}")]))? {
+        warn!("{message} To add it, run:\n{command}");
+        return Ok(());
+    }
+
+    append_line(&rc_path, &contents, &line)?;
+    info!("{directory:?} is added to $PATH in {rc_path:?}. Restart the shell to apply it.");
+
+    Ok(())
+}
+
+fn get_shell_config(home: Option<&str>, directory: &str) -> Option<(PathBuf, String)> {
+    let home = Path::new(home?);
+    let shell = env::var("SHELL").ok()?;
+
+    Some(match Path::new(&shell).file_name()?.to_str()? {
+        "bash" => (home.join(".bashrc"), format!(r#"export PATH="{directory}:$PATH""#)),
+        "zsh" => {
+            let config_dir = env::var_os("ZDOTDIR").map(PathBuf::from).unwrap_or_else(|| home.to_owned());
+            (config_dir.join(".zshrc"), format!(r#"export PATH="{directory}:$PATH""#))
+        },
+        "fish" => (home.join(".config/fish/config.fish"), format!(r#"fish_add_path "{directory}""#)),
+        _ => return None,
+    })
+}
+
+fn append_line(path: &Path, contents: &str, line: &str) -> EmptyResult {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Unable to create {parent:?}: {e}"))?;
+    }
+
+    let separator = if contents.is_empty() || contents.ends_with('\n') { "" } else { "\n" };
+
+    OpenOptions::new().create(true).append(true).open(path)
+        .and_then(|mut file| writeln!(file, "{separator}{line}"))
+        .map_err(|e| format!("Unable to write to {path:?}: {e}"))?;
+
+    Ok(())
+}
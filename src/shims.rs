@@ -0,0 +1,348 @@
+use std::env;
+use std::fs::{self, File, Permissions};
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use log::{debug, info, warn};
+use toml_edit::DocumentMut;
+
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::install::{self, Mode};
+use crate::provider::Providers;
+use crate::reflink;
+use crate::state::State;
+use crate::timings::Timings;
+use crate::tool::Layout;
+use crate::util;
+
+pub const VERSION_FILE_NAME: &str = ".binup-version";
+pub const PROJECT_FILE_NAME: &str = "binup.toml";
+
+const SHIM_HEADER: &str = "#!/bin/sh\n# binup shim\n";
+
+pub struct Pin {
+    pub tool: String,
+    pub version: String,
+    pub path: PathBuf,
+}
+
+pub struct Shim {
+    pub path: PathBuf,
+    pub versions_path: PathBuf,
+}
+
+impl Shim {
+    // Keeps a copy of the just installed binary under its version directory, so it can be pinned later, and points the
+    // command to the shim.
+    pub fn install(&self, name: &str, version: &str, binary: &Path) -> EmptyResult {
+        let version_path = self.versions_path.join(version);
+        let versioned_binary = version_path.join(name);
+
+        fs::create_dir_all(&version_path).map_err(|e| format!("Unable to create {version_path:?}: {e}"))?;
+        if let Err(err) = fs::remove_file(&versioned_binary) {
+            if err.kind() != ErrorKind::NotFound {
+                return Err!("Unable to delete {versioned_binary:?}: {err}");
+            }
+        }
+
+        debug!("Linking {binary:?} to {versioned_binary:?}...");
+        fs::hard_link(binary, &versioned_binary)
+            .or_else(|_| reflink::copy(binary, &versioned_binary))
+            .map_err(|e| format!("Unable to copy {binary:?} to {versioned_binary:?}: {e}"))?;
+
+        let script = render(name, &self.versions_path)?;
+        if fs::read(&self.path).is_ok_and(|data| data == script.as_bytes()) {
+            return Ok(());
+        }
+
+        debug!("Installing {name} shim to {:?}...", self.path);
+        if let Some(path) = self.path.parent() {
+            fs::create_dir_all(path).map_err(|e| format!("Unable to create {path:?}: {e}"))?;
+        }
+        util::write_file(&self.path, script.as_bytes())?;
+        fs::set_permissions(&self.path, Permissions::from_mode(0o755)).map_err(|e| format!(
+            "Unable to change {:?} permissions: {e}", self.path))?;
+
+        Ok(())
+    }
+}
+
+pub fn is_shim(path: &Path) -> GenericResult<bool> {
+    let mut header = Vec::with_capacity(SHIM_HEADER.len());
+
+    match File::open(path).and_then(|file| file.take(SHIM_HEADER.len() as u64).read_to_end(&mut header)) {
+        Ok(_) => Ok(header == SHIM_HEADER.as_bytes()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+        Err(err) => Err!("Unable to read {path:?}: {err}"),
+    }
+}
+
+// The shim is run on each tool invocation, so it's a plain shell script which doesn't depend on binup
+fn render(name: &str, versions_path: &Path) -> GenericResult<String> {
+    let versions_path = versions_path.to_str().ok_or_else(|| format!("Invalid path: {versions_path:?}"))?;
+
+    // Only `name = "version"` lines of [tools] table are supported in binup.toml, which is enough for the version pins
+    Ok(format!(indoc::indoc!(r#"
+        {header}
+        name={name}
+        versions={versions}
+
+        find_version() {{
+            version=
+            [ -f "$1" ] || return 1
+
+            case "$1" in
+                *.toml)
+                    section=
+                    while IFS= read -r line || [ -n "$line" ]; do
+                        line="${{line%%#*}}"
+                        case "$line" in
+                            *=*)
+                                [ "$section" = "[tools]" ] || continue
+                                set -- ${{line%%=*}}
+                                [ "$1" = "$name" ] || [ "$1" = "\"$name\"" ] || continue
+                                set -- ${{line#*=}}
+                                version="${{1#[\"\']}}"
+                                version="${{version%[\"\']}}"
+                                [ -z "$version" ] || return 0
+                                ;;
+                            *)
+                                set -- $line
+                                [ $# -eq 0 ] || section="$1"
+                                ;;
+                        esac
+                    done < "$1"
+                    ;;
+                *)
+                    while read -r tool version _ || [ -n "$tool" ]; do
+                        [ "$tool" != "$name" ] || [ -z "$version" ] || return 0
+                    done < "$1"
+                    version=
+                    ;;
+            esac
+
+            return 1
+        }}
+
+        set -f
+        dir="$PWD"
+        while :; do
+            for file in "$dir/{version_file}" "$dir/{project_file}"; do
+                find_version "$file" || continue
+                if [ ! -x "$versions/$version/$name" ]; then
+                    echo "$name $version is pinned in $file, but isn't installed. Run \`binup shims install\` to install it." >&2
+                    exit 127
+                fi
+                exec "$versions/$version/$name" "$@"
+            done
+
+            [ -n "$dir" ] || break
+            dir="${{dir%/*}}"
+        done
+
+        exec "$versions/$name" "$@"
+    "#), header=SHIM_HEADER.trim_end(), name=util::shell_quote(name), versions=util::shell_quote(versions_path),
+        version_file=VERSION_FILE_NAME, project_file=PROJECT_FILE_NAME))
+}
+
+pub fn install(config: &Config, name: Option<String>, version: Option<String>) -> GenericResult<ExitCode> {
+    if !config.shims {
+        return Err!("Shim mode is disabled. Enable shims option in the configuration file to use it");
+    }
+
+    let (pins, pin_files) = match (name, version) {
+        (Some(name), Some(version)) => (vec![(name, version)], Vec::new()),
+        (name, _) => {
+            let pins: Vec<_> = find_current_pins()?.into_iter()
+                .filter(|pin| name.as_ref().is_none_or(|name| *name == pin.tool))
+                .collect();
+
+            if pins.is_empty() {
+                info!("There are no pinned versions in {VERSION_FILE_NAME} and {PROJECT_FILE_NAME} files.");
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let pin_files = pins.iter().map(|pin| pin.path.clone()).collect();
+            (pins.into_iter().map(|pin| (pin.tool, pin.version)).collect(), pin_files)
+        },
+    };
+
+    // The pinned versions are installed as regular tools, but into their version directories and without touching the
+    // shims and the state.
+    let mut config = config.clone();
+    config.shims = false;
+
+    let providers = Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?;
+    let timings = Timings::new(false);
+
+    for (name, version) in pins {
+        let Some(spec) = config.tools.get(&name) else {
+            warn!("{name} is pinned to {version}, but it's not specified in the configuration file. Skipping it.");
+            continue;
+        };
+
+        if spec.layout == Layout::Tree {
+            return Err!("{name} has tree layout, which isn't supported in shim mode");
+        } else if spec.url.is_some() || spec.artifact.is_some() {
+            return Err!("Unable to install a specific version of {name}: it's not installed from project releases");
+        }
+
+        let mut spec = spec.clone();
+        spec.path = Some(config.get_tool_versions_path(&name).join(&version));
+        spec.hold = None;
+        spec.post = None;
+
+        let tags = if version.starts_with('v') {
+            vec![version.clone()]
+        } else {
+            vec![format!("v{version}"), version.clone()]
+        };
+
+        let mut result = Ok(None);
+
+        for tag in tags {
+            debug!("Trying {name} {tag} release...");
+            spec.tag = Some(tag);
+
+            result = install::install_tool(&config, &name, &spec, &providers, Mode::Install {
+                force: false, recheck_spec: false, reuse_cache: false,
+            }, None, false, &timings);
+
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result.map_err(|e| format!("Failed to install {name} {version}: {e}"))?;
+    }
+
+    // Remember the projects, so `binup gc` doesn't delete the versions they need
+    let mut state = State::load(&config)?;
+    let count = state.pin_files.len();
+    state.pin_files.extend(pin_files);
+    if state.pin_files.len() != count {
+        state.save()?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn find_current_pins() -> GenericResult<Vec<Pin>> {
+    let current_dir = env::current_dir().map_err(|e| format!("Unable to determine current directory: {e}"))?;
+    find_pins(&current_dir)
+}
+
+// Collects the pinned versions from the version files in the specified directory and its parents. The nearest pin wins
+// and .binup-version takes precedence over binup.toml of the same directory.
+fn find_pins(path: &Path) -> GenericResult<Vec<Pin>> {
+    let mut pins: Vec<Pin> = Vec::new();
+
+    for directory in path.ancestors() {
+        for name in [VERSION_FILE_NAME, PROJECT_FILE_NAME] {
+            for pin in read_pins(&directory.join(name))?.unwrap_or_default() {
+                if !pins.iter().any(|other| other.tool == pin.tool) {
+                    pins.push(pin);
+                }
+            }
+        }
+    }
+
+    Ok(pins)
+}
+
+// Reads the pins from .binup-version or binup.toml file. Returns None if the file doesn't exist.
+pub fn read_pins(path: &Path) -> GenericResult<Option<Vec<Pin>>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) => return Ok(None),
+        Err(err) => return Err!("Unable to read {path:?}: {err}"),
+    };
+
+    let pins = if path.file_name().is_some_and(|name| name == PROJECT_FILE_NAME) {
+        parse_project_pins(&contents).map_err(|e| format!("Invalid {path:?}: {e}"))?
+    } else {
+        parse_pins(&contents)
+    };
+
+    Ok(Some(pins.into_iter().map(|(tool, version)| Pin {tool, version, path: path.to_owned()}).collect()))
+}
+
+fn parse_pins(contents: &str) -> Vec<(String, String)> {
+    contents.lines().filter_map(|line| {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(tool), Some(version)) if !tool.starts_with('#') => Some((tool.to_owned(), version.to_owned())),
+            _ => None,
+        }
+    }).collect()
+}
+
+fn parse_project_pins(contents: &str) -> GenericResult<Vec<(String, String)>> {
+    let document: DocumentMut = contents.parse()?;
+
+    let Some(tools) = document.get("tools") else {
+        return Ok(Vec::new());
+    };
+    let tools = tools.as_table().ok_or("tools must be a table")?;
+
+    tools.iter().map(|(tool, version)| {
+        let version = version.as_str().ok_or_else(|| format!("Invalid {tool} version: it must be a string"))?;
+        Ok((tool.to_owned(), version.to_owned()))
+    }).collect()
+}
+
+pub fn uninstall(path: &Path, versions_path: &Path) -> GenericResult<bool> {
+    let deleted = is_shim(path)? && match fs::remove_file(path) {
+        Ok(()) => true,
+        Err(err) if err.kind() == ErrorKind::NotFound => false,
+        Err(err) => return Err!("Unable to delete {path:?}: {err}"),
+    };
+
+    install::remove_tree(versions_path)?;
+
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use super::*;
+
+    #[test]
+    fn pins() {
+        let contents = indoc!("
+            # Project tools
+            ripgrep 14.1.0
+            fd   v10.2.0  # comment
+
+            incomplete
+        ");
+
+        assert_eq!(parse_pins(contents), vec![
+            ("ripgrep".to_owned(), "14.1.0".to_owned()),
+            ("fd".to_owned(), "v10.2.0".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn project_pins() {
+        let contents = indoc!(r#"
+            [package]
+            name = "example"
+
+            [tools]
+            ripgrep = "14.1.0"  # comment
+            "fd" = 'v10.2.0'
+        "#);
+
+        assert_eq!(parse_project_pins(contents).unwrap(), vec![
+            ("ripgrep".to_owned(), "14.1.0".to_owned()),
+            ("fd".to_owned(), "v10.2.0".to_owned()),
+        ]);
+
+        assert!(parse_project_pins("[tools]\nripgrep = 14").is_err());
+    }
+}
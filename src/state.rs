@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::i18n;
+use crate::util;
+
+const STATE_KEY_SIZE: usize = 32;
+
+// Information about installed tools which can't be obtained from the tools themselves
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct State {
+    #[serde(skip)]
+    path: PathBuf,
+
+    #[serde(skip)]
+    key: Option<StateKey>,
+
+    #[serde(default)]
+    pub tools: BTreeMap<String, InstallInfo>,
+
+    // Results of the last install/upgrade attempts
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub runs: BTreeMap<String, RunResult>,
+
+    // Latest versions of outdated tools which have been already reported by `report --new-only`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reported: BTreeMap<String, String>,
+
+    // Version files of the projects which tool versions have been installed by `binup shims install` for
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub pin_files: BTreeSet<PathBuf>,
+
+    // HMAC-SHA256 of the state which protects it from tampering when state key is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct InstallInfo {
+    pub version: String,
+    pub asset: String,
+    pub binary: PathBuf,
+
+    // Asset update time and checksum which identify the cached asset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+
+    // Asset download URL which allows to verify the installed binary against the upstream release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    // Hash of the tool spec the tool has been installed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_hash: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RunResult {
+    pub time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl State {
+    pub fn load(config: &Config) -> GenericResult<State> {
+        let mut state = State::load_unverified(&config.get_state_path())?;
+        let Some(key_path) = config.state_key.as_ref() else {
+            return Ok(state);
+        };
+
+        let path = &state.path;
+        let key = StateKey::load(key_path)?;
+
+        match state.signature.take() {
+            Some(signature) => if !state.verify(&key, &signature)? {
+                return Err!(concat!(
+                    "{:?} signature verification failed: the state file has been modified outside of binup or signed ",
+                    "with another key. Check the installed tools and run `binup state sign` if you trust the state file."
+                ), path);
+            },
+            None => if path.exists() {
+                return Err!(
+                    "{path:?} is not signed. Run `binup state sign` if you trust the state file.");
+            },
+        }
+
+        state.key.replace(key);
+        Ok(state)
+    }
+
+    fn load_unverified(path: &Path) -> GenericResult<State> {
+        let mut state: State = match fs::read(path) {
+            Ok(data) => serde_yaml::from_slice(&data).map_err(|e| format!(
+                "Error while reading {path:?}: {e}"))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => State::default(),
+            Err(err) => return Err!("Unable to read {path:?}: {err}"),
+        };
+
+        state.path = path.to_owned();
+        Ok(state)
+    }
+
+    pub fn record_run(&mut self, name: &str, error: Option<&str>) {
+        self.runs.insert(name.to_owned(), RunResult {
+            time: Utc::now(),
+            error: error.map(ToOwned::to_owned),
+        });
+    }
+
+    pub fn save(&self) -> EmptyResult {
+        if let Some(path) = self.path.parent() {
+            fs::create_dir_all(path).map_err(|e| format!(
+                "Unable to create {path:?}: {e}"))?;
+        }
+
+        let data = match self.key.as_ref() {
+            Some(key) => {
+                let signature = self.sign(key)?;
+                serde_yaml::to_string(&State {
+                    signature: Some(signature),
+                    ..self.clone()
+                })?
+            },
+            None => serde_yaml::to_string(self)?,
+        };
+
+        util::write_file(&self.path, data.as_bytes())
+    }
+
+    // The signature covers the canonical serialization of the state, so any modification of the parsed data breaks it
+    fn sign(&self, key: &StateKey) -> GenericResult<String> {
+        let data = serde_yaml::to_string(&State {
+            signature: None,
+            ..self.clone()
+        })?;
+
+        let key = PKey::hmac(&key.0)?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+        signer.update(data.as_bytes())?;
+
+        Ok(signer.sign_to_vec()?.iter().fold(String::new(), |mut signature, byte| {
+            let _ = write!(signature, "{byte:02x}");
+            signature
+        }))
+    }
+
+    // Compares the signatures in constant time to not leak the expected one via timing
+    fn verify(&self, key: &StateKey, signature: &str) -> GenericResult<bool> {
+        let expected = self.sign(key)?;
+        Ok(signature.len() == expected.len() && memcmp::eq(signature.as_bytes(), expected.as_bytes()))
+    }
+}
+
+// Local secret key which is used to sign the state file
+#[derive(Clone)]
+struct StateKey(Vec<u8>);
+
+impl StateKey {
+    // Generates a new key if it doesn't exist yet
+    fn load(path: &Path) -> GenericResult<StateKey> {
+        match fs::read(path) {
+            Ok(key) if key.len() < STATE_KEY_SIZE => Err!(
+                "Invalid state key {path:?}: it must be at least {STATE_KEY_SIZE} bytes long"),
+            Ok(key) => Ok(StateKey(key)),
+            Err(err) if err.kind() == ErrorKind::NotFound => StateKey::generate(path),
+            Err(err) => Err!("Unable to read {path:?}: {err}"),
+        }
+    }
+
+    fn generate(path: &Path) -> GenericResult<StateKey> {
+        if let Some(path) = path.parent() {
+            fs::create_dir_all(path).map_err(|e| format!(
+                "Unable to create {path:?}: {e}"))?;
+        }
+
+        let mut key = vec![0; STATE_KEY_SIZE];
+        openssl::rand::rand_bytes(&mut key)?;
+
+        OpenOptions::new().create_new(true).write(true).mode(0o600).open(path)
+            .and_then(|mut file| file.write_all(&key))
+            .map_err(|e| format!("Unable to create {path:?}: {e}"))?;
+
+        warn!("Generated a new state signing key: {path:?}.");
+        Ok(StateKey(key))
+    }
+}
+
+// Signs the current state file, so it will be trusted by the following runs
+pub fn sign(config: &Config) -> GenericResult<ExitCode> {
+    let Some(key_path) = config.state_key.as_ref() else {
+        return Err!("State key is not configured");
+    };
+
+    let path = config.get_state_path();
+    if !util::confirm(i18n::trf(
+        "Sign {}? Do it only if you trust its contents: binup relies on it to decide which tools are up-to-date.",
+        &[&format_args!("{path:?}")],
+    ))? {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let mut state = State::load_unverified(&path)?;
+    state.key.replace(StateKey::load(key_path)?);
+    state.save()?;
+
+    info!("{path:?} is signed.");
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature() {
+        let key = StateKey(vec![1; STATE_KEY_SIZE]);
+
+        let mut state = State::default();
+        state.reported.insert("tool".to_owned(), "1.0.0".to_owned());
+        let signature = state.sign(&key).unwrap();
+
+        assert_eq!(state.sign(&key).unwrap(), signature);
+        assert!(state.verify(&key, &signature).unwrap());
+        assert!(!state.verify(&key, &signature[1..]).unwrap());
+        assert!(!state.verify(&StateKey(vec![2; STATE_KEY_SIZE]), &signature).unwrap());
+
+        state.reported.insert("tool".to_owned(), "1.0.1".to_owned());
+        assert!(!state.verify(&key, &signature).unwrap());
+    }
+}
@@ -0,0 +1,139 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use tabled::Table;
+use tabled::builder::Builder;
+use tabled::settings::style::Style;
+
+use crate::i18n;
+
+// Run phases which are measured by --timings. Downloading includes extraction, because the archive is extracted while
+// it's being downloaded.
+#[derive(Clone, Copy)]
+pub enum Phase {
+    Api,
+    Download,
+    Extract,
+    Install,
+    Hooks,
+}
+
+impl Phase {
+    const ALL: [Phase; 5] = [Phase::Api, Phase::Download, Phase::Extract, Phase::Install, Phase::Hooks];
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Api => "API",
+            Phase::Download => "Download",
+            Phase::Extract => "Extract",
+            Phase::Install => "Install",
+            Phase::Hooks => "Hooks",
+        }
+    }
+}
+
+// Name which is used for the phases that aren't related to a specific tool (pre_run and post_upgrade scripts)
+pub const GLOBAL: &str = "(global)";
+
+// Collects per-tool durations of the run phases
+pub struct Timings {
+    enabled: bool,
+    tools: RefCell<Vec<(String, [Duration; Phase::ALL.len()])>>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Timings {
+        Timings {
+            enabled,
+            tools: RefCell::default(),
+        }
+    }
+
+    pub fn measure<T>(&self, tool: &str, phase: Phase, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+
+        let start_time = Instant::now();
+        let result = f();
+        self.add(tool, phase, start_time.elapsed());
+
+        result
+    }
+
+    fn add(&self, tool: &str, phase: Phase, duration: Duration) {
+        let mut tools = self.tools.borrow_mut();
+
+        let index = match tools.iter().position(|(name, _)| name == tool) {
+            Some(index) => index,
+            None => {
+                tools.push((tool.to_owned(), Default::default()));
+                tools.len() - 1
+            },
+        };
+
+        tools[index].1[phase as usize] += duration;
+    }
+
+    pub fn print(&self) {
+        if self.enabled && !self.tools.borrow().is_empty() {
+            println!("{}", self.format());
+        }
+    }
+
+    fn format(&self) -> Table {
+        let tools = self.tools.borrow();
+        let format = |duration: Duration| format!("{:.2}s", duration.as_secs_f64());
+
+        let mut builder = Builder::default();
+        builder.push_record(
+            ["Tool"].into_iter().chain(Phase::ALL.iter().map(|phase| phase.name())).chain(["Total"]).map(i18n::tr));
+
+        let mut totals: [Duration; Phase::ALL.len()] = Default::default();
+
+        for (name, durations) in tools.iter() {
+            for (total, duration) in totals.iter_mut().zip(durations) {
+                *total += *duration;
+            }
+
+            builder.push_record(
+                [name.clone()].into_iter()
+                    .chain(durations.iter().copied().map(format))
+                    .chain([format(durations.iter().sum())]));
+        }
+
+        if tools.len() > 1 {
+            builder.push_record(
+                [i18n::tr("Total").to_owned()].into_iter()
+                    .chain(totals.iter().copied().map(format))
+                    .chain([format(totals.iter().sum())]));
+        }
+
+        let mut table = builder.build();
+        table.with(Style::blank());
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let timings = Timings::new(true);
+        timings.add("first", Phase::Api, Duration::from_millis(250));
+        timings.add("second", Phase::Api, Duration::from_millis(500));
+        timings.add("first", Phase::Download, Duration::from_millis(1500));
+        timings.add("first", Phase::Api, Duration::from_millis(250));
+        timings.add(GLOBAL, Phase::Hooks, Duration::from_secs(2));
+
+        assert_eq!(timings.format().to_string(), concat!(
+            " Tool       API     Download   Extract   Install   Hooks   Total \n",
+            " first      0.50s   1.50s      0.00s     0.00s     0.00s   2.00s \n",
+            " second     0.50s   0.00s      0.00s     0.00s     0.00s   0.50s \n",
+            " (global)   0.00s   0.00s      0.00s     0.00s     2.00s   2.00s \n",
+            " Total      1.00s   1.50s      0.00s     0.00s     2.00s   4.50s ",
+        ));
+    }
+}
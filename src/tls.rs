@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use reqwest::blocking::ClientBuilder;
+use reqwest::{Certificate, Identity};
+use rustls::{ClientConfig, RootCertStore};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+
+use crate::core::GenericResult;
+use crate::util;
+
+// Custom CA bundle (for example, for corporate TLS interception) and client certificate (for mTLS-protected hosts)
+// which are used by both GitHub API and download clients
+#[derive(Clone, Default, Deserialize, Validate, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_tls_config"))]
+pub struct TlsConfig {
+    #[serde(default, deserialize_with = "util::deserialize_optional_path")]
+    pub ca_file: Option<PathBuf>,
+    #[serde(default, deserialize_with = "util::deserialize_optional_path")]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default, deserialize_with = "util::deserialize_optional_path")]
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub fn configure_client(&self, mut builder: ClientBuilder) -> GenericResult<ClientBuilder> {
+        if let Some(path) = self.ca_file.as_ref() {
+            for certificate in Certificate::from_pem_bundle(&read(path)?).map_err(|e| format!(
+                "Invalid CA bundle {path:?}: {e}"))? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let (Some(cert_path), Some(key_path)) = (self.client_cert.as_ref(), self.client_key.as_ref()) {
+            let identity = Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?).map_err(|e| format!(
+                "Invalid client certificate {cert_path:?} or key {key_path:?}: {e}"))?;
+            builder = builder.identity(identity);
+        }
+
+        Ok(builder)
+    }
+
+    // Builds rustls configuration for GitHub API client: system root certificates plus the configured ones
+    pub fn get_rustls_config(&self) -> GenericResult<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+
+        let native = rustls_native_certs::load_native_certs();
+        if native.certs.is_empty() {
+            if let Some(err) = native.errors.first() {
+                return Err!("Unable to load system root certificates: {err}");
+            }
+        }
+        roots.add_parsable_certificates(native.certs);
+
+        if let Some(path) = self.ca_file.as_ref() {
+            for certificate in parse_certificates(path)? {
+                roots.add(certificate).map_err(|e| format!("Invalid CA bundle {path:?}: {e}"))?;
+            }
+        }
+
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+
+        Ok(match (self.client_cert.as_ref(), self.client_key.as_ref()) {
+            (Some(cert_path), Some(key_path)) => {
+                let key = parse_private_key(key_path)?;
+                builder.with_client_auth_cert(parse_certificates(cert_path)?, key).map_err(|e| format!(
+                    "Invalid client certificate {cert_path:?} or key {key_path:?}: {e}"))?
+            },
+            _ => builder.with_no_client_auth(),
+        })
+    }
+}
+
+fn validate_tls_config(config: &TlsConfig) -> Result<(), ValidationError> {
+    if config.client_cert.is_some() != config.client_key.is_some() {
+        return Err(ValidationError::new("client_cert").with_message(
+            "client_cert and client_key must be specified together".into()));
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> GenericResult<Vec<u8>> {
+    Ok(fs::read(path).map_err(|e| format!("Unable to read {path:?}: {e}"))?)
+}
+
+fn parse_certificates(path: &Path) -> GenericResult<Vec<CertificateDer<'static>>> {
+    let certificates = rustls_pemfile::certs(&mut read(path)?.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Unable to parse {path:?}: {e}"))?;
+
+    if certificates.is_empty() {
+        return Err!("{path:?} doesn't contain any certificates");
+    }
+
+    Ok(certificates)
+}
+
+fn parse_private_key(path: &Path) -> GenericResult<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut read(path)?.as_slice())
+        .map_err(|e| format!("Unable to parse {path:?}: {e}"))?
+        .ok_or_else(|| format!("{path:?} doesn't contain a private key").into())
+}
@@ -1,68 +1,625 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::{self, Display, Formatter};
 use std::fs;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::SystemTime;
 
+use http::{HeaderName, HeaderValue};
 use log::debug;
-use nondestructive::yaml::MappingMut;
-use serde::Deserialize;
+use nondestructive::yaml::{self, MappingMut, Separator};
+use openssl::sha::Sha256;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
 use url::Url;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-use crate::core::{EmptyResult, GenericResult};
+use crate::access;
+use crate::core::{EmptyResult, GenericError, GenericResult};
 use crate::matcher::Matcher;
 use crate::util;
 
+pub const DEFAULT_MAX_RELEASE_PAGES: usize = 10;
+
 #[derive(Deserialize, Validate, PartialEq, Clone)]
 #[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_source"))]
 pub struct ToolSpec {
-    #[validate(length(min = 1))]
-    pub project: String,
+    // Projects to get the release from in order of preference. Empty for tools which are downloaded from plain HTTP URL.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    #[validate(custom(function = "validate_projects"))]
+    pub project: Vec<String>,
+    #[validate(nested)]
+    pub url: Option<UrlSpec>,
+    // Templates the tool inherits its options from. They are merged into the spec on configuration reading, so the
+    // field is kept only to preserve it on configuration editing.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub extends: Vec<String>,
     pub changelog: Option<Url>,
+    #[validate(length(min = 1))]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub strategy: ReleaseStrategy,
+    // Safety limit for release list pagination
+    #[serde(default = "default_max_release_pages")]
+    #[validate(range(min = 1))]
+    pub max_release_pages: usize,
+    #[validate(nested)]
+    pub artifact: Option<ArtifactSpec>,
+    // Machines the tool is installed on. Bulk operations skip the tool on other machines.
+    pub only_on: Option<OnlyOn>,
 
     pub release_matcher: Option<Matcher>,
+    // Variant build (like full, lite, gnu or pgo) which the automatically generated release matchers require to be in the
+    // asset name
+    #[validate(length(min = 1))]
+    pub variant: Option<String>,
     pub binary_matcher: Option<Matcher>,
+    pub binary_excludes: Option<Vec<Matcher>>,
+    // Archive directory (like `tool-*` for archives with versioned top-level directory) which is considered as archive
+    // root: the files outside of it are ignored, and the matchers and tree layout use the paths relative to it.
+    pub subdir: Option<Matcher>,
+    #[serde(default)]
+    pub layout: Layout,
+    #[serde(default)]
+    pub rosetta_fallback: bool,
+    #[validate(custom(function = "validate_sha256"))]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub checksum: ChecksumPolicy,
+
+    #[serde(default)]
+    #[validate(custom(function = "validate_headers"))]
+    pub download_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub forward_auth_on_redirect: bool,
+
+    // Linux file capabilities in setcap format
+    #[validate(length(min = 1))]
+    pub capabilities: Option<String>,
+    // Restore security extended attributes (SELinux context, capabilities, etc.) of the replaced binary
+    #[serde(default)]
+    pub preserve_xattrs: bool,
+
+    // Don't upgrade the tool. The value is the reason which is shown when the tool is skipped.
+    #[validate(length(min = 1))]
+    pub hold: Option<String>,
 
     #[serde(default, deserialize_with = "util::deserialize_optional_path")]
     pub path: Option<PathBuf>,
-    pub post: Option<String>,
+    // Umask and group owner of the files and directories which are created on installation
+    #[validate(custom(function = "validate_umask"))]
+    pub umask: Option<String>,
+    #[validate(length(min = 1))]
+    pub group: Option<String>,
+    // Command which checks the extracted binary (its path is passed in $BINUP_PATH) before it replaces the installed one
+    #[validate(length(min = 1))]
+    pub smoke_test: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_post_hook")]
+    #[validate(nested)]
+    pub post: Option<PostHook>,
 }
 
 impl ToolSpec {
+    // Returns hash of the spec which allows to detect its changes since the tool installation
+    pub fn get_hash(&self) -> GenericResult<String> {
+        let mut document = yaml::from_slice("")?;
+        self.serialize(&mut document.as_mut().make_mapping())?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(document.to_string().as_bytes());
+        Ok(hasher.finish().iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
     pub fn serialize(&self, map: &mut MappingMut) -> EmptyResult {
         map.clear();
-        map.insert_str("project", &self.project);
+        if let [project] = self.project.as_slice() {
+            map.insert_str("project", project);
+        } else if !self.project.is_empty() {
+            let mut projects = map.insert("project", Separator::Auto).make_sequence();
+            for project in &self.project {
+                projects.push_string(project);
+            }
+        }
+        if let Some(ref url) = self.url {
+            let mut map = map.insert("url", Separator::Auto).make_mapping();
+            map.insert_str("template", &url.template);
+            map.insert_str("latest_version_url", url.latest_version_url.as_str());
+            if let Some(ref version_regex) = url.version_regex {
+                map.insert_str("version_regex", version_regex);
+            }
+        }
+
+        if let [template] = self.extends.as_slice() {
+            map.insert_str("extends", template);
+        } else if !self.extends.is_empty() {
+            let mut templates = map.insert("extends", Separator::Auto).make_sequence();
+            for template in &self.extends {
+                templates.push_string(template);
+            }
+        }
 
         if let Some(ref changelog) = self.changelog {
             map.insert_str("changelog", changelog.as_str());
         }
+        if let Some(ref tag) = self.tag {
+            map.insert_str("tag", tag);
+        }
+        if self.strategy != ReleaseStrategy::default() {
+            map.insert_str("strategy", self.strategy.to_string());
+        }
+        if self.max_release_pages != DEFAULT_MAX_RELEASE_PAGES {
+            map.insert_u64("max_release_pages", self.max_release_pages as u64);
+        }
+        if let Some(ref artifact) = self.artifact {
+            let mut map = map.insert("artifact", Separator::Auto).make_mapping();
+            map.insert_str("workflow", &artifact.workflow);
+            map.insert_str("branch", &artifact.branch);
+            map.insert_str("name", &artifact.name);
+        }
+        if let Some(ref only_on) = self.only_on {
+            let mut map = map.insert("only_on", Separator::Auto).make_mapping();
+            for (name, values) in [
+                ("os", only_on.os.iter().map(ToString::to_string).collect::<Vec<_>>()),
+                ("arch", only_on.arch.iter().map(ToString::to_string).collect()),
+                ("hostname", only_on.hostname.iter().map(ToString::to_string).collect()),
+            ] {
+                if !values.is_empty() {
+                    let mut sequence = map.insert(name, Separator::Auto).make_sequence();
+                    for value in values {
+                        sequence.push_string(value);
+                    }
+                }
+            }
+        }
         if let Some(ref release_matcher) = self.release_matcher {
             map.insert_str("release_matcher", release_matcher.to_string());
         }
+        if let Some(ref variant) = self.variant {
+            map.insert_str("variant", variant);
+        }
         if let Some(ref binary_matcher) = self.binary_matcher {
             map.insert_str("binary_matcher", binary_matcher.to_string());
         }
+        if let Some(ref binary_excludes) = self.binary_excludes {
+            let mut excludes = map.insert("binary_excludes", Separator::Auto).make_sequence();
+            for matcher in binary_excludes {
+                excludes.push_string(matcher.to_string());
+            }
+        }
+        if let Some(ref subdir) = self.subdir {
+            map.insert_str("subdir", subdir.to_string());
+        }
+        if self.layout != Layout::default() {
+            map.insert_str("layout", self.layout.to_string());
+        }
+        if self.rosetta_fallback {
+            map.insert_bool("rosetta_fallback", true);
+        }
+        if let Some(ref sha256) = self.sha256 {
+            map.insert_str("sha256", sha256);
+        }
+        if self.checksum != ChecksumPolicy::default() {
+            map.insert_str("checksum", self.checksum.to_string());
+        }
+        if !self.download_headers.is_empty() {
+            let mut headers = map.insert("download_headers", Separator::Auto).make_mapping();
+            for (name, value) in &self.download_headers {
+                headers.insert_str(name, value);
+            }
+        }
+        if self.forward_auth_on_redirect {
+            map.insert_bool("forward_auth_on_redirect", true);
+        }
+        if let Some(ref capabilities) = self.capabilities {
+            map.insert_str("capabilities", capabilities);
+        }
+        if self.preserve_xattrs {
+            map.insert_bool("preserve_xattrs", true);
+        }
+        if let Some(ref hold) = self.hold {
+            map.insert_str("hold", hold);
+        }
         if let Some(ref path) = self.path {
             let path = path.to_str().ok_or_else(|| format!("Invalid path: {path:?}"))?;
             map.insert_str("path", path);
         }
+        if let Some(ref umask) = self.umask {
+            map.insert_str("umask", umask);
+        }
+        if let Some(ref group) = self.group {
+            map.insert_str("group", group);
+        }
+        if let Some(ref smoke_test) = self.smoke_test {
+            map.insert_str("smoke_test", smoke_test);
+        }
         if let Some(ref post) = self.post {
-            map.insert_str("post", post);
+            if post.on == ChangeKind::ALL {
+                map.insert_str("post", &post.command);
+            } else {
+                let mut hook = map.insert("post", Separator::Auto).make_mapping();
+                hook.insert_str("command", &post.command);
+
+                let mut changes = hook.insert("on", Separator::Auto).make_sequence();
+                for change in &post.on {
+                    changes.push_string(change.to_string());
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+// GitHub Actions workflow artifact which is used instead of release assets
+#[derive(Deserialize, Validate, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ArtifactSpec {
+    // Workflow file name or ID
+    #[validate(length(min = 1))]
+    pub workflow: String,
+    #[validate(length(min = 1))]
+    pub branch: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+// Plain HTTP source which is used instead of project releases. The latest version is discovered by fetching
+// latest_version_url, and the asset URL is generated by substituting it into the template.
+#[derive(Deserialize, Validate, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_url_spec"))]
+pub struct UrlSpec {
+    // Asset URL with {version} placeholder
+    pub template: String,
+    pub latest_version_url: Url,
+    // Extracts the version from latest_version_url contents (the first capture group or the whole match). The whole
+    // contents is the version if it's not specified.
+    pub version_regex: Option<String>,
+}
+
+impl UrlSpec {
+    pub fn get_url(&self, version: &str) -> GenericResult<Url> {
+        let url = self.template.replace("{version}", version);
+        Ok(Url::parse(&url).map_err(|e| format!("Invalid asset URL ({url}): {e}"))?)
+    }
+}
+
+// Conditions on the current machine. Each of the specified lists must match.
+#[derive(Deserialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OnlyOn {
+    // Values of std::env::consts::OS: linux, macos, etc.
+    #[serde(default)]
+    pub os: Vec<String>,
+    // Values of std::env::consts::ARCH: x86_64, aarch64, etc.
+    #[serde(default)]
+    pub arch: Vec<String>,
+    #[serde(default)]
+    pub hostname: Vec<Matcher>,
+}
+
+impl OnlyOn {
+    pub fn matches(&self) -> bool {
+        self.matches_environment(env::consts::OS, env::consts::ARCH, util::get_hostname().as_deref())
+    }
+
+    fn matches_environment(&self, os: &str, arch: &str, hostname: Option<&str>) -> bool {
+        (self.os.is_empty() || self.os.iter().any(|value| value == os)) &&
+        (self.arch.is_empty() || self.arch.iter().any(|value| value == arch)) &&
+        (self.hostname.is_empty() || hostname.is_some_and(|hostname| {
+            self.hostname.iter().any(|matcher| matcher.matches(hostname))
+        }))
+    }
+}
+
+impl FromStr for ArtifactSpec {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<ArtifactSpec> {
+        let mut parts = value.split(':');
+
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(workflow), Some(branch), Some(name), None) if !workflow.is_empty() && !branch.is_empty() && !name.is_empty() => {
+                Ok(ArtifactSpec {
+                    workflow: workflow.to_owned(),
+                    branch: branch.to_owned(),
+                    name: name.to_owned(),
+                })
+            },
+            _ => Err!("Invalid artifact specification: {value:?}"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReleaseStrategy {
+    // The release which GitHub marks as latest
+    #[default]
+    GithubLatest,
+
+    // The release with the highest version (prereleases are ignored)
+    HighestVersion,
+}
+
+impl ReleaseStrategy {
+    pub const VALUES: [&'static str; 2] = ["github-latest", "highest-version"];
+}
+
+impl FromStr for ReleaseStrategy {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<ReleaseStrategy> {
+        Ok(match value {
+            "github-latest" => ReleaseStrategy::GithubLatest,
+            "highest-version" => ReleaseStrategy::HighestVersion,
+            _ => return Err!("Invalid release strategy: {value:?}"),
+        })
+    }
+}
+
+impl Display for ReleaseStrategy {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            ReleaseStrategy::GithubLatest => "github-latest",
+            ReleaseStrategy::HighestVersion => "highest-version",
+        })
+    }
+}
+
+// Post-install command which may be restricted to the specific kinds of changes: for example, to restart a service only
+// on actual upgrade
+#[derive(Deserialize, Validate, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_post_hook"))]
+pub struct PostHook {
+    #[validate(length(min = 1))]
+    pub command: String,
+    #[serde(default = "default_post_hook_changes")]
+    pub on: Vec<ChangeKind>,
+}
+
+impl PostHook {
+    pub fn new(command: String) -> PostHook {
+        PostHook {command, on: default_post_hook_changes()}
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Install,
+    Upgrade,
+    Reinstall,
+}
+
+impl ChangeKind {
+    pub const ALL: [ChangeKind; 3] = [ChangeKind::Install, ChangeKind::Upgrade, ChangeKind::Reinstall];
+    pub const VALUES: [&'static str; 3] = ["install", "upgrade", "reinstall"];
+}
+
+impl FromStr for ChangeKind {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<ChangeKind> {
+        Ok(match value {
+            "install" => ChangeKind::Install,
+            "upgrade" => ChangeKind::Upgrade,
+            "reinstall" => ChangeKind::Reinstall,
+            _ => return Err!("Invalid change kind: {value:?}"),
+        })
+    }
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            ChangeKind::Install => "install",
+            ChangeKind::Upgrade => "upgrade",
+            ChangeKind::Reinstall => "reinstall",
+        })
+    }
+}
+
+// Verification of the release asset against the checksum file published beside it (SHA256SUMS, checksums.txt, etc.)
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumPolicy {
+    // Refuse to install the asset if it's not listed in the checksum files
+    Required,
+
+    // Verify the asset if the release has a checksum file which lists it
+    #[default]
+    Auto,
+
+    Off,
+}
+
+impl ChecksumPolicy {
+    pub const VALUES: [&'static str; 3] = ["required", "auto", "off"];
+}
+
+impl FromStr for ChecksumPolicy {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<ChecksumPolicy> {
+        Ok(match value {
+            "required" => ChecksumPolicy::Required,
+            "auto" => ChecksumPolicy::Auto,
+            "off" => ChecksumPolicy::Off,
+            _ => return Err!("Invalid checksum policy: {value:?}"),
+        })
+    }
+}
+
+impl Display for ChecksumPolicy {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            ChecksumPolicy::Required => "required",
+            ChecksumPolicy::Auto => "auto",
+            ChecksumPolicy::Off => "off",
+        })
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    // Only the binary is installed
+    #[default]
+    Binary,
+
+    // The whole archive is extracted to a per-tool directory and the binary is symlinked to the install path
+    Tree,
+}
+
+impl Layout {
+    pub const VALUES: [&'static str; 2] = ["binary", "tree"];
+}
+
+impl FromStr for Layout {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<Layout> {
+        Ok(match value {
+            "binary" => Layout::Binary,
+            "tree" => Layout::Tree,
+            _ => return Err!("Invalid layout: {value:?}"),
+        })
+    }
+}
+
+impl Display for Layout {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            Layout::Binary => "binary",
+            Layout::Tree => "tree",
+        })
+    }
+}
+
+fn default_max_release_pages() -> usize {
+    DEFAULT_MAX_RELEASE_PAGES
+}
+
+fn deserialize_one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where D: Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Values {
+        Single(String),
+        Multiple(Vec<String>),
+    }
+
+    Ok(match Values::deserialize(deserializer)? {
+        Values::Single(value) => vec![value],
+        Values::Multiple(values) => values,
+    })
+}
+
+fn deserialize_post_hook<'de, D>(deserializer: D) -> Result<Option<PostHook>, D::Error>
+    where D: Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Hook {
+        Command(String),
+        Hook(PostHook),
+    }
+
+    Ok(Option::<Hook>::deserialize(deserializer)?.map(|hook| match hook {
+        Hook::Command(command) => PostHook::new(command),
+        Hook::Hook(hook) => hook,
+    }))
+}
+
+fn default_post_hook_changes() -> Vec<ChangeKind> {
+    ChangeKind::ALL.to_vec()
+}
+
+fn validate_post_hook(hook: &PostHook) -> Result<(), ValidationError> {
+    if hook.on.is_empty() {
+        return Err(ValidationError::new("on").with_message("At least one kind of change must be specified".into()));
+    }
+    Ok(())
+}
+
+fn validate_source(spec: &ToolSpec) -> Result<(), ValidationError> {
+    match (spec.project.is_empty(), spec.url.is_some()) {
+        (true, false) => Err(ValidationError::new("project").with_message("Project must be specified".into())),
+        (false, true) => Err(ValidationError::new("url").with_message("Project and URL are mutually exclusive".into())),
+        (true, true) if spec.artifact.is_some() => Err(ValidationError::new("artifact").with_message(
+            "Workflow artifacts can't be used with URL".into())),
+        _ => Ok(()),
+    }
+}
+
+fn validate_url_spec(spec: &UrlSpec) -> Result<(), ValidationError> {
+    if let Err(err) = spec.get_url("0.0.0") {
+        return Err(ValidationError::new("template").with_message(err.to_string().into()));
+    }
+
+    if let Some(version_regex) = spec.version_regex.as_ref() {
+        match Regex::new(version_regex) {
+            Ok(regex) if regex.captures_len() > 2 => return Err(ValidationError::new("version_regex").with_message(
+                "Version regex must have at most one capture group".into())),
+            Ok(_) => {},
+            Err(err) => return Err(ValidationError::new("version_regex").with_message(
+                format!("Invalid version regex: {err}").into())),
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_projects(projects: &[String]) -> Result<(), ValidationError> {
+    if projects.iter().any(String::is_empty) {
+        return Err(ValidationError::new("project").with_message("Project name must not be empty".into()));
+    }
+    Ok(())
+}
+
+fn validate_sha256(checksum: &str) -> Result<(), ValidationError> {
+    if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ValidationError::new("sha256").with_message("Invalid SHA-256 checksum".into()));
+    }
+    Ok(())
+}
+
+fn validate_umask(umask: &str) -> Result<(), ValidationError> {
+    access::parse_umask(umask).map_err(|e| ValidationError::new("umask").with_message(e.to_string().into()))?;
+    Ok(())
+}
+
+fn validate_headers(headers: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    for (name, value) in headers {
+        if HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(ValidationError::new("header").with_message(format!("Invalid header name: {name:?}").into()));
+        }
+        if HeaderValue::from_str(value).is_err() {
+            return Err(ValidationError::new("header").with_message(format!("Invalid {name} header value").into()));
+        }
+    }
+    Ok(())
+}
+
 pub struct ToolState {
     pub modify_time: SystemTime,
+    // Binary access time which is updated on each run (unless the filesystem is mounted with noatime)
+    pub access_time: SystemTime,
 }
 
 pub fn check(path: &Path) -> GenericResult<Option<ToolState>> {
     debug!("Checking {path:?}...");
 
-    let modify_time = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
-        Ok(metadata) => metadata,
+    let (modify_time, access_time) = match fs::metadata(path).and_then(|metadata| {
+        Ok((metadata.modified()?, metadata.accessed()?))
+    }) {
+        Ok(times) => times,
         Err(err) if err.kind() == ErrorKind::NotFound => {
             return Ok(None);
         },
@@ -71,5 +628,39 @@ pub fn check(path: &Path) -> GenericResult<Option<ToolState>> {
         },
     };
 
-    Ok(Some(ToolState {modify_time}))
-}
\ No newline at end of file
+    Ok(Some(ToolState {modify_time, access_time}))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(os, arch, hostname, expected,
+        case("linux", "x86_64", Some("web-1"), true),
+        case("linux", "aarch64", Some("web-1"), true),
+        case("macos", "x86_64", Some("web-1"), false),
+        case("linux", "x86_64", Some("db-1"), false),
+        case("linux", "x86_64", None, false),
+    )]
+    fn only_on(os: &str, arch: &str, hostname: Option<&str>, expected: bool) {
+        let condition = OnlyOn {
+            os: vec!["linux".to_owned()],
+            arch: Vec::new(),
+            hostname: vec![Matcher::new("web-*").unwrap()],
+        };
+        assert_eq!(condition.matches_environment(os, arch, hostname), expected);
+    }
+
+    #[rstest(post, command, on,
+        case("post: systemctl restart app", "systemctl restart app", &ChangeKind::ALL),
+        case("post: {command: systemctl restart app}", "systemctl restart app", &ChangeKind::ALL),
+        case("post: {command: systemctl restart app, on: [upgrade]}", "systemctl restart app", &[ChangeKind::Upgrade]),
+    )]
+    fn post_hook(post: &str, command: &str, on: &[ChangeKind]) {
+        let spec: ToolSpec = serde_yaml::from_str(&format!("{{project: owner/app, {post}}}")).unwrap();
+        let hook = spec.post.unwrap();
+        assert_eq!(hook.command, command);
+        assert!(hook.on == on);
+    }
+}
@@ -8,6 +8,10 @@ use log::{info, error};
 
 use crate::config::Config;
 use crate::core::GenericResult;
+use crate::i18n;
+use crate::install;
+use crate::shims;
+use crate::state::State;
 use crate::util;
 
 pub fn uninstall(config: &mut Config, names: Vec<String>) -> GenericResult<ExitCode> {
@@ -16,28 +20,50 @@ pub fn uninstall(config: &mut Config, names: Vec<String>) -> GenericResult<ExitC
 
     for name in &names {
         match config.tools.get(name) {
-            Some(spec) => tools.push((name, config.get_tool_path(name, spec))),
+            Some(spec) => tools.push((
+                name, config.get_command_path(name, spec), config.get_tool_tree_path(name, spec),
+                config.uses_shim(spec).then(|| config.get_tool_versions_path(name)),
+            )),
             None => invalid.push(name),
         }
     }
 
     if !invalid.is_empty() {
         return Err!("The following tools aren't specified in the configuration file: {}", invalid.iter().join(", "));
-    } else if !util::confirm("Are you sure want to uninstall the specified tools?") {
+    } else if !util::confirm(i18n::tr("Are you sure want to uninstall the specified tools?"))? {
         return Ok(ExitCode::FAILURE);
     }
 
+    let mut state = State::load(config)?;
     let mut exit_code = ExitCode::SUCCESS;
 
-    for (name, path) in tools {
+    for (name, path, tree_path, versions_path) in tools {
         match config.edit(
             |config, raw| config.remove_tool(raw, name),
-            |_| uninstall_tool(&path),
+            |_| match versions_path.as_ref() {
+                Some(versions_path) => shims::uninstall(&path, versions_path),
+                None => uninstall_tool(&path, tree_path.as_deref()),
+            },
         ) {
-            Ok(deleted) => if deleted {
-                info!("{name} ({}) is uninstalled.", path.display());
-            } else {
-                info!("{name} is uninstalled.");
+            Ok(deleted) => {
+                if deleted {
+                    info!("{name} ({}) is uninstalled.", path.display());
+                } else {
+                    info!("{name} is uninstalled.");
+                }
+
+                if let Err(err) = install::remove_tree(&config.cache_path.join(name)) {
+                    error!("{err}.");
+                    exit_code = ExitCode::FAILURE;
+                }
+
+                let installed = state.tools.remove(name.as_str()).is_some();
+                if state.runs.remove(name.as_str()).is_some() || installed {
+                    if let Err(err) = state.save() {
+                        error!("{err}.");
+                        exit_code = ExitCode::FAILURE;
+                    }
+                }
             },
             Err(err) => {
                 error!("Failed to uninstall {name}: {err}.");
@@ -49,10 +75,16 @@ pub fn uninstall(config: &mut Config, names: Vec<String>) -> GenericResult<ExitC
     Ok(exit_code)
 }
 
-fn uninstall_tool(path: &Path) -> GenericResult<bool> {
-    Ok(match fs::remove_file(path) {
+fn uninstall_tool(path: &Path, tree_path: Option<&Path>) -> GenericResult<bool> {
+    let deleted = match fs::remove_file(path) {
         Ok(()) => true,
         Err(err) if err.kind() == ErrorKind::NotFound => false,
         Err(err) => return Err!("Unable to delete {path:?}: {err}"),
-    })
+    };
+
+    if let Some(tree_path) = tree_path {
+        install::remove_tree(tree_path)?;
+    }
+
+    Ok(deleted)
 }
\ No newline at end of file
@@ -1,13 +1,24 @@
+use std::env;
 use std::fmt::Display;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use const_format::formatcp;
+use is_terminal::IsTerminal;
 use itertools::Itertools;
-use log::debug;
+use log::{debug, error, info, warn};
 use serde::Deserialize;
 use serde::de::{Deserializer, Error};
 
+use crate::core::{EmptyResult, GenericError, GenericResult};
+use crate::i18n;
+
 pub static USER_AGENT: &str = formatcp!(
     "{name}/{version} ({homepage})",
     name=env!("CARGO_PKG_NAME"), version=env!("CARGO_PKG_VERSION"), homepage=env!("CARGO_PKG_REPOSITORY"),
@@ -17,6 +28,45 @@ pub fn format_list<T: Display, I: Iterator<Item = T>>(mut iter: I) -> String {
     "\n* ".to_owned() + &iter.join("\n* ")
 }
 
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1024 {
+        return format!("{size} B");
+    }
+
+    let mut size = size as f64;
+    let mut unit = "B";
+
+    for next_unit in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next_unit;
+    }
+
+    format!("{size:.1} {unit}")
+}
+
+// Parses durations like 90m, 12h, 30d or 2w
+pub fn parse_duration(value: &str) -> GenericResult<Duration> {
+    let unit_start = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err!("Invalid duration: {value:?}"),
+    };
+
+    number.parse::<u64>().ok().and_then(|number| number.checked_mul(multiplier)).map(Duration::from_secs)
+        .ok_or_else(|| format!("Invalid duration: {value:?}").into())
+}
+
 pub fn format_multiline(text: &str) -> String {
     let text = text.trim_end();
 
@@ -27,12 +77,64 @@ pub fn format_multiline(text: &str) -> String {
     }
 }
 
-pub fn confirm<S: Display>(message: S) -> bool {
+// Answer to confirmation prompts when we run in non-interactive environment
+#[derive(Clone, Copy, Default)]
+pub enum PromptDefault {
+    #[default]
+    Fail,
+    Yes,
+    No,
+}
+
+impl PromptDefault {
+    pub const VALUES: [&'static str; 3] = ["fail", "yes", "no"];
+}
+
+impl FromStr for PromptDefault {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> GenericResult<PromptDefault> {
+        Ok(match value {
+            "fail" => PromptDefault::Fail,
+            "yes" => PromptDefault::Yes,
+            "no" => PromptDefault::No,
+            _ => return Err!("Invalid prompt default: {value:?}"),
+        })
+    }
+}
+
+static PROMPT_DEFAULT: OnceLock<PromptDefault> = OnceLock::new();
+
+pub fn set_prompt_default(default: PromptDefault) {
+    let _ = PROMPT_DEFAULT.set(default);
+}
+
+pub fn is_interactive() -> bool {
+    let ci = env::var("CI").is_ok_and(|value| !matches!(value.to_lowercase().as_str(), "" | "0" | "false"));
+    !ci && io::stdin().is_terminal()
+}
+
+pub fn confirm<S: Display>(message: S) -> GenericResult<bool> {
+    if !is_interactive() {
+        return match PROMPT_DEFAULT.get().copied().unwrap_or_default() {
+            PromptDefault::Fail => Err!(
+                "{message} Unable to ask for confirmation in non-interactive mode (see --prompt-default option)"),
+            PromptDefault::Yes => {
+                info!("{}", i18n::trf("{} Assuming yes (non-interactive mode).", &[&message]));
+                Ok(true)
+            },
+            PromptDefault::No => {
+                info!("{}", i18n::trf("{} Assuming no (non-interactive mode).", &[&message]));
+                Ok(false)
+            },
+        };
+    }
+
     loop {
-        if let Err(err) = write!(io::stderr(), "{} (y/n): ", message)
+        if let Err(err) = write!(io::stderr(), "{} {}: ", message, i18n::tr("(y/n)"))
             .and_then(|_| io::stderr().flush()) {
             debug!("Failed to question the user: {err}. Assume no.");
-            return false;
+            return Ok(false);
         }
 
         let mut answer = String::new();
@@ -41,21 +143,67 @@ pub fn confirm<S: Display>(message: S) -> bool {
             Ok(size) => if size == 0 {
                 let _ = writeln!(io::stderr());
                 debug!("Failed to question the user: stdin is closed. Assume no.");
-                return false;
+                return Ok(false);
             },
             Err(err) => {
                 let _ = writeln!(io::stderr());
                 debug!("Failed to question the user: {err}. Assume no.");
-                return false;
+                return Ok(false);
             }
         }
 
-        match answer.trim() {
-            "y" | "yes" => return true,
-            "n" | "no" => return false,
-            _ => {},
+        if let Some(answer) = i18n::parse_answer(&answer) {
+            return Ok(answer);
+        }
+    }
+}
+
+pub fn write_file(path: &Path, data: &[u8]) -> EmptyResult {
+    write_file_with_mode(path, data, None)
+}
+
+// Writes the file atomically. If mode isn't specified, the mode of the existing file is preserved.
+pub fn write_file_with_mode(path: &Path, data: &[u8], mode: Option<u32>) -> EmptyResult {
+    let temp_path = {
+        let mut path = path.as_os_str().to_owned();
+        path.push(".new");
+        PathBuf::from(path)
+    };
+
+    if let Err(err) = fs::remove_file(&temp_path) {
+        if err.kind() != ErrorKind::NotFound {
+            return Err!("Unable to delete {temp_path:?}: {err}");
+        }
+    }
+
+    let mut open_options = OpenOptions::new();
+    open_options.create_new(true).write(true);
+
+    if let Some(mode) = mode {
+        open_options.mode(mode);
+    } else {
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                open_options.mode(metadata.mode());
+            },
+            Err(err) => if err.kind() != ErrorKind::NotFound {
+                return Err!("Unable to stat() {path:?}: {err}");
+            }
         }
     }
+
+    open_options.open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data).inspect_err(|_| {
+                if let Err(err) = fs::remove_file(&temp_path) {
+                    error!("Failed to delete {temp_path:?}: {err}.");
+                }
+            })
+        })
+        .and_then(|_| fs::rename(&temp_path, path))
+        .map_err(|e| format!("Failed to write {path:?}: {e}"))?;
+
+    Ok(())
 }
 
 pub fn deserialize_path<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
@@ -80,4 +228,65 @@ fn parse_path<'de, D>(path: &str) -> Result<PathBuf, D::Error>
         return Err(D::Error::custom("The path must be absolute"));
     }
     Ok(path)
-}
\ No newline at end of file
+}
+
+pub fn get_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+
+    let len = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r#"'\''"#))
+}
+
+// Temporary directory which is deleted with all its contents on drop
+pub struct TempDir {
+    pub path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> GenericResult<TempDir> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "binup-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+        fs::create_dir(&path).map_err(|e| format!("Unable to create {path:?}: {e}"))?;
+
+        Ok(TempDir {path})
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            warn!("Failed to delete {:?}: {err}.", self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("90s", Some(90)),
+        case("15m", Some(15 * 60)),
+        case("12h", Some(12 * 60 * 60)),
+        case("30d", Some(30 * 24 * 60 * 60)),
+        case("2w", Some(2 * 7 * 24 * 60 * 60)),
+        case("30", None),
+        case("d", None),
+        case("1.5d", None),
+        case("30y", None),
+    )]
+    fn duration(value: &str, expected: Option<u64>) {
+        assert_eq!(parse_duration(value).ok(), expected.map(Duration::from_secs));
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use itertools::Itertools;
+use log::{debug, error, info, warn};
+use openssl::sha::Sha256;
+use url::Url;
+
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::download::{self, DownloadOptions, ExtractOptions, Installer};
+use crate::netrc::Netrc;
+use crate::provider::Providers;
+use crate::state::{InstallInfo, State};
+use crate::tool::ToolSpec;
+
+// Compares installed binaries with the ones from their release assets without changing anything, so it may be run from
+// a security cron job to detect tampering of the installed tools. In paranoid mode the release assets are downloaded
+// from upstream instead of trusting the local cache.
+pub fn verify(config: &Config, names: Vec<String>, paranoid: bool) -> GenericResult<ExitCode> {
+    let invalid: Vec<_> = names.iter().filter(|name| !config.tools.contains_key(name.as_str())).collect();
+    if !invalid.is_empty() {
+        return Err!("The following tools aren't specified in the configuration file: {}", invalid.iter().join(", "));
+    }
+
+    let explicit = !names.is_empty();
+    let tools: Vec<_> = config.tools.iter()
+        .filter(|(name, _)| !explicit || names.contains(name))
+        .collect();
+
+    let state = State::load(config)?;
+    let upstream = if paranoid {
+        Some(Upstream {
+            providers: Providers::new(&config.github, &config.bitbucket, &config.s3, &config.tls, &config.network)?,
+            netrc: config.netrc.then(Netrc::load).transpose()?,
+        })
+    } else {
+        None
+    };
+
+    let mut exit_code = ExitCode::SUCCESS;
+
+    for (name, spec) in tools {
+        let Some(info) = state.tools.get(name) else {
+            let path = config.get_tool_path(name, spec);
+
+            if explicit {
+                error!("{name} isn't installed by binup, so it can't be verified.");
+                exit_code = ExitCode::FAILURE;
+            } else if crate::tool::check(&path)?.is_some() {
+                warn!("{name} ({}) isn't installed by binup, so it can't be verified.", path.display());
+            } else {
+                debug!("{name} isn't installed. Skipping it.");
+            }
+
+            continue;
+        };
+
+        match verify_tool(config, name, spec, info, upstream.as_ref()) {
+            Ok(()) => info!("{name} is OK."),
+            Err(err) => {
+                error!("{name} verification failed: {err}.");
+                exit_code = ExitCode::FAILURE;
+            },
+        }
+    }
+
+    Ok(exit_code)
+}
+
+struct Upstream {
+    providers: Providers,
+    netrc: Option<Netrc>,
+}
+
+fn verify_tool(config: &Config, name: &str, spec: &ToolSpec, info: &InstallInfo, upstream: Option<&Upstream>) -> EmptyResult {
+    let path = config.get_tool_path(name, spec);
+    if crate::tool::check(&path)?.is_none() {
+        return Err!("{path:?} is missing");
+    }
+
+    let options = ExtractOptions {
+        decompressors: &config.decompressors,
+        limits: &config.extraction_limits,
+    };
+
+    let mut extractor = BinaryExtractor {
+        binary: info.binary.clone(),
+        checksum: None,
+    };
+
+    let asset_checksum = if let Some(upstream) = upstream {
+        let url = get_asset_url(name, spec, info, &upstream.providers)?;
+        debug!("Downloading {name} release asset from {url}...");
+
+        // The asset is downloaded without caching to not modify anything during verification
+        let (checksum, _) = download::download(
+            &url, &info.asset, &DownloadOptions {
+                headers: &upstream.providers.get_download_headers(spec),
+                forward_auth_on_redirect: spec.forward_auth_on_redirect,
+                netrc: upstream.netrc.as_ref(),
+                tls: &config.tls,
+                network: &config.network,
+            }, &options, None, &mut extractor,
+        ).map_err(|e| format!("Failed to download {url}: {e}"))?;
+
+        checksum
+    } else {
+        let cache_path = config.get_tool_cache_path(name, &info.asset);
+        if !cache_path.exists() {
+            return Err!("{} release asset is not cached. Use --paranoid to verify it against upstream", info.asset);
+        } else if info.sha256.is_none() {
+            return Err!("The state has no checksum of the cached release asset. Use --paranoid to verify it against upstream");
+        }
+
+        download::extract_cached(&cache_path, &info.asset, &options, &mut extractor).map_err(|e| format!(
+            "Failed to extract {cache_path:?}: {e}"))?
+    };
+
+    if let Some(expected) = info.sha256.as_ref() {
+        if asset_checksum != *expected {
+            return Err!(
+                "{} checksum mismatch: expected {expected} (recorded on installation), got {asset_checksum}",
+                info.asset);
+        }
+    }
+
+    let expected = extractor.checksum.ok_or_else(|| format!(
+        "{} release asset has no {:?}", info.asset, info.binary))?;
+    let checksum = download::get_checksum(&path)?;
+    debug!("{path:?} SHA-256: {checksum}.");
+
+    if checksum != expected {
+        return Err!("{path:?} doesn't match {:?} from {} release asset", info.binary, info.asset);
+    }
+
+    Ok(())
+}
+
+// Binaries installed by older binup versions have no recorded asset URL, so try to find it in the latest release.
+// Expiring (presigned) asset URLs are always looked up.
+fn get_asset_url(name: &str, spec: &ToolSpec, info: &InstallInfo, providers: &Providers) -> GenericResult<Url> {
+    if let Some(url) = info.url.as_ref().filter(|_| !providers.has_expiring_urls(spec)) {
+        return Ok(Url::parse(url).map_err(|e| format!("Invalid recorded asset URL ({url}): {e}"))?);
+    }
+
+    let (_, asset) = providers.get_tool_release(name, spec)?;
+    match asset {
+        Ok(asset) if asset.name == info.asset && Some(asset.time) == info.time => Ok(asset.url),
+        _ => Err!("The state has no URL of the installed release asset. Reinstall the tool to record it"),
+    }
+}
+
+// Calculates checksum of the installed binary in the release asset
+struct BinaryExtractor {
+    binary: PathBuf,
+    checksum: Option<String>,
+}
+
+impl Installer for BinaryExtractor {
+    fn on_file(&mut self, path: &Path, _mode: u32, data: &mut dyn Read) -> EmptyResult {
+        if path != self.binary {
+            return Ok(());
+        }
+
+        let mut hasher = Sha256::new();
+        let mut buffer = [0; 64 * 1024];
+
+        loop {
+            let size = match data.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(size) => size,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            hasher.update(&buffer[..size]);
+        }
+
+        self.checksum.replace(hasher.finish().iter().map(|byte| format!("{byte:02x}")).collect());
+        Ok(())
+    }
+}
@@ -1,9 +1,13 @@
 use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use log::debug;
-use semver::Version;
+use regex::Regex;
+use semver::{BuildMetadata, Prerelease, Version};
 
 use crate::util;
 
@@ -20,7 +24,7 @@ impl ReleaseVersion {
         }
 
         match Version::parse(version) {
-            Ok(version) => ReleaseVersion::Version(version),
+            Ok(version) => ReleaseVersion::Version(normalize(version)),
             Err(_) => ReleaseVersion::Tag(tag.to_owned()),
         }
     }
@@ -35,17 +39,37 @@ impl Display for ReleaseVersion {
     }
 }
 
+// Returns version and the string it's specified by in the file name (like tool-v1.2.3-linux-amd64.tar.gz)
+pub fn get_file_version(name: &str) -> Option<(Version, String)> {
+    let regex = Regex::new(r"(?:^|[-_.])(v?\d+\.\d+\.\d+)(?:[-_.]|$)").unwrap();
+    let tag = regex.captures(name)?.get(1)?.as_str();
+
+    match ReleaseVersion::new(tag) {
+        ReleaseVersion::Version(version) if version.pre.is_empty() => Some((version, tag.to_owned())),
+        _ => None,
+    }
+}
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub fn get_binary_version(path: &Path) -> Option<Version> {
     let mut command = Command::new(path);
     command.arg("--version");
 
     debug!("Trying to determine {path:?} version by spawning `{command:?}`...");
 
-    match command.output() {
+    match run_with_timeout(&mut command, VERSION_TIMEOUT) {
         Ok(result) => if result.status.success() {
-            debug!("Got the following output:{}", util::format_multiline(&String::from_utf8_lossy(&result.stdout)));
+            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stderr = String::from_utf8_lossy(&result.stderr);
 
-            match String::from_utf8(result.stdout).ok().and_then(|stdout| parse_binary_version(&stdout)) {
+            debug!("Got the following output:{}", util::format_multiline(&stdout));
+            if !stderr.trim().is_empty() {
+                debug!("Got the following stderr output:{}", util::format_multiline(&stderr));
+            }
+
+            // Some tools print their version to stderr
+            match parse_binary_version(&stdout).or_else(|| parse_binary_version(&stderr)) {
                 Some(version) => {
                     debug!("Got the following version: {}.", version);
                     Some(version)
@@ -62,18 +86,82 @@ pub fn get_binary_version(path: &Path) -> Option<Version> {
             None
         },
         Err(err) => {
-            debug!("Failed to spawn `{command:?}`: {err}.");
+            debug!("Failed to run `{command:?}`: {err}.");
             None
         },
     }
 }
 
-fn parse_binary_version(stdout: &str) -> Option<Version> {
-    for word in stdout.split('\n').next().unwrap().split(' ') {
+// Some tools might not support --version and start their normal work instead, so don't wait for them forever
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout_reader = thread::spawn(move || {
+        let mut data = Vec::new();
+        stdout.read_to_end(&mut data).map(|_| data)
+    });
+
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr_reader = thread::spawn(move || {
+        let mut data = Vec::new();
+        stderr.read_to_end(&mut data).map(|_| data)
+    });
+
+    let deadline = Instant::now() + timeout;
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::TimedOut, format!(
+                "the process hasn't finished in {} seconds", timeout.as_secs())));
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap()?,
+        stderr: stderr_reader.join().unwrap()?,
+    })
+}
+
+// Binaries often report versions like 1.2.3+build.5 or 1.2.3-5-gabcdef (git describe output) for release tag
+// v1.2.3, so strip build metadata and commit suffixes to make such versions comparable with release versions.
+fn normalize(mut version: Version) -> Version {
+    version.build = BuildMetadata::EMPTY;
+
+    let mut identifiers = version.pre.split('.').flat_map(|identifier| identifier.split('-'));
+    let is_commit = |identifier: &str| {
+        identifier.len() >= 7 && identifier.strip_prefix('g').is_some_and(|hash| {
+            hash.chars().all(|c| c.is_ascii_hexdigit())
+        })
+    };
+
+    if !version.pre.is_empty() && identifiers.clone().any(is_commit) && identifiers.all(|identifier| {
+        is_commit(identifier) || identifier.chars().all(|c| c.is_ascii_digit())
+    }) {
+        version.pre = Prerelease::EMPTY;
+    }
+
+    version
+}
+
+// Scans the output lines in order, so version from the first line is preferred over versions of dependencies, which
+// are often printed below it
+fn parse_binary_version(output: &str) -> Option<Version> {
+    for word in output.split_whitespace() {
         for token in word.split('-') {
-            let token = token.strip_prefix('v').unwrap_or(token);
+            let token = token.trim_matches(|c| matches!(c, ',' | '.' | ';' | ':' | '(' | ')' | '[' | ']' | '"' | '\''));
+            let token = token.strip_prefix(['v', 'V']).unwrap_or(token);
             if let Ok(version) = Version::parse(token) {
-                return Some(version);
+                return Some(normalize(version));
             }
         }
     }
@@ -106,7 +194,26 @@ mod tests {
               go version:       go1.22.2
               platform:         linux/amd64
               tags:             netgo,builtinassets,stringlabels
-        "#), "2.51.2")
+        "#), "2.51.2"),
+
+        case(indoc!(r#"
+            Some Tool
+            Copyright (c) 2024 Some Author
+
+            Version: 1.4.2
+        "#), "1.4.2"),
+
+        case(indoc!(r#"
+            tool (version 0.9.1, built with rustc 1.78.0)
+        "#), "0.9.1"),
+
+        case(indoc!(r#"
+            Versión V2.0.1.
+        "#), "2.0.1"),
+
+        case(indoc!("
+            tool\t3.1.0
+        "), "3.1.0")
     )]
     fn parse(stdout: &str, version: &str) {
         let version = Version::parse(version).unwrap();
@@ -117,4 +224,28 @@ mod tests {
         assert_eq!(parse_binary_version(stdout), Some(version.clone()));
         assert_eq!(parse_binary_version(stdout.trim_end()), Some(version));
     }
-}
\ No newline at end of file
+
+    #[rstest(version, expected,
+        case("1.2.3", "1.2.3"),
+        case("1.2.3+build.5", "1.2.3"),
+        case("1.2.3-gabcdef1", "1.2.3"),
+        case("1.2.3-5-gabcdef1", "1.2.3"),
+        case("1.2.3-rc.1", "1.2.3-rc.1"),
+        case("1.2.3-rc.1+build.5", "1.2.3-rc.1"),
+        case("1.2.3-gamma", "1.2.3-gamma"),
+    )]
+    fn normalization(version: &str, expected: &str) {
+        assert_eq!(normalize(Version::parse(version).unwrap()), Version::parse(expected).unwrap());
+    }
+
+    #[rstest(name, version,
+        case("tool-1.2.3-linux-amd64.tar.gz", Some("1.2.3")),
+        case("tool_v1.2.3_darwin_arm64.tar.gz", Some("v1.2.3")),
+        case("tool-linux-amd64-1.10.0.tar.gz", Some("1.10.0")),
+        case("tool2-linux-amd64.tar.gz", None),
+        case("tool-1.2-linux-amd64.tar.gz", None),
+    )]
+    fn file_version(name: &str, version: Option<&str>) {
+        assert_eq!(get_file_version(name).map(|(_, tag)| tag).as_deref(), version);
+    }
+}
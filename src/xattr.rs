@@ -0,0 +1,91 @@
+use std::ffi::CString;
+#[cfg(target_os = "linux")] use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use crate::core::{EmptyResult, GenericResult};
+
+pub type Xattrs = Vec<(CString, Vec<u8>)>;
+
+// Returns security.* extended attributes of the file (SELinux context, file capabilities, IMA/EVM signatures)
+#[cfg(target_os = "linux")]
+pub fn get_security_xattrs(path: &Path) -> GenericResult<Xattrs> {
+    let c_path = get_c_path(path)?;
+
+    let names = read_buffer(|buf, size| unsafe {
+        libc::listxattr(c_path.as_ptr(), buf as *mut libc::c_char, size)
+    }).map_err(|e| format!("Unable to list {path:?} extended attributes: {e}"))?;
+
+    let mut xattrs = Vec::new();
+
+    for name in names.split(|&byte| byte == 0).filter(|name| name.starts_with(b"security.")) {
+        let name = CString::new(name).unwrap();
+
+        let value = read_buffer(|buf, size| unsafe {
+            libc::getxattr(c_path.as_ptr(), name.as_ptr(), buf as *mut libc::c_void, size)
+        }).map_err(|e| format!("Unable to read {path:?} {name:?} extended attribute: {e}"))?;
+
+        xattrs.push((name, value));
+    }
+
+    Ok(xattrs)
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_xattrs(path: &Path, xattrs: &Xattrs) -> EmptyResult {
+    let c_path = get_c_path(path)?;
+
+    for (name, value) in xattrs {
+        if unsafe {
+            libc::setxattr(c_path.as_ptr(), name.as_ptr(), value.as_ptr() as *const libc::c_void, value.len(), 0)
+        } != 0 {
+            return Err!("Unable to set {path:?} {name:?} extended attribute: {}", io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_security_xattrs(_path: &Path) -> GenericResult<Xattrs> {
+    Err!("Extended attributes preservation is supported only on Linux")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_xattrs(_path: &Path, _xattrs: &Xattrs) -> EmptyResult {
+    Err!("Extended attributes preservation is supported only on Linux")
+}
+
+fn get_c_path(path: &Path) -> GenericResult<CString> {
+    Ok(CString::new(path.as_os_str().as_bytes()).map_err(|_| format!("Invalid path: {path:?}"))?)
+}
+
+// Calls the function first to get the required buffer size and then to fill the buffer
+#[cfg(target_os = "linux")]
+fn read_buffer<F>(read: F) -> io::Result<Vec<u8>>
+    where F: Fn(*mut u8, usize) -> isize
+{
+    loop {
+        let size = read(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut buf = vec![0; size as usize];
+        if buf.is_empty() {
+            return Ok(buf);
+        }
+
+        let size = read(buf.as_mut_ptr(), buf.len());
+        if size >= 0 {
+            buf.truncate(size as usize);
+            return Ok(buf);
+        }
+
+        // The attributes might be changed between the calls
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ERANGE) {
+            return Err(err);
+        }
+    }
+}